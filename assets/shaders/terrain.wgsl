@@ -0,0 +1,100 @@
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::mesh_bindings
+#import bevy_pbr::mesh_functions
+#import bevy_pbr::pbr_types
+#import bevy_pbr::utils
+#import bevy_pbr::clustered_forward
+#import bevy_pbr::lighting
+#import bevy_pbr::shadows
+#import bevy_pbr::pbr_functions
+
+struct TerrainMaterial {
+    texture_size: f32,
+};
+
+@group(1) @binding(0)
+var textures: texture_2d_array<f32>;
+@group(1) @binding(1)
+var textures_sampler: sampler;
+@group(1) @binding(2)
+var<uniform> terrain: TerrainMaterial;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) weights: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) weights: vec4<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    out.world_position = mesh_position_local_to_world(mesh.model, vec4<f32>(vertex.position, 1.0));
+    out.clip_position = mesh_position_world_to_clip(out.world_position);
+    out.world_normal = mesh_normal_local_to_world(vertex.normal);
+    out.color = vertex.color;
+    out.weights = vertex.weights;
+    return out;
+}
+
+struct FragmentInput {
+    @builtin(front_facing) is_front: bool,
+    @builtin(position) frag_coord: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) weights: vec4<f32>,
+};
+
+// project the layer along each axis and blend projections by the normal
+fn triplanar(layer: i32, position: vec3<f32>, blend: vec3<f32>) -> vec3<f32> {
+    let x = textureSample(textures, textures_sampler, position.zy, layer).rgb;
+    let y = textureSample(textures, textures_sampler, position.xz, layer).rgb;
+    let z = textureSample(textures, textures_sampler, position.xy, layer).rgb;
+    return x * blend.x + y * blend.y + z * blend.z;
+}
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    let normal = normalize(in.world_normal);
+
+    var blend = pow(abs(normal), vec3<f32>(4.0));
+    blend = blend / (blend.x + blend.y + blend.z);
+
+    let position = in.world_position.xyz / terrain.texture_size;
+    let w = in.weights;
+    let total = w.x + w.y + w.z + w.w;
+
+    let textured = triplanar(0, position, blend) * w.x
+        + triplanar(1, position, blend) * w.y
+        + triplanar(2, position, blend) * w.z
+        + triplanar(3, position, blend) * w.w;
+
+    let color = textured + in.color.rgb * (1.0 - clamp(total, 0.0, 1.0));
+
+    var pbr_input = pbr_input_new();
+    pbr_input.material.base_color = vec4<f32>(color, 1.0);
+    pbr_input.material.perceptual_roughness = 1.0;
+    pbr_input.material.metallic = 0.0;
+    pbr_input.material.reflectance = 0.0;
+    pbr_input.frag_coord = in.frag_coord;
+    pbr_input.world_position = in.world_position;
+    pbr_input.world_normal = normal;
+    pbr_input.is_orthographic = view.projection[3].w == 1.0;
+    pbr_input.N = normal;
+    pbr_input.V = calculate_view(in.world_position, pbr_input.is_orthographic);
+
+    var output_color = pbr(pbr_input);
+#ifdef TONEMAP_IN_SHADER
+    output_color = tone_mapping(output_color);
+#endif
+    return output_color;
+}
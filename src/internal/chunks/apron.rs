@@ -0,0 +1,76 @@
+use super::Chunk;
+use crate::internal::{direction::Direction, pos::GlobalVoxelPos};
+
+const FACE_AREA: usize = Chunk::SIZE_VOXELS * Chunk::SIZE_VOXELS;
+
+/// Values of the voxels right behind the faces of the chunk, taken from the loaded neighbor
+/// chunks of the same detail level.
+///
+/// Normals at the chunk border are computed from them, so both chunks get the same normals
+/// there. Faces without such a neighbor have no values, and normals use only the chunk voxels.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChunkApron {
+    faces: [Option<Vec<f32>>; Direction::COUNT],
+}
+
+impl ChunkApron {
+    /// Take values of the `dir` face from the `neighbor` chunk behind it, `None` clears the face
+    pub fn set_face(&mut self, dir: Direction, neighbor: Option<&Chunk>) {
+        let offset = GlobalVoxelPos::from(dir) * Chunk::SIZE_I64;
+
+        self.faces[dir as usize] = neighbor.map(|neighbor| {
+            (0..FACE_AREA)
+                .map(|i| {
+                    let pos = Self::face_voxel_pos(dir, i) - offset;
+                    neighbor.get_voxel(pos).unwrap_or_default().value()
+                })
+                .collect()
+        });
+    }
+
+    pub fn is_face_changed(&self, other: &ChunkApron, dir: Direction) -> bool {
+        self.faces[dir as usize] != other.faces[dir as usize]
+    }
+
+    /// Value of the voxel right behind a face, `None` for other positions and faces without values
+    pub fn get_value(&self, pos: GlobalVoxelPos) -> Option<f32> {
+        let size = Chunk::SIZE_VOXELS_I64;
+        let inside = |v: i64| (0..size).contains(&v);
+
+        let (dir, u, v) = match (pos.x, pos.y, pos.z) {
+            (-1, y, z) => (Direction::WEST, y, z),
+            (x, y, z) if x == size => (Direction::EAST, y, z),
+            (x, -1, z) => (Direction::DOWN, x, z),
+            (x, y, z) if y == size => (Direction::UP, x, z),
+            (x, y, -1) => (Direction::NORTH, x, y),
+            (x, y, z) if z == size => (Direction::SOUTH, x, y),
+            _ => return None,
+        };
+
+        if !inside(u) || !inside(v) {
+            return None;
+        }
+
+        self.faces[dir as usize]
+            .as_ref()
+            .map(|values| values[(u * size + v) as usize])
+    }
+
+    /// Position of the voxel of the `dir` face by its index, the other two axes go in order
+    fn face_voxel_pos(dir: Direction, index: usize) -> GlobalVoxelPos {
+        let size = Chunk::SIZE_VOXELS_I64;
+        let (u, v) = (
+            (index / Chunk::SIZE_VOXELS) as i64,
+            (index % Chunk::SIZE_VOXELS) as i64,
+        );
+
+        match dir {
+            Direction::WEST => GlobalVoxelPos::new(-1, u, v),
+            Direction::EAST => GlobalVoxelPos::new(size, u, v),
+            Direction::DOWN => GlobalVoxelPos::new(u, -1, v),
+            Direction::UP => GlobalVoxelPos::new(u, size, v),
+            Direction::NORTH => GlobalVoxelPos::new(u, v, -1),
+            Direction::SOUTH => GlobalVoxelPos::new(u, v, size),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::internal::voxel::{voxel_types::VoxelId, Voxel};
+use bevy::prelude::*;
+
+/// Id of the voxels filled by [`BrushMode::Smooth`] and [`BrushMode::Flatten`] without solid neighbors
+const FILL_ID: VoxelId = VoxelId::DIRT;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect, FromReflect)]
+pub enum BrushShape {
+    #[default]
+    Sphere,
+    Cube,
+}
+
+impl BrushShape {
+    fn distance(&self, offset: Vec3) -> f32 {
+        match self {
+            Self::Sphere => offset.length(),
+            Self::Cube => offset.abs().max_element(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect, FromReflect)]
+pub enum BrushMode {
+    /// Remove value from voxels, used for mining
+    #[default]
+    Remove,
+    /// Add value to voxels, empty voxels become the given id
+    Add(VoxelId),
+    /// Move values to the average of the neighbor voxels
+    Smooth,
+    /// Move the surface to the plane through the brush center
+    Flatten { normal: Vec3 },
+    /// Change id of the solid voxels without changing their values
+    Paint(VoxelId),
+}
+
+/// Terrain edit applied by [`Chunk::apply_brush`](super::Chunk::apply_brush).
+///
+/// Effect of the brush fades from `strength` in the center to zero at `radius`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect, FromReflect)]
+pub struct Brush {
+    pub mode: BrushMode,
+    pub shape: BrushShape,
+    /// Radius in meters, half size of the cube
+    pub radius: f32,
+    pub strength: f32,
+}
+
+impl Brush {
+    pub fn new(mode: BrushMode, shape: BrushShape, radius: f32, strength: f32) -> Self {
+        Self {
+            mode,
+            shape,
+            radius,
+            strength,
+        }
+    }
+
+    /// Strength at the offset from the brush center, `None` outside of the brush
+    pub fn get_strength(&self, offset: Vec3) -> Option<f32> {
+        let distance = self.shape.distance(offset);
+
+        if distance < self.radius {
+            Some(self.strength * (1.0 - distance / self.radius))
+        } else {
+            None
+        }
+    }
+
+    /// Apply the brush to the voxel at the offset from the brush center.
+    ///
+    /// `neighbors` returns average value of the voxel and its neighbors and id of a solid neighbor.
+    /// Returns `None` if the voxel is not changed.
+    pub fn apply<F: FnOnce() -> (f32, Option<VoxelId>)>(
+        &self,
+        voxel: Voxel,
+        offset: Vec3,
+        strength: f32,
+        neighbors: F,
+    ) -> Option<Voxel> {
+        let move_to = |target: f32, id: Option<VoxelId>| {
+            let value = voxel.value();
+            let value = value + (target - value) * strength.min(1.0);
+            let id = if voxel.is_empty() {
+                id.unwrap_or(FILL_ID)
+            } else {
+                voxel.id()
+            };
+
+            Voxel::new(value, id)
+        };
+
+        match self.mode {
+            BrushMode::Remove => Some(voxel - strength),
+            BrushMode::Add(id) => {
+                let id = if voxel.is_empty() { id } else { voxel.id() };
+                Some(Voxel::new(voxel.value() + strength, id))
+            }
+            BrushMode::Smooth => {
+                let (average, id) = neighbors();
+                Some(move_to(average, id))
+            }
+            BrushMode::Flatten { normal } => {
+                // values change linearly between voxels, so the surface crosses the plane
+                let distance = offset.dot(normal.normalize_or_zero());
+                let target = (-distance / Voxel::SCALE).clamp(-1.0, 1.0);
+                Some(move_to(target, neighbors().1))
+            }
+            BrushMode::Paint(_) if voxel.is_empty() => None,
+            BrushMode::Paint(id) => {
+                let mut voxel = voxel;
+                voxel.set_id(id);
+                Some(voxel)
+            }
+        }
+    }
+}
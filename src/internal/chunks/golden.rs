@@ -0,0 +1,210 @@
+//! Golden output tests of the world generation and meshing.
+//!
+//! Hashes of generated voxels and meshes are compared with the values stored in
+//! [`GOLDEN_PATH`]. If the change of the generated terrain is intended, run tests with
+//! `UPDATE_GOLDEN=1` to write new values and commit the updated file.
+//!
+//! Existing worlds are generated with the pinned generator version, so hashes of the
+//! already released versions should never change (see `tests/golden/README.md`).
+
+use super::{in_world_chunk::InWorldChunk, pointer::ChunkPointer, seams::ChunkSeams, Chunk};
+use crate::{
+    internal::{pos::ChunkPos, voxel::Voxel},
+    plugins::{
+        game_world::resources::GameWorld,
+        static_mesh::components::IndexedMesh,
+        world_generator::{
+            internal::biomes::ChunkBiomes,
+            resources::{settings::GeneratorSettings, WorldGenerator, WorldSeed},
+        },
+    },
+};
+use bevy::prelude::*;
+use std::{collections::BTreeMap, env, fs, path::PathBuf};
+
+const GOLDEN_PATH: &str = "tests/golden/chunks.json";
+
+const SEEDS: [WorldSeed; 3] = [0, 123, 0xdead_beef_cafe_f00d];
+
+const CHUNKS: [((i64, i64, i64), usize); 5] = [
+    ((0, 0, 0), 0),
+    ((0, 0, 0), 2),
+    ((3, -1, 2), 3),
+    ((-5, 0, 7), GameWorld::MAX_DETAIL_LEVEL),
+    ((12, -2, -9), GameWorld::MAX_DETAIL_LEVEL),
+];
+
+/// FNV-1a, unlike `DefaultHasher` it is guaranteed to be the same in every rust version
+struct GoldenHasher(u64);
+
+impl GoldenHasher {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_f32(&mut self, value: f32) {
+        self.write(&value.to_bits().to_le_bytes());
+    }
+
+    fn write_vec3(&mut self, value: Vec3) {
+        self.write_f32(value.x);
+        self.write_f32(value.y);
+        self.write_f32(value.z);
+    }
+
+    fn finish(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+
+fn hash_voxels(voxels: &[Voxel]) -> String {
+    let mut hasher = GoldenHasher::new();
+    hasher.write(&bincode::serialize(voxels).unwrap());
+    hasher.finish()
+}
+
+fn hash_mesh(mesh: &IndexedMesh) -> String {
+    let mut hasher = GoldenHasher::new();
+
+    for index in mesh.indices.iter() {
+        hasher.write(&index.to_le_bytes());
+    }
+
+    for vertex in mesh.vertices.iter() {
+        hasher.write_vec3(vertex.pos);
+        hasher.write_vec3(vertex.normal);
+        vertex
+            .color
+            .as_rgba_f32()
+            .into_iter()
+            .for_each(|v| hasher.write_f32(v));
+        vertex
+            .texture_weights
+            .to_array()
+            .into_iter()
+            .for_each(|v| hasher.write_f32(v));
+    }
+
+    hasher.finish()
+}
+
+fn generate_chunk(gen: &WorldGenerator, pos: ChunkPos, level: usize) -> Chunk {
+    let region_pos = GameWorld::level_pos_to_level_pos(pos, level, 0);
+    let mut chunk = Chunk::generate(gen, ChunkBiomes::new(gen, region_pos), pos, level);
+
+    // skirts on every face of not detailed chunks, as they were before the seams were tracked
+    if level != GameWorld::MAX_DETAIL_LEVEL {
+        chunk.set_seams(ChunkSeams::ALL);
+    }
+
+    chunk
+}
+
+fn golden_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(GOLDEN_PATH)
+}
+
+#[test]
+fn generation_matches_golden() {
+    let versions = [GeneratorSettings::legacy(), GeneratorSettings::current()];
+
+    let mut actual = BTreeMap::new();
+
+    for settings in versions {
+        for seed in SEEDS {
+            let gen = WorldGenerator::with_settings(seed, settings.clone());
+
+            for ((x, y, z), level) in CHUNKS {
+                let pos = ChunkPos::new(x, y, z);
+                let mut chunk = generate_chunk(&gen, pos, level);
+                let key = format!("v{}/{}/{}_{}_{}-{}", settings.version, seed, x, y, z, level);
+
+                actual.insert(format!("{}/voxels", key), hash_voxels(&chunk.voxels));
+                let blocks = chunk.generate_mesh(&gen, pos, level);
+                actual.insert(
+                    format!("{}/mesh", key),
+                    hash_mesh(&IndexedMesh::join(blocks.iter().map(|(_, mesh)| mesh))),
+                );
+            }
+        }
+    }
+
+    let path = golden_path();
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+        return;
+    }
+
+    let expected: BTreeMap<String, String> = fs::read(&path)
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_else(|| {
+            panic!(
+                "can't read {}, run tests with UPDATE_GOLDEN=1 to create it",
+                path.display()
+            )
+        });
+
+    let changed = actual
+        .iter()
+        .filter(|(key, hash)| expected.get(*key) != Some(hash))
+        .map(|(key, _)| key.as_str())
+        .collect::<Vec<_>>();
+
+    assert!(
+        changed.is_empty(),
+        "generated terrain changed: {:?}\nrun tests with UPDATE_GOLDEN=1 if it is intended",
+        changed
+    );
+}
+
+/// Simplified sub-chunks should be the same as the chunk generated at the parent level,
+/// otherwise unmodified terrain jumps when detail level changes.
+#[test]
+fn simplified_sub_chunks_match_parent() {
+    let gen = WorldGenerator::new(123);
+
+    for ((x, y, z), level) in CHUNKS {
+        if level == GameWorld::MAX_DETAIL_LEVEL {
+            continue;
+        }
+
+        let pos = ChunkPos::new(x, y, z);
+
+        let sub_chunks = (0..8)
+            .map(|i| {
+                let sub_pos = pos * 2 + ChunkPos::from_index(i, 2);
+                let chunk = generate_chunk(&gen, sub_pos, level + 1);
+
+                InWorldChunk::Loaded(
+                    ChunkPointer::new(chunk, sub_pos, level + 1),
+                    Entity::from_raw(i as u32),
+                )
+            })
+            .collect();
+
+        let simplified = InWorldChunk::SubChunks(sub_chunks).simplify().unwrap();
+        let parent = generate_chunk(&gen, pos, level);
+
+        for (i, (a, b)) in simplified.iter().zip(parent.voxels.iter()).enumerate() {
+            // thickness of the first layer depends on the voxel size, so only values are compared
+            assert_eq!(
+                (a.is_empty(), a.value()),
+                (b.is_empty(), b.value()),
+                "voxel {} of chunk {:?}-{} differs",
+                i,
+                pos,
+                level
+            );
+        }
+    }
+}
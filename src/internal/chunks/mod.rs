@@ -1,22 +1,54 @@
+use self::{
+    apron::ChunkApron,
+    brush::{Brush, BrushMode, BrushShape},
+    seams::ChunkSeams,
+    sparse::SparseChunk,
+};
 use super::{
+    direction::Direction,
     pos::{ChunkPos, GlobalVoxelPos, VoxelPos},
-    voxel::{voxels_to_vertex::append_vertex, Voxel},
+    voxel::{
+        mesh_builder::MeshBuilder, voxel_types::VoxelId, voxels_to_vertex::append_vertex, Voxel,
+    },
 };
 use crate::plugins::{
-    static_mesh::components::Vertex,
+    game_world::resources::GameWorld,
+    static_mesh::components::IndexedMesh,
     world_generator::{internal::biomes::ChunkBiomes, resources::WorldGenerator},
 };
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
 
+pub mod apron;
+pub mod brush;
+#[cfg(test)]
+mod golden;
 pub mod in_world_chunk;
 pub mod pointer;
+pub mod seams;
+pub mod sparse;
+
+/// Meshes of the blocks of cells by index of the block, see [`Chunk::generate_mesh`]
+pub type MeshBlocks = Vec<(usize, IndexedMesh)>;
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct Chunk {
     voxels: Vec<Voxel>,
     need_redraw: bool,
     need_save: bool,
+    /// Depends on the loaded neighbors, so it is never saved
+    #[serde(skip)]
+    seams: ChunkSeams,
+    /// Depends on the loaded neighbors, so it is never saved
+    #[serde(skip)]
+    apron: ChunkApron,
+    /// Revision of the mesh each block of cells was built in, `None` if the block changed since
+    #[serde(skip)]
+    mesh_blocks: Vec<Option<u64>>,
+    /// Number of meshes built from the chunk, used to drop outdated meshes built in background
+    #[serde(skip)]
+    mesh_revision: u64,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -37,11 +69,20 @@ impl Chunk {
     pub const SIZES: VoxelPos = VoxelPos::from_scalar(Self::SIZE);
     pub const SIZES_VOXELS: VoxelPos = VoxelPos::from_scalar(Self::SIZE_VOXELS);
 
+    /// Number of cells along each axis of the mesh block, see [`Self::generate_mesh`]
+    pub const MESH_BLOCK_SIZE: usize = 4;
+    pub const MESH_BLOCKS: usize = Self::SIZE / Self::MESH_BLOCK_SIZE;
+    pub const MESH_BLOCKS_VOLUME: usize = Self::MESH_BLOCKS * Self::MESH_BLOCKS * Self::MESH_BLOCKS;
+
     pub fn empty() -> Self {
         Self {
             voxels: vec![Voxel::default(); Self::VOLUME_VOXELS],
             need_redraw: false,
             need_save: false,
+            seams: ChunkSeams::NONE,
+            apron: ChunkApron::default(),
+            mesh_blocks: Vec::new(),
+            mesh_revision: 0,
         }
     }
 
@@ -52,14 +93,11 @@ impl Chunk {
         pos: ChunkPos,
         level: usize,
     ) -> Self {
-        let mut chunk = Self::generate(gen, biomes, pos, level);
+        let modified = SparseChunk::from_voxels(&voxels);
+        let need_save = !modified.is_empty();
 
-        for (i, voxel) in voxels.iter().enumerate() {
-            if voxel.is_modified() {
-                chunk.voxels[i] = *voxel;
-                chunk.need_save = true;
-            }
-        }
+        let mut chunk = modified.into_chunk(gen, biomes, pos, level);
+        chunk.need_save = need_save;
 
         chunk
     }
@@ -74,6 +112,10 @@ impl Chunk {
             voxels: gen.generate_voxels(&biomes, pos, level),
             need_redraw: false,
             need_save: false,
+            seams: ChunkSeams::NONE,
+            apron: ChunkApron::default(),
+            mesh_blocks: Vec::new(),
+            mesh_revision: 0,
         }
     }
 
@@ -82,6 +124,10 @@ impl Chunk {
             voxels,
             need_redraw: false,
             need_save: false,
+            seams: ChunkSeams::NONE,
+            apron: ChunkApron::default(),
+            mesh_blocks: Vec::new(),
+            mesh_revision: 0,
         }
     }
 
@@ -97,10 +143,62 @@ impl Chunk {
         self.need_redraw = need_redraw;
     }
 
+    pub fn mesh_revision(&self) -> u64 {
+        self.mesh_revision
+    }
+
     pub fn set_need_save(&mut self, need_save: bool) {
         self.need_save = need_save;
     }
 
+    pub fn seams(&self) -> ChunkSeams {
+        self.seams
+    }
+
+    /// Set faces bordering chunks of another detail level, used by [`Self::generate_mesh`]
+    pub fn set_seams(&mut self, seams: ChunkSeams) {
+        if self.seams != seams {
+            self.mesh_blocks.clear();
+        }
+
+        self.seams = seams;
+    }
+
+    pub fn apron(&self) -> &ChunkApron {
+        &self.apron
+    }
+
+    /// Set values of the neighbor voxels behind the faces, used by [`Self::generate_mesh`].
+    ///
+    /// Only the blocks of cells on the changed faces are built again.
+    pub fn set_apron(&mut self, apron: ChunkApron) {
+        let last = Self::MESH_BLOCKS - 1;
+
+        for dir in Direction::iter() {
+            if !self.apron.is_face_changed(&apron, dir) {
+                continue;
+            }
+
+            for (i, block) in self.mesh_blocks.iter_mut().enumerate() {
+                let pos = VoxelPos::from_index(i, Self::MESH_BLOCKS);
+                let on_face = match dir {
+                    Direction::WEST => pos.x == 0,
+                    Direction::EAST => pos.x == last,
+                    Direction::DOWN => pos.y == 0,
+                    Direction::UP => pos.y == last,
+                    Direction::NORTH => pos.z == 0,
+                    Direction::SOUTH => pos.z == last,
+                };
+
+                if on_face {
+                    *block = None;
+                }
+            }
+        }
+
+        self.apron = apron;
+    }
+
     /// Get voxel at the given position.
     ///
     /// Returns None if position is out of chunk bounds.
@@ -130,30 +228,98 @@ impl Chunk {
         Some(self.voxels[pos.to_index(Self::SIZE_VOXELS)])
     }
 
+    /// Get value of the voxel at the given position, including the voxels behind the faces.
+    ///
+    /// Returns None if position is out of chunk bounds and there is no apron value.
+    pub fn get_voxel_value(&self, pos: GlobalVoxelPos) -> Option<f32> {
+        self.get_voxel(pos)
+            .map(|voxel| voxel.value())
+            .or_else(|| self.apron.get_value(pos))
+    }
+
     /// Remove value from voxels at the given position.
     ///
     /// Should be called only for max_detail_level chunks.
     pub fn mine(&mut self, relative_pos: Vec3, radius: f32, strength: f32) {
-        // FIXME: iterate only over voxels in radius
-        for x in 0..Self::SIZE_VOXELS {
-            for y in 0..Self::SIZE_VOXELS {
-                for z in 0..Self::SIZE_VOXELS {
-                    let voxel_pos = VoxelPos::new(x, y, z);
-                    let pos =
-                        Self::voxel_pos_to_vec(GlobalVoxelPos::new(x as i64, y as i64, z as i64));
-                    let distance = (pos - relative_pos).length();
-
-                    if distance < radius {
-                        let voxel = &mut self.voxels[voxel_pos.to_index(Self::SIZE_VOXELS)];
-                        *voxel -= strength * (1.0 - distance / radius);
-                        voxel.set_modified(true);
-
-                        self.need_redraw = true;
-                        self.need_save = true;
+        let brush = Brush::new(BrushMode::Remove, BrushShape::Sphere, radius, strength);
+        self.apply_brush(relative_pos, &brush);
+    }
+
+    /// Edit voxels with the brush centered at the given position.
+    ///
+    /// Should be called only for max_detail_level chunks.
+    pub fn apply_brush(&mut self, relative_pos: Vec3, brush: &Brush) {
+        let min = Self::vec_to_voxel_pos(relative_pos - Vec3::splat(brush.radius));
+        let max = Self::vec_to_voxel_pos(relative_pos + Vec3::splat(brush.radius));
+
+        let axis_range = |min: i64, max: i64| min.max(0)..=max.min(Self::SIZE_VOXELS_I64 - 1);
+
+        // brushes read neighbor voxels, so changes are applied after all of them are computed
+        let mut changes = Vec::new();
+
+        for x in axis_range(min.x, max.x) {
+            for y in axis_range(min.y, max.y) {
+                for z in axis_range(min.z, max.z) {
+                    let voxel_pos = VoxelPos::new(x as usize, y as usize, z as usize);
+                    let offset =
+                        Self::voxel_pos_to_vec(GlobalVoxelPos::new(x, y, z)) - relative_pos;
+
+                    let strength = match brush.get_strength(offset) {
+                        Some(strength) => strength,
+                        None => continue,
+                    };
+
+                    let voxel = self.voxels[voxel_pos.to_index(Self::SIZE_VOXELS)];
+                    if let Some(voxel) = brush.apply(voxel, offset, strength, || {
+                        self.get_neighbors_average(voxel_pos)
+                    }) {
+                        changes.push((voxel_pos, voxel));
                     }
                 }
             }
         }
+
+        for (voxel_pos, mut voxel) in changes {
+            voxel.set_modified(true);
+            self.voxels[voxel_pos.to_index(Self::SIZE_VOXELS)] = voxel;
+
+            self.invalidate_mesh(voxel_pos);
+            self.need_redraw = true;
+            self.need_save = true;
+        }
+    }
+
+    /// Average value of the voxel and its neighbors, and id of a solid neighbor.
+    ///
+    /// Voxels on the chunk border are shared with the neighbor chunk, so only neighbors on the border
+    /// are used for them, otherwise both chunks would get different values.
+    fn get_neighbors_average(&self, pos: VoxelPos) -> (f32, Option<VoxelId>) {
+        let on_border = |v: usize| v == 0 || v == Self::SIZE_VOXELS - 1;
+        let axes = [
+            (on_border(pos.x), GlobalVoxelPos::new(1, 0, 0)),
+            (on_border(pos.y), GlobalVoxelPos::new(0, 1, 0)),
+            (on_border(pos.z), GlobalVoxelPos::new(0, 0, 1)),
+        ];
+
+        let center = GlobalVoxelPos::from(pos);
+        let mut sum = self.voxels[pos.to_index(Self::SIZE_VOXELS)].value();
+        let mut count = 1;
+        let mut id = None;
+
+        for (_, step) in axes.into_iter().filter(|(border, _)| !border) {
+            for neighbor in [center - step, center + step] {
+                let voxel = self.get_voxel(neighbor).unwrap_or_default();
+
+                sum += voxel.value();
+                count += 1;
+
+                if !voxel.is_empty() {
+                    id.get_or_insert(voxel.id());
+                }
+            }
+        }
+
+        (sum / count as f32, id)
     }
 
     pub fn set_voxel(&mut self, pos: VoxelPos, voxel: Voxel) -> Result<(), VoxelAccessError> {
@@ -162,26 +328,115 @@ impl Chunk {
         }
 
         self.voxels[pos.to_index(Self::SIZE_VOXELS)] = voxel;
+        self.invalidate_mesh(pos);
 
         Ok(())
     }
 
-    pub fn generate_vertices(
+    /// Mark mesh blocks of the cells around the voxel to be built again
+    ///
+    /// Normals of the vertices depend on the voxel values around the cell edges,
+    /// so cells one voxel further are built again too.
+    fn invalidate_mesh(&mut self, pos: VoxelPos) {
+        if self.mesh_blocks.is_empty() {
+            return;
+        }
+
+        let axis_cells = |v: usize| v.saturating_sub(2)..=(v + 1).min(Self::SIZE - 1);
+
+        for x in axis_cells(pos.x) {
+            for y in axis_cells(pos.y) {
+                for z in axis_cells(pos.z) {
+                    let block = VoxelPos::new(x, y, z) / Self::MESH_BLOCK_SIZE;
+                    self.mesh_blocks[block.to_index(Self::MESH_BLOCKS)] = None;
+                }
+            }
+        }
+    }
+
+    /// Check if voxels of the cells between `origin` and `origin + size` are partly empty
+    fn has_surface(&self, origin: VoxelPos, size: usize) -> bool {
+        let mut empty = false;
+        let mut solid = false;
+
+        for i in 0..(size + 1) * (size + 1) * (size + 1) {
+            let pos = origin + VoxelPos::from_index(i, size + 1);
+
+            if self.voxels[pos.to_index(Self::SIZE_VOXELS)].is_empty() {
+                empty = true;
+            } else {
+                solid = true;
+            }
+
+            if empty && solid {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn generate_block_mesh(
         &self,
         gen: &WorldGenerator,
         chunk_pos: ChunkPos,
         level: usize,
-    ) -> Vec<Vertex> {
-        let mut vertices: Vec<Vertex> = Vec::new();
-        for x in 0..Self::SIZE {
-            for y in 0..Self::SIZE {
-                for z in 0..Self::SIZE {
-                    append_vertex(gen, chunk_pos, (x, y, z).into(), self, &mut vertices, level);
-                }
-            }
+        block: usize,
+    ) -> IndexedMesh {
+        let origin = VoxelPos::from_index(block, Self::MESH_BLOCKS) * Self::MESH_BLOCK_SIZE;
+
+        if !self.has_surface(origin, Self::MESH_BLOCK_SIZE) {
+            return IndexedMesh::default();
+        }
+
+        let mut builder = MeshBuilder::new(origin, Self::MESH_BLOCK_SIZE);
+
+        for i in 0..Self::MESH_BLOCK_SIZE * Self::MESH_BLOCK_SIZE * Self::MESH_BLOCK_SIZE {
+            let pos = origin + VoxelPos::from_index(i, Self::MESH_BLOCK_SIZE);
+            append_vertex(gen, chunk_pos, pos, self, &mut builder, level);
         }
 
-        vertices
+        builder.build()
+    }
+
+    /// Build meshes of the blocks of cells changed since the last call.
+    ///
+    /// Meshes of the blocks without surface are empty. Each block is a separate entity,
+    /// so after [`Self::mine`] or [`Self::set_voxel`] only the blocks around the changed
+    /// voxels are built and uploaded again.
+    pub fn generate_mesh(
+        &mut self,
+        gen: &WorldGenerator,
+        chunk_pos: ChunkPos,
+        level: usize,
+    ) -> MeshBlocks {
+        self.mesh_revision += 1;
+        self.mesh_blocks.resize(Self::MESH_BLOCKS_VOLUME, None);
+
+        // most of the chunks are entirely in the air or under the ground
+        let has_surface = self.has_surface(VoxelPos::zero(), Self::SIZE);
+
+        let changed = (0..Self::MESH_BLOCKS_VOLUME)
+            .filter(|i| self.mesh_blocks[*i].is_none())
+            .collect::<Vec<_>>();
+
+        changed
+            .into_iter()
+            .map(|i| {
+                let mesh = match has_surface {
+                    true => self.generate_block_mesh(gen, chunk_pos, level, i),
+                    false => IndexedMesh::default(),
+                };
+                self.mesh_blocks[i] = Some(self.mesh_revision);
+
+                (i, mesh)
+            })
+            .collect()
+    }
+
+    /// Check if the block was built by [`Self::generate_mesh`] at the `revision` and is not changed
+    pub fn is_mesh_block_current(&self, block: usize, revision: u64) -> bool {
+        self.mesh_blocks.get(block) == Some(&Some(revision))
     }
 
     fn normalize_axis(axis: i64) -> usize {
@@ -282,3 +537,176 @@ fn test_vec_to_chunk_pos() {
 
     assert_eq!(chunk_pos, ChunkPos::new(-1, 0, 0));
 }
+
+#[test]
+fn mesh_vertices_are_shared() {
+    let gen = WorldGenerator::new(123);
+    let pos = ChunkPos::new(0, 0, 0);
+
+    let mut chunk = Chunk::generate(&gen, ChunkBiomes::new(&gen, pos), pos, 0);
+    let blocks = chunk.generate_mesh(&gen, pos, 0);
+    let mesh = IndexedMesh::join(blocks.iter().map(|(_, mesh)| mesh));
+
+    assert!(!mesh.is_empty());
+    assert!(mesh
+        .indices
+        .iter()
+        .all(|i| (*i as usize) < mesh.vertices.len()));
+    // triangle soup would have a vertex per index
+    assert!(mesh.vertices.len() * 2 < mesh.indices.len());
+}
+
+#[test]
+fn incremental_mesh_matches_full() {
+    let gen = WorldGenerator::new(123);
+    let level = GameWorld::MAX_DETAIL_LEVEL;
+
+    let height = gen.get_surface_height(0.0, 0.0) as f32;
+    let pos = Chunk::vec_to_chunk_pos(Vec3::new(0.0, height, 0.0));
+    let region_pos = GameWorld::chunk_pos_to_region_pos(pos);
+
+    let mut chunk = Chunk::generate(&gen, ChunkBiomes::new(&gen, region_pos), pos, level);
+    let mut blocks = chunk.generate_mesh(&gen, pos, level);
+    assert_eq!(blocks.len(), Chunk::MESH_BLOCKS_VOLUME);
+
+    let surface = Vec3::new(0.0, height, 0.0) - Chunk::pos_to_translation(pos);
+    chunk.mine(surface, 1.0, 1.0);
+
+    let changed = chunk.generate_mesh(&gen, pos, level);
+    assert!(!changed.is_empty() && changed.len() < blocks.len());
+
+    for (i, mesh) in changed {
+        blocks[i] = (i, mesh);
+    }
+
+    let full = Chunk::from_voxels(chunk.voxels.clone()).generate_mesh(&gen, pos, level);
+
+    assert!(full.iter().any(|(_, mesh)| !mesh.is_empty()));
+    for ((_, incremental), (_, full)) in blocks.iter().zip(full.iter()) {
+        assert_eq!(incremental.indices, full.indices);
+        assert!(incremental
+            .vertices
+            .iter()
+            .zip(full.vertices.iter())
+            .all(|(a, b)| a.pos == b.pos && a.normal == b.normal));
+    }
+}
+
+#[test]
+fn neighbor_chunks_have_same_border_normals() {
+    let gen = WorldGenerator::new(123);
+    let level = GameWorld::MAX_DETAIL_LEVEL;
+
+    let height = gen.get_surface_height(Chunk::REAL_SIZE as f64, 0.0) as f32;
+    let west = Chunk::vec_to_chunk_pos(Vec3::new(0.0, height, 0.0));
+    let east = west + ChunkPos::new(1, 0, 0);
+
+    // a hole dug across the border, like the mining system does on all touched chunks
+    let hole = Vec3::new(Chunk::REAL_SIZE, height, Chunk::REAL_SIZE / 2.0);
+    let brush = Brush::new(BrushMode::Remove, BrushShape::Sphere, 3.0, 1.0);
+
+    let mut chunks = [west, east].map(|pos| {
+        let biomes = ChunkBiomes::new(&gen, GameWorld::chunk_pos_to_region_pos(pos));
+        let mut chunk = Chunk::generate(&gen, biomes, pos, level);
+        chunk.apply_brush(hole - Chunk::pos_to_translation(pos), &brush);
+        chunk
+    });
+
+    let mut west_apron = ChunkApron::default();
+    west_apron.set_face(Direction::EAST, Some(&chunks[1]));
+    let mut east_apron = ChunkApron::default();
+    east_apron.set_face(Direction::WEST, Some(&chunks[0]));
+    chunks[0].set_apron(west_apron);
+    chunks[1].set_apron(east_apron);
+
+    let [west_mesh, east_mesh] = [(0, west), (1, east)].map(|(i, pos)| {
+        let blocks = chunks[i].generate_mesh(&gen, pos, level);
+        IndexedMesh::join(blocks.iter().map(|(_, mesh)| mesh))
+    });
+
+    let border = west_mesh
+        .vertices
+        .iter()
+        .filter(|v| v.pos.x == Chunk::REAL_SIZE)
+        .collect::<Vec<_>>();
+    assert!(!border.is_empty());
+
+    for a in border {
+        let b = east_mesh
+            .vertices
+            .iter()
+            .find(|b| b.pos == Vec3::new(0.0, a.pos.y, a.pos.z))
+            .unwrap();
+        assert!((a.normal - b.normal).length() < 1e-5);
+    }
+}
+
+#[test]
+fn brushes_edit_voxels() {
+    let center = Vec3::splat(Chunk::REAL_SIZE / 2.0);
+    let center_pos = VoxelPos::from(Chunk::vec_to_voxel_pos(center));
+    let brush = |mode: BrushMode| Brush::new(mode, BrushShape::Cube, 1.0, 2.0);
+
+    let mut chunk = Chunk::empty();
+
+    chunk.apply_brush(center, &brush(BrushMode::Add(VoxelId::STONE)));
+    let voxel = chunk.get_voxel_at(center_pos).unwrap();
+    assert!(!voxel.is_empty() && voxel.is_modified());
+    assert_eq!(voxel.id(), VoxelId::STONE);
+    assert!(chunk.is_need_save() && chunk.is_need_redraw());
+
+    chunk.apply_brush(center, &brush(BrushMode::Paint(VoxelId::SAND)));
+    assert_eq!(chunk.get_voxel_at(center_pos).unwrap().id(), VoxelId::SAND);
+
+    // everything above the plane through the center is removed
+    chunk.apply_brush(center, &brush(BrushMode::Flatten { normal: Vec3::Y }));
+    assert!(!chunk
+        .get_voxel_at(center_pos - VoxelPos::new(0, 1, 0))
+        .unwrap()
+        .is_empty());
+    assert!(chunk
+        .get_voxel_at(center_pos + VoxelPos::new(0, 1, 0))
+        .unwrap()
+        .is_empty());
+
+    chunk.apply_brush(center, &brush(BrushMode::Remove));
+    assert!(chunk.get_voxel_at(center_pos).unwrap().is_empty());
+}
+
+#[test]
+fn texture_weights_are_averaged() {
+    let gen = WorldGenerator::new(123);
+    let level = GameWorld::MAX_DETAIL_LEVEL;
+
+    let center = Chunk::REAL_SIZE / 2.0;
+    let height = gen.get_surface_height(center as f64, center as f64) as f32;
+    let pos = Chunk::vec_to_chunk_pos(Vec3::new(center, height, center));
+    let region_pos = GameWorld::chunk_pos_to_region_pos(pos);
+
+    let mut chunk = Chunk::generate(&gen, ChunkBiomes::new(&gen, region_pos), pos, level);
+
+    // flint has no texture, so its vertices fall back to the color
+    let surface = Vec3::new(center, height - pos.y as f32 * Chunk::REAL_SIZE, center);
+    let brush = Brush::new(
+        BrushMode::Paint(VoxelId::FLINT),
+        BrushShape::Sphere,
+        3.0,
+        1.0,
+    );
+    chunk.apply_brush(surface, &brush);
+
+    let blocks = chunk.generate_mesh(&gen, pos, level);
+    let mesh = IndexedMesh::join(blocks.iter().map(|(_, mesh)| mesh));
+
+    assert!(!mesh.is_empty());
+    for vertex in mesh.vertices.iter() {
+        let sum = vertex.texture_weights.dot(Vec4::ONE);
+        assert!(vertex.texture_weights.min_element() >= 0.0);
+        assert!(sum <= 1.0 + 1e-5);
+    }
+
+    assert!(mesh
+        .vertices
+        .iter()
+        .any(|vertex| vertex.texture_weights.dot(Vec4::ONE) < 1.0 - 1e-5));
+}
@@ -0,0 +1,21 @@
+use crate::internal::direction::Direction;
+
+/// Faces of the chunk bordering chunks of another detail level.
+///
+/// Meshes of such chunks don't match at the border, so skirts are added on these faces
+/// to hide the gaps.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkSeams(u8);
+
+impl ChunkSeams {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self((1 << Direction::COUNT) - 1);
+
+    pub fn with(self, dir: Direction) -> Self {
+        Self(self.0 | (1 << dir as usize))
+    }
+
+    pub fn has(&self, dir: Direction) -> bool {
+        self.0 & (1 << dir as usize) != 0
+    }
+}
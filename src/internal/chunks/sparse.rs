@@ -0,0 +1,138 @@
+use super::Chunk;
+use crate::{
+    internal::{
+        pos::{ChunkPos, VoxelPos},
+        voxel::Voxel,
+    },
+    plugins::world_generator::{internal::biomes::ChunkBiomes, resources::WorldGenerator},
+};
+use serde::{Deserialize, Serialize};
+
+/// Saved state of the chunk.
+///
+/// Only modified voxels are stored, the rest of the chunk is generated again on load.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SparseChunk {
+    /// Modified voxels with their indices in the chunk
+    voxels: Vec<(u16, Voxel)>,
+}
+
+impl SparseChunk {
+    pub fn from_chunk(chunk: &Chunk) -> Self {
+        Self::from_voxels(&chunk.voxels)
+    }
+
+    /// Take modified voxels of the dense chunk data
+    pub fn from_voxels(voxels: &[Voxel]) -> Self {
+        let voxels = voxels
+            .iter()
+            .enumerate()
+            .filter(|(_, voxel)| voxel.is_modified())
+            .map(|(i, voxel)| (i as u16, *voxel))
+            .collect();
+
+        Self { voxels }
+    }
+
+    /// Store all voxels of the generated chunk, so it can be loaded without generation.
+    ///
+    /// Once the chunk is modified and saved again only modified voxels are kept.
+    pub fn bake(chunk: &Chunk) -> Self {
+        let voxels = chunk
+            .voxels
+            .iter()
+            .enumerate()
+            .map(|(i, voxel)| (i as u16, *voxel))
+            .collect();
+
+        Self { voxels }
+    }
+
+    /// Check if all voxels of the chunk are stored
+    pub fn is_baked(&self) -> bool {
+        self.voxels.len() == Chunk::VOLUME_VOXELS
+    }
+
+    /// Modified voxels with their positions in the chunk
+    pub fn iter(&self) -> impl Iterator<Item = (VoxelPos, Voxel)> + '_ {
+        self.voxels.iter().map(|(i, voxel)| {
+            (
+                VoxelPos::from_index(*i as usize, Chunk::SIZE_VOXELS),
+                *voxel,
+            )
+        })
+    }
+
+    /// Count of modified voxels
+    pub fn len(&self) -> usize {
+        self.voxels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.voxels.is_empty()
+    }
+
+    /// Check that all voxels are inside of the chunk
+    pub fn is_valid(&self) -> bool {
+        self.voxels
+            .iter()
+            .all(|(i, _)| (*i as usize) < Chunk::VOLUME_VOXELS)
+    }
+
+    /// Generate chunk and apply modified voxels to it
+    pub fn into_chunk(
+        self,
+        gen: &WorldGenerator,
+        biomes: ChunkBiomes,
+        pos: ChunkPos,
+        level: usize,
+    ) -> Chunk {
+        let mut chunk = if self.is_baked() {
+            Chunk::empty()
+        } else {
+            Chunk::generate(gen, biomes, pos, level)
+        };
+
+        for (i, voxel) in self.voxels {
+            chunk.voxels[i as usize] = voxel;
+        }
+
+        chunk
+    }
+
+    /// Upgrade chunk payload that contains all voxels of the chunk to the sparse one
+    pub fn migrate_from_dense(data: Vec<u8>) -> Result<Vec<u8>, String> {
+        let chunk: Chunk = bincode::deserialize(&data).map_err(|err| err.to_string())?;
+
+        bincode::serialize(&Self::from_chunk(&chunk)).map_err(|err| err.to_string())
+    }
+}
+
+#[test]
+fn sparse_chunk_from_dense() {
+    use crate::internal::voxel::voxel_types::VoxelId;
+
+    let mut chunk = Chunk::empty();
+
+    let mut voxel = Voxel::new(0.5, VoxelId::STONE);
+    voxel.set_modified(true);
+
+    let pos = VoxelPos::new(1, 2, 3);
+    chunk.set_voxel(pos, voxel).unwrap();
+    chunk
+        .set_voxel(VoxelPos::new(3, 2, 1), Voxel::new(0.5, VoxelId::STONE))
+        .unwrap();
+
+    let sparse = SparseChunk::from_chunk(&chunk);
+    assert_eq!(
+        sparse.voxels,
+        vec![(pos.to_index(Chunk::SIZE_VOXELS) as u16, voxel)]
+    );
+    assert!(sparse.is_valid());
+
+    let migrated = SparseChunk::migrate_from_dense(bincode::serialize(&chunk).unwrap()).unwrap();
+    assert_eq!(
+        bincode::deserialize::<SparseChunk>(&migrated).unwrap(),
+        sparse
+    );
+}
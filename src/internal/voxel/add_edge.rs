@@ -1,90 +1,98 @@
-use super::append_triangle::append_triangle_with_normal;
-use crate::{
-    internal::{chunks::Chunk, color::Color, pos::VoxelPos},
-    plugins::static_mesh::components::Vertex,
+use super::mesh_builder::MeshBuilder;
+use crate::internal::{
+    chunks::{seams::ChunkSeams, Chunk},
+    direction::Direction,
+    pos::VoxelPos,
 };
 use bevy::prelude::Vec3;
 
 const FRAME_SIZE: f32 = 0.25;
 
+/// Position of the triangle corner in voxels and index of its vertex
+type Point = (Vec3, u32);
+
 /// Additional mesh at the chunks border to hide seams between chunks with different LODs
+///
+/// Added only on the faces from `seams`, where the neighbor chunk has another detail level
 pub fn append_edge(
-    vertex: &mut Vec<Vertex>,
-    color: Color,
+    builder: &mut MeshBuilder,
     scale: f32,
     pos: VoxelPos,
     normal: Vec3,
-    points: (Vec3, Vec3, Vec3),
+    points: (Point, Point, Point),
+    seams: ChunkSeams,
 ) {
     let chunk_size = Chunk::SIZE as f32;
-    let (a, b, c) = points;
+    let (pa, pb, pc) = points;
+    let (a, b, c) = (pa.0, pb.0, pc.0);
 
-    let mut f = |a: Vec3, b: Vec3, mask: Vec3, color: Color| {
+    // skirt shares the vertices with the border of the surface
+    let mut f = |a: Point, b: Point, mask: Vec3| {
         let dir = -normal * mask;
         let dir = dir.normalize() * FRAME_SIZE;
 
-        let c = b + dir;
-        let d = a + dir;
+        let c = builder.add_vertex_copy(b.1, (b.0 + dir) * scale, normal);
+        let d = builder.add_vertex_copy(a.1, (a.0 + dir) * scale, normal);
 
-        append_triangle_with_normal(vertex, scale, color, c, b, a, normal);
-        append_triangle_with_normal(vertex, scale, color, a, d, c, normal);
+        builder.add_indices([a.1, b.1, c]);
+        builder.add_indices([c, d, a.1]);
     };
 
     // We have 3 points, if two of them are on the same edge, wee need to create
     // additional face to fill potential gap between chunks with different
     // detail level
 
-    if pos.x == 0 {
+    if pos.x == 0 && seams.has(Direction::WEST) {
         if a.x == 0. && b.x == 0. {
-            f(a, b, Vec3::new(0.0, 1.0, 1.0), color);
+            f(pa, pb, Vec3::new(0.0, 1.0, 1.0));
         } else if a.x == 0. && c.x == 0. {
-            f(c, a, Vec3::new(0.0, 1.0, 1.0), color);
+            f(pc, pa, Vec3::new(0.0, 1.0, 1.0));
         } else if b.x == 0. && c.x == 0. {
-            f(b, c, Vec3::new(0.0, 1.0, 1.0), color);
+            f(pb, pc, Vec3::new(0.0, 1.0, 1.0));
         }
-    } else if pos.x == Chunk::SIZE - 1 {
+    } else if pos.x == Chunk::SIZE - 1 && seams.has(Direction::EAST) {
         if a.x == chunk_size && b.x == chunk_size {
-            f(a, b, Vec3::new(0.0, 1.0, 1.0), color);
+            f(pa, pb, Vec3::new(0.0, 1.0, 1.0));
         } else if a.x == chunk_size && c.x == chunk_size {
-            f(c, a, Vec3::new(0.0, 1.0, 1.0), color);
+            f(pc, pa, Vec3::new(0.0, 1.0, 1.0));
         } else if b.x == chunk_size && c.x == chunk_size {
-            f(b, c, Vec3::new(0.0, 1.0, 1.0), color);
+            f(pb, pc, Vec3::new(0.0, 1.0, 1.0));
         }
     }
 
-    if pos.y == 0 {
+    if pos.y == 0 && seams.has(Direction::DOWN) {
         if a.y == 0. && b.y == 0. {
-            f(a, b, Vec3::new(1.0, 0.0, 1.0), color);
+            f(pa, pb, Vec3::new(1.0, 0.0, 1.0));
         } else if a.y == 0. && c.y == 0. {
-            f(c, a, Vec3::new(1.0, 0.0, 1.0), color);
+            f(pc, pa, Vec3::new(1.0, 0.0, 1.0));
         } else if b.y == 0. && c.y == 0. {
-            f(b, c, Vec3::new(1.0, 0.0, 1.0), color);
+            f(pb, pc, Vec3::new(1.0, 0.0, 1.0));
         }
-    } else if pos.y == Chunk::SIZE - 1 {
+    } else if pos.y == Chunk::SIZE - 1 && seams.has(Direction::UP) {
         if a.y == chunk_size && b.y == chunk_size {
-            f(a, b, Vec3::new(1.0, 0.0, 1.0), color);
+            f(pa, pb, Vec3::new(1.0, 0.0, 1.0));
         } else if a.y == chunk_size && c.y == chunk_size {
-            f(c, a, Vec3::new(1.0, 0.0, 1.0), color);
+            f(pc, pa, Vec3::new(1.0, 0.0, 1.0));
         } else if b.y == chunk_size && c.y == chunk_size {
-            f(b, c, Vec3::new(1.0, 0.0, 1.0), color);
+            f(pb, pc, Vec3::new(1.0, 0.0, 1.0));
         }
     }
 
-    if pos.z == 0 {
+    if pos.z == 0 && seams.has(Direction::NORTH) {
         if a.z == 0. && b.z == 0. {
-            f(a, b, Vec3::new(1.0, 1.0, 0.0), color);
+            f(pa, pb, Vec3::new(1.0, 1.0, 0.0));
         } else if a.z == 0. && c.z == 0. {
-            f(c, a, Vec3::new(1.0, 1.0, 0.0), color);
+            f(pc, pa, Vec3::new(1.0, 1.0, 0.0));
         } else if b.z == 0. && c.z == 0. {
-            f(b, c, Vec3::new(1.0, 1.0, 0.0), color);
+            f(pb, pc, Vec3::new(1.0, 1.0, 0.0));
         }
-    } else if pos.z == Chunk::SIZE - 1 {
+    } else if pos.z == Chunk::SIZE - 1 && seams.has(Direction::SOUTH) {
         if a.z == chunk_size && b.z == chunk_size {
-            f(a, b, Vec3::new(1.0, 1.0, 0.0), color);
+            f(pa, pb, Vec3::new(1.0, 1.0, 0.0));
         } else if a.z == chunk_size && c.z == chunk_size {
-            f(c, a, Vec3::new(1.0, 1.0, 0.0), color);
+            f(pc, pa, Vec3::new(1.0, 1.0, 0.0));
         } else if b.z == chunk_size && c.z == chunk_size {
-            f(b, c, Vec3::new(1.0, 1.0, 0.0), color);
+            f(pb, pc, Vec3::new(1.0, 1.0, 0.0));
         }
     }
 }
@@ -0,0 +1,114 @@
+use crate::{
+    internal::pos::VoxelPos,
+    plugins::static_mesh::components::{IndexedMesh, Vertex},
+};
+use bevy::math::{Vec3, Vec4};
+
+const NO_VERTEX: u32 = u32::MAX;
+
+/// Builds indexed mesh of the block of cells.
+///
+/// Vertices on the cell edges are cached by the edge, so neighbor cells share them
+/// and texture weights of the shared vertices are averaged over all their cells.
+pub struct MeshBuilder {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    /// Number of cells which added texture weights to each vertex
+    weight_counts: Vec<u32>,
+    /// Vertex which texture weights are copied to each vertex, see [`Self::add_vertex_copy`]
+    weight_sources: Vec<u32>,
+    /// First voxel of the block
+    origin: VoxelPos,
+    /// Number of cells along each axis of the block
+    size: usize,
+    /// Vertex index of each cell edge, see [`Self::edge_key`]
+    edges: Vec<u32>,
+}
+
+impl MeshBuilder {
+    pub fn new(origin: VoxelPos, size: usize) -> Self {
+        Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            weight_counts: Vec::new(),
+            weight_sources: Vec::new(),
+            origin,
+            size,
+            edges: vec![NO_VERTEX; (size + 1) * (size + 1) * (size + 1) * 3],
+        }
+    }
+
+    /// Edge starts at the voxel `pos` and goes along the `axis` to the next voxel
+    fn edge_key(&self, pos: VoxelPos, axis: usize) -> usize {
+        (pos - self.origin).to_index(self.size + 1) * 3 + axis
+    }
+
+    /// Get vertex of the edge, `create` is called only if the edge has no vertex yet
+    pub fn edge_vertex<F: FnOnce() -> Vertex>(
+        &mut self,
+        pos: VoxelPos,
+        axis: usize,
+        create: F,
+    ) -> u32 {
+        let key = self.edge_key(pos, axis);
+
+        if self.edges[key] == NO_VERTEX {
+            self.edges[key] = self.add_vertex(create());
+        }
+
+        self.edges[key]
+    }
+
+    /// Add vertex, which is not shared with other cells
+    pub fn add_vertex(&mut self, vertex: Vertex) -> u32 {
+        self.vertices.push(vertex);
+        self.weight_counts.push(0);
+        self.weight_sources.push(NO_VERTEX);
+        (self.vertices.len() - 1) as u32
+    }
+
+    /// Add copy of the vertex with another position and normal, which is not shared with other cells.
+    ///
+    /// Texture weights are copied in [`Self::build`], when all cells added theirs to the source
+    pub fn add_vertex_copy(&mut self, source: u32, pos: Vec3, normal: Vec3) -> u32 {
+        let index = self.add_vertex(Vertex {
+            pos,
+            normal,
+            ..self.vertices[source as usize]
+        });
+        self.weight_sources[index as usize] = source;
+        index
+    }
+
+    pub fn add_indices(&mut self, triangle: [u32; 3]) {
+        self.indices.extend_from_slice(&triangle);
+    }
+
+    /// Add texture weights of the cell to the vertex, they are averaged in [`Self::build`]
+    pub fn add_texture_weights(&mut self, index: u32, weights: Vec4) {
+        self.vertices[index as usize].texture_weights += weights;
+        self.weight_counts[index as usize] += 1;
+    }
+
+    pub fn build(mut self) -> IndexedMesh {
+        // weights of voxels without textures are zero, so the sum stays below 1
+        // and the vertex color is used for the rest
+        for (vertex, count) in self.vertices.iter_mut().zip(&self.weight_counts) {
+            if *count > 0 {
+                vertex.texture_weights /= *count as f32;
+            }
+        }
+
+        for (index, source) in self.weight_sources.iter().enumerate() {
+            if *source != NO_VERTEX {
+                self.vertices[index].texture_weights =
+                    self.vertices[*source as usize].texture_weights;
+            }
+        }
+
+        IndexedMesh {
+            vertices: self.vertices,
+            indices: self.indices,
+        }
+    }
+}
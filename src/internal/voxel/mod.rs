@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::ops::{Sub, SubAssign};
 
 pub(self) mod add_edge;
-pub(self) mod append_triangle;
+pub mod mesh_builder;
 pub(self) mod triangulation_table;
 pub mod voxel_types;
 pub mod voxels_to_vertex;
@@ -99,6 +99,13 @@ impl Voxel {
             Self::NotEmpty(v) => v.modified = modified,
         }
     }
+
+    /// Change id of the solid voxel, empty voxels have no id
+    pub fn set_id(&mut self, id: VoxelId) {
+        if let Self::NotEmpty(v) = self {
+            v.id = id;
+        }
+    }
 }
 
 impl Default for Voxel {
@@ -1,3 +1,4 @@
+use bevy::math::Vec4;
 use bevy_reflect::{FromReflect, Reflect};
 use lerp::Lerp;
 use serde::{Deserialize, Serialize};
@@ -9,9 +10,12 @@ use crate::internal::color::Color;
 )]
 pub struct VoxelId(u32);
 
+/// Ids can't be interpolated, so the closest one is used.
+///
+/// Random blending of ids is done by [`ChunkBiomes`](crate::plugins::world_generator::internal::biomes::ChunkBiomes)
 impl Lerp<f32> for VoxelId {
     fn lerp(self, other: Self, pos: f32) -> Self {
-        if rand::random::<f32>() > pos {
+        if pos < 0.5 {
             self
         } else {
             other
@@ -26,6 +30,13 @@ impl VoxelId {
     pub const SAND: Self = Self(3);
     pub const SAND_STONE: Self = Self(4);
     pub const SNOW: Self = Self(5);
+    pub const FLINT: Self = Self(6);
+    pub const CLAY: Self = Self(7);
+    pub const COPPER_ORE: Self = Self(8);
+    pub const TIN_ORE: Self = Self(9);
+    pub const MOSS: Self = Self(10);
+    pub const CRYSTAL: Self = Self(11);
+    pub const BASALT: Self = Self(12);
 
     pub const fn new(id: u32) -> Self {
         Self(id)
@@ -43,8 +54,35 @@ impl VoxelId {
             3 => Color::rgb_u8(218, 185, 113),
             4 => Color::rgb_u8(200, 158, 100),
             5 => Color::rgb_u8(255, 255, 255),
+            6 => Color::rgb_u8(45, 45, 50),
+            7 => Color::rgb_u8(160, 95, 70),
+            8 => Color::rgb_u8(150, 90, 50),
+            9 => Color::rgb_u8(170, 170, 180),
+            10 => Color::rgb_u8(70, 110, 40),
+            11 => Color::rgb_u8(150, 120, 220),
+            12 => Color::rgb_u8(50, 35, 35),
             // Unknown voxel id.
             _ => Color::rgb_u8(255, 0, 255),
         }
     }
+
+    /// Layer of the terrain texture array, voxels without texture use only their color
+    pub fn get_texture_layer(&self) -> Option<usize> {
+        match *self {
+            Self::GRASS => Some(0),
+            Self::DIRT => Some(1),
+            Self::STONE => Some(2),
+            Self::SAND => Some(3),
+            _ => None,
+        }
+    }
+
+    /// Weights of the terrain texture layers, see [`TerrainMaterial`](crate::plugins::static_mesh::materials::TerrainMaterial)
+    pub fn get_texture_weights(&self) -> Vec4 {
+        let mut weights = Vec4::ZERO;
+        if let Some(layer) = self.get_texture_layer() {
+            weights[layer] = 1.0;
+        }
+        weights
+    }
 }
@@ -1,12 +1,13 @@
 use super::add_edge::append_edge;
+use super::mesh_builder::MeshBuilder;
 use super::triangulation_table::{get_index_by_voxels, TABLE};
-use super::{append_triangle::append_triangle, Voxel};
-use crate::internal::chunks::Chunk;
+use super::Voxel;
+use crate::internal::chunks::{seams::ChunkSeams, Chunk};
 use crate::internal::pos::{ChunkPos, GlobalVoxelPos, VoxelPos};
 use crate::plugins::game_world::resources::GameWorld;
 use crate::plugins::static_mesh::components::Vertex;
 use crate::plugins::world_generator::resources::WorldGenerator;
-use bevy::math::Vec3;
+use bevy::math::{Vec3, Vec4};
 
 #[derive(Clone, Copy)]
 struct VertexNode {
@@ -14,6 +15,26 @@ struct VertexNode {
     pos: Vec3,
 }
 
+impl VertexNode {
+    /// Offset of the first voxel of the cell edge the node lies on, and axis of the edge
+    fn edge(&self) -> (VoxelPos, usize) {
+        let offset = |v: f32| if v == 1.0 { 1 } else { 0 };
+
+        let axis = if self.pos.x == 0.5 {
+            0
+        } else if self.pos.y == 0.5 {
+            1
+        } else {
+            2
+        };
+
+        (
+            VoxelPos::new(offset(self.pos.x), offset(self.pos.y), offset(self.pos.z)),
+            axis,
+        )
+    }
+}
+
 const NODE_DN: VertexNode = VertexNode {
     index: 0,
     pos: Vec3 {
@@ -207,15 +228,116 @@ fn shift_node_pos(pos: Vec3, value: f32) -> Vec3 {
     panic!("failed to process pos {:?}", pos);
 }
 
+/// Gradient of the voxel values at the voxel, the surface normal is opposite to it.
+///
+/// Voxels on the chunk border are shared with the neighbor chunk, so differences across
+/// the border use the voxels of the neighbor from [`Chunk::apron`], and both chunks get
+/// the same normals there. Without the neighbor the difference is one-sided.
+fn voxel_gradient(chunk: &Chunk, pos: VoxelPos) -> Vec3 {
+    let pos = GlobalVoxelPos::from(pos);
+    let value = |pos: GlobalVoxelPos| chunk.get_voxel_value(pos);
+
+    let axes = [
+        GlobalVoxelPos::new(1, 0, 0),
+        GlobalVoxelPos::new(0, 1, 0),
+        GlobalVoxelPos::new(0, 0, 1),
+    ];
+
+    let diff = axes.map(|dir| match (value(pos - dir), value(pos + dir)) {
+        (Some(prev), Some(next)) => (next - prev) * 0.5,
+        (None, Some(next)) => next - value(pos).unwrap_or_default(),
+        (Some(prev), None) => value(pos).unwrap_or_default() - prev,
+        (None, None) => 0.0,
+    });
+
+    Vec3::from(diff)
+}
+
+/// Average texture weights of the solid voxels of the cell
+fn get_cell_texture_weights(voxels: VoxelsBlock) -> Vec4 {
+    let solid = voxels
+        .iter()
+        .flatten()
+        .flatten()
+        .filter(|voxel| !voxel.is_empty())
+        .collect::<Vec<_>>();
+
+    solid
+        .iter()
+        .map(|voxel| voxel.id().get_texture_weights())
+        .sum::<Vec4>()
+        / solid.len().max(1) as f32
+}
+
+/// Add vertex of the node, it is shared with the cells around the same edge
+///
+/// Texture weights of the edge voxels are interpolated like the position, empty voxel has
+/// no texture, so weights of the solid voxels of the cell are used for it. Each cell adds
+/// its weights to the shared vertex, so ids of all voxels around the edge are blended.
+#[allow(clippy::too_many_arguments)]
+fn append_node_vertex(
+    gen: &WorldGenerator,
+    chunk_pos: ChunkPos,
+    chunk: &Chunk,
+    pos: VoxelPos,
+    builder: &mut MeshBuilder,
+    node: VertexNode,
+    voxel: Voxel,
+    cell_weights: Vec4,
+    level: usize,
+) -> (Vec3, u32) {
+    let scale = Voxel::SCALE * GameWorld::level_to_scale(level) as f32;
+    let pos_vec = Vec3::new(pos.x as f32, pos.y as f32, pos.z as f32);
+    let node_pos = shift_node_pos(node.pos, voxel.value()) + pos_vec;
+
+    let (offset, axis) = node.edge();
+    let edge_pos = pos + offset;
+    let axes = [
+        VoxelPos::new(1, 0, 0),
+        VoxelPos::new(0, 1, 0),
+        VoxelPos::new(0, 0, 1),
+    ];
+    let next_pos = edge_pos + axes[axis];
+
+    let index = builder.edge_vertex(edge_pos, axis, || {
+        // normal is interpolated along the edge like the position
+        let gradient =
+            voxel_gradient(chunk, edge_pos).lerp(voxel_gradient(chunk, next_pos), voxel.value());
+
+        Vertex {
+            pos: node_pos * scale,
+            normal: -gradient.normalize_or_zero(),
+            color: gen.randomize_color(
+                (chunk_pos * Chunk::SIZE as i64) + GlobalVoxelPos::from(edge_pos),
+                voxel.id().get_color(),
+            ),
+            texture_weights: Vec4::ZERO,
+        }
+    });
+
+    let weights = |pos: VoxelPos| match get_voxel(chunk, pos) {
+        voxel if voxel.is_empty() => cell_weights,
+        voxel => voxel.id().get_texture_weights(),
+    };
+    builder.add_texture_weights(
+        index,
+        weights(edge_pos).lerp(weights(next_pos), voxel.value()),
+    );
+
+    (node_pos, index)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn append_voxel_triangle(
     gen: &WorldGenerator,
     chunk_pos: ChunkPos,
+    chunk: &Chunk,
     pos: VoxelPos,
-    vertices: &mut Vec<Vertex>,
+    builder: &mut MeshBuilder,
     nodes: Nodes,
     points: (VertexNode, VertexNode, VertexNode),
-    scale: f32,
-    with_edges: bool,
+    cell_weights: Vec4,
+    level: usize,
 ) {
     let (a, b, c) = points;
 
@@ -227,23 +349,27 @@ fn append_voxel_triangle(
         return;
     }
 
-    let pos_vec = Vec3::new(pos.x as f32, pos.y as f32, pos.z as f32);
-
-    let a_pos = shift_node_pos(a.pos, a_v.value()) + pos_vec;
-    let b_pos = shift_node_pos(b.pos, b_v.value()) + pos_vec;
-    let c_pos = shift_node_pos(c.pos, c_v.value()) + pos_vec;
-
-    let color = a_v.id().get_color();
-    let color = gen.randomize_color(
-        (chunk_pos * Chunk::SIZE as i64) + GlobalVoxelPos::from(pos),
-        color,
-    );
-
-    let scale = Voxel::SCALE * scale;
-    let normal = append_triangle(vertices, scale, color, a_pos, b_pos, c_pos);
-
-    if with_edges {
-        append_edge(vertices, color, scale, pos, normal, (a_pos, b_pos, c_pos));
+    let [a, b, c] = [(a, a_v), (b, b_v), (c, c_v)].map(|(node, voxel)| {
+        append_node_vertex(
+            gen,
+            chunk_pos,
+            chunk,
+            pos,
+            builder,
+            node,
+            voxel,
+            cell_weights,
+            level,
+        )
+    });
+
+    builder.add_indices([c.1, b.1, a.1]);
+
+    let seams = chunk.seams();
+    if seams != ChunkSeams::NONE {
+        let scale = Voxel::SCALE * GameWorld::level_to_scale(level) as f32;
+        let normal = (c.0 - a.0).cross(b.0 - a.0).normalize();
+        append_edge(builder, scale, pos, normal, (a, b, c), seams);
     }
 }
 
@@ -252,14 +378,18 @@ pub fn append_vertex(
     chunk_pos: ChunkPos,
     pos: VoxelPos,
     chunk: &Chunk,
-    vertices: &mut Vec<Vertex>,
+    builder: &mut MeshBuilder,
     level: usize,
 ) {
-    let scale = GameWorld::level_to_scale(level) as f32;
     let voxels = get_voxels_for_vertex(chunk, pos);
-    let nodes = get_vertex_nodes(voxels);
 
     let triangle_points = TABLE[get_index_by_voxels(voxels)];
+    if triangle_points[0] == -1 {
+        return;
+    }
+
+    let nodes = get_vertex_nodes(voxels);
+    let cell_weights = get_cell_texture_weights(voxels);
 
     let mut triangle_offset = 0;
 
@@ -271,12 +401,13 @@ pub fn append_vertex(
         append_voxel_triangle(
             gen,
             chunk_pos,
+            chunk,
             pos,
-            vertices,
+            builder,
             nodes,
             (a, b, c),
-            scale,
-            level != GameWorld::MAX_DETAIL_LEVEL,
+            cell_weights,
+            level,
         );
 
         triangle_offset += 3;
@@ -1,7 +1,8 @@
+use crate::internal::chunks::brush::Brush;
 use crate::internal::chunks::pointer::ChunkPointer;
-use crate::internal::chunks::Chunk;
+use crate::internal::chunks::{Chunk, MeshBlocks};
 use crate::internal::pos::ChunkPos;
-use crate::plugins::static_mesh::components::Vertex;
+use crate::plugins::game_world::utils::save_error::SaveError;
 use crate::plugins::world_generator::internal::biomes::ChunkBiomes;
 use bevy::prelude::*;
 use crossbeam_channel::Receiver;
@@ -17,7 +18,7 @@ pub struct ChunkComponent {
 pub struct ComputeChunkCreateData {
     pub pos: ChunkPos,
     pub chunk: Chunk,
-    pub vertices: Vec<Vertex>,
+    pub mesh: MeshBlocks,
     pub biomes: ChunkBiomes,
 }
 
@@ -26,22 +27,36 @@ pub struct ComputeChunkUnloadData {
     pub pos: ChunkPos,
     pub level: usize,
     pub chunk: Chunk,
-    pub vertices: Vec<Vertex>,
+    pub mesh: MeshBlocks,
 }
 
 pub struct ComputeChunkDetailedData {
     pub prev_chunk_entity: Entity,
     pub pos: ChunkPos,
     pub level: usize,
-    pub chunks: Vec<(Chunk, Vec<Vertex>)>,
+    pub chunks: Vec<(Chunk, MeshBlocks)>,
+    /// Errors happened while loading saved chunks, failed chunks are generated instead
+    pub errors: Vec<SaveError>,
+}
+
+/// Mesh of the chunk rebuilt in background, e.g. after its seams changed
+pub struct ComputeChunkRedrawData {
+    pub chunk_entity: Entity,
+    pub level: usize,
+    pub mesh: MeshBlocks,
+    /// [`Chunk::mesh_revision`] after the mesh was built, blocks built again since are not replaced
+    pub revision: u64,
 }
 
 #[derive(Component)]
 pub struct ComputeTask<T>(pub Receiver<Box<T>>);
 
+/// Mesh of one block of cells of the chunk, see [`Chunk::generate_mesh`]
 #[derive(Debug, Clone, Copy, Component, Default, Reflect, FromReflect)]
 #[reflect(Component)]
-pub struct ChunkMeshComponent;
+pub struct ChunkMeshComponent {
+    pub block: usize,
+}
 
 #[derive(Debug, Clone, Copy, Component, Default, Reflect, FromReflect)]
 #[reflect(Component)]
@@ -55,6 +70,11 @@ pub struct DetailingChunkComponent;
 #[reflect(Component)]
 pub struct UnloadingChunkComponent;
 
+/// Mesh of the chunk is being rebuilt in background
+#[derive(Debug, Clone, Copy, Component, Default, Reflect, FromReflect)]
+#[reflect(Component)]
+pub struct RedrawingChunkComponent;
+
 #[derive(Debug, Clone, Component, Default, Reflect, FromReflect)]
 #[reflect(Component)]
 pub struct ChunkSmoothMining {
@@ -62,17 +82,18 @@ pub struct ChunkSmoothMining {
     started_at: Duration,
     strength: f32,
     rest: f32,
-    radius: f32,
+    brush: Brush,
 }
 
 impl ChunkSmoothMining {
-    pub fn new(time: &Time, duration: Duration, strength: f32, radius: f32) -> Self {
+    /// Apply the `brush` with its strength spread over the `duration`
+    pub fn new(time: &Time, duration: Duration, brush: Brush) -> Self {
         Self {
             duration,
             started_at: time.elapsed(),
-            strength,
-            rest: strength,
-            radius,
+            strength: brush.strength,
+            rest: brush.strength,
+            brush,
         }
     }
 
@@ -90,8 +111,12 @@ impl ChunkSmoothMining {
         delta_strength
     }
 
-    pub fn get_radius(&self) -> f32 {
-        self.radius
+    /// Brush with the strength of the current frame
+    pub fn get_brush(&self, strength: f32) -> Brush {
+        Brush {
+            strength,
+            ..self.brush
+        }
     }
 
     pub fn is_done(&self) -> bool {
@@ -1,2 +1,5 @@
 pub mod spawn_chunk;
+pub mod update_aprons;
+pub mod update_chunk_mesh;
 pub mod update_objects_parent;
+pub mod update_seams;
@@ -1,28 +1,51 @@
 use crate::{
-    internal::chunks::pointer::ChunkPointer,
+    internal::chunks::{pointer::ChunkPointer, MeshBlocks},
     plugins::{
         chunks::components::{ChunkComponent, ChunkMeshComponent, RealChunkComponent},
         game_world::resources::GameWorld,
         inspector::components::InspectorGroupChunks,
         loading::resources::GameAssets,
-        static_mesh::components::{StaticMeshComponent, Vertex},
+        static_mesh::{
+            components::{IndexedMesh, StaticMeshComponent},
+            materials::TerrainMaterial,
+        },
     },
 };
 use bevy::prelude::*;
 
+/// Spawn mesh and collider of the block of cells, the entity should be a child of the chunk
+pub fn spawn_mesh_block(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    assets: &GameAssets,
+    block: usize,
+    mesh: IndexedMesh,
+    textured: bool,
+) -> Entity {
+    let entity = StaticMeshComponent::spawn(commands, meshes, assets, mesh, textured);
+    commands
+        .entity(entity)
+        .insert(ChunkMeshComponent { block })
+        .insert(Name::new("chunk:mesh"));
+
+    entity
+}
+
+/// Spawn the chunk with an entity for each of its mesh blocks with surface
 pub fn spawn_chunk(
     commands: &mut Commands,
     meshes: &mut Assets<Mesh>,
     assets: &GameAssets,
     world: &mut GameWorld,
     chunk: ChunkPointer,
-    vertices: Vec<Vertex>,
+    mesh: MeshBlocks,
 ) -> Entity {
-    let mesh = StaticMeshComponent::spawn(commands, meshes, assets, vertices);
-    commands
-        .entity(mesh)
-        .insert(ChunkMeshComponent)
-        .insert(Name::new("chunk:mesh"));
+    let textured = TerrainMaterial::is_textured_level(chunk.get_level());
+    let blocks = mesh
+        .into_iter()
+        .filter(|(_, mesh)| !mesh.is_empty())
+        .map(|(block, mesh)| spawn_mesh_block(commands, meshes, assets, block, mesh, textured))
+        .collect::<Vec<_>>();
 
     let chunk_pos_vec = chunk.get_translation();
 
@@ -41,7 +64,7 @@ pub fn spawn_chunk(
         VisibilityBundle::default(),
     ));
 
-    chunk_entity.add_child(mesh);
+    chunk_entity.push_children(&blocks);
 
     let pos = chunk.get_pos();
     let level = chunk.get_level();
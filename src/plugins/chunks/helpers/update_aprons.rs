@@ -0,0 +1,21 @@
+use crate::{internal::pos::ChunkPos, plugins::game_world::resources::GameWorld};
+
+/// Update aprons of chunk at given `pos` at given `level` and of its neighbors,
+/// should be called after the chunk is loaded, changed its detail level or was edited.
+///
+/// Chunks with changed aprons are marked to be redrawn.
+pub fn update_aprons(world: &GameWorld, pos: ChunkPos, level: usize) {
+    let mut chunks = world.get_all_subchunks(pos, level);
+    chunks.append(&mut world.get_face_neighbors(pos, level));
+
+    for chunk in chunks {
+        // neighbors are locked to read their voxels, so the chunk is locked only after that
+        let apron = world.get_chunk_apron(chunk.get_pos(), chunk.get_level());
+        let mut chunk = chunk.lock();
+
+        if *chunk.apron() != apron {
+            chunk.set_apron(apron);
+            chunk.set_need_redraw(true);
+        }
+    }
+}
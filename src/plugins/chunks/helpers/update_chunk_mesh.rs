@@ -0,0 +1,52 @@
+use super::spawn_chunk::spawn_mesh_block;
+use crate::{
+    internal::chunks::MeshBlocks,
+    plugins::{
+        chunks::components::ChunkMeshComponent,
+        loading::resources::GameAssets,
+        static_mesh::{components::StaticMeshComponent, materials::TerrainMaterial},
+    },
+};
+use bevy::{prelude::*, utils::HashMap};
+
+/// Replace meshes of the blocks of the chunk built again, other blocks keep their meshes.
+///
+/// Only blocks with surface have entities, so they are spawned or despawned when
+/// the surface appears in the block or disappears from it.
+#[allow(clippy::too_many_arguments)]
+pub fn update_chunk_mesh(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    assets: &GameAssets,
+    chunk_entity: Entity,
+    children: Option<&Children>,
+    blocks_q: &Query<(Entity, &ChunkMeshComponent, &Handle<Mesh>)>,
+    mesh: MeshBlocks,
+    level: usize,
+) {
+    let textured = TerrainMaterial::is_textured_level(level);
+
+    let spawned = children
+        .into_iter()
+        .flat_map(|children| children.iter())
+        .filter_map(|child| blocks_q.get(*child).ok())
+        .map(|(entity, block, handle)| (block.block, (entity, handle)))
+        .collect::<HashMap<_, _>>();
+
+    for (block, mesh) in mesh {
+        match (spawned.get(&block), mesh.is_empty()) {
+            (Some((entity, handle)), false) => {
+                StaticMeshComponent::update(commands, meshes, *entity, handle, mesh, textured);
+            }
+            (Some((entity, handle)), true) => {
+                meshes.remove(*handle);
+                commands.entity(*entity).despawn_recursive();
+            }
+            (None, false) => {
+                let entity = spawn_mesh_block(commands, meshes, assets, block, mesh, textured);
+                commands.entity(chunk_entity).add_child(entity);
+            }
+            (None, true) => {}
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use crate::{internal::pos::ChunkPos, plugins::game_world::resources::GameWorld};
+
+/// Update seams of chunk at given `pos` at given `level` and of its neighbors,
+/// should be called after the chunk changed its detail level.
+///
+/// Chunks with changed seams are marked to be redrawn.
+pub fn update_seams(world: &GameWorld, pos: ChunkPos, level: usize) {
+    let mut chunks = world.get_all_subchunks(pos, level);
+    chunks.append(&mut world.get_face_neighbors(pos, level));
+
+    for chunk in chunks {
+        let seams = world.get_chunk_seams(chunk.get_pos(), chunk.get_level());
+        let mut chunk = chunk.lock();
+
+        if chunk.seams() != seams {
+            chunk.set_seams(seams);
+            chunk.set_need_redraw(true);
+        }
+    }
+}
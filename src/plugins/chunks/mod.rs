@@ -5,6 +5,7 @@ use self::{
         details::*,
         loading::{handle_region_loaded_system, region_loading_system},
         mine::*,
+        redraw::{handle_redraw_task_system, redraw_chunks_system},
         unload::*,
     },
 };
@@ -32,6 +33,8 @@ impl Plugin for ChunksPlugin {
                     .with_system(region_loading_system)
                     .with_system(mine_system)
                     .with_system(handle_mining_system)
+                    .with_system(redraw_chunks_system.after(handle_mining_system))
+                    .with_system(handle_redraw_task_system)
                     .with_system(unload_system),
             );
     }
@@ -9,10 +9,21 @@ use crate::{
                 ChunkComponent, ComputeChunkDetailedData, ComputeTask, DetailingChunkComponent,
                 RealChunkComponent, UnloadingChunkComponent,
             },
-            helpers::{spawn_chunk::spawn_chunk, update_objects_parent::update_objects_parent},
+            helpers::{
+                spawn_chunk::spawn_chunk, update_aprons::update_aprons,
+                update_objects_parent::update_objects_parent, update_seams::update_seams,
+            },
             resources::ChunkLoadingEnabled,
         },
-        game_world::resources::{meta::GameWorldMeta, GameWorld},
+        game_world::{
+            events::SaveErrorEvent,
+            resources::{
+                meta::GameWorldMeta,
+                save_worker::{SaveWorker, UnsavedData},
+                storage::GameWorldStorage,
+                GameWorld,
+            },
+        },
         inspector::components::InspectorDisabled,
         loading::resources::GameAssets,
         objects::components::GameWorldObject,
@@ -23,6 +34,7 @@ use crate::{
 use bevy::prelude::*;
 use crossbeam_channel::unbounded;
 
+#[allow(clippy::too_many_arguments)]
 fn detail_chunk(
     commands: &mut Commands,
     world: &mut GameWorld,
@@ -30,6 +42,8 @@ fn detail_chunk(
     prev_chunk: ChunkPointer,
     gen: WorldGenerator,
     meta: &GameWorldMeta,
+    storage: &GameWorldStorage,
+    unsaved: UnsavedData,
 ) -> Option<()> {
     let pos = prev_chunk.get_pos();
     let level = prev_chunk.get_level();
@@ -69,11 +83,18 @@ fn detail_chunk(
         .1
         .clone();
 
+    let seams = (0..8)
+        .map(|i| world.get_chunk_seams(pos * 2 + ChunkPos::from_index(i, 2), level + 1))
+        .collect::<Vec<_>>();
+
     let meta = meta.clone();
+    let storage = storage.clone();
 
     let (tx, rx) = unbounded();
 
     std::thread::spawn(move || {
+        let mut errors = Vec::new();
+
         let chunks = (0..8)
             .map(|i| {
                 let sub_pos = VoxelPos::from_index(i, 2);
@@ -82,13 +103,22 @@ fn detail_chunk(
 
                 let level = level + 1;
 
-                let chunk = meta
-                    .load_chunk(pos, level)
+                // chunk could be unloaded recently and not written yet
+                let saved = unsaved.get_chunk(pos, level).or_else(|| {
+                    meta.load_chunk(&storage, pos, level).unwrap_or_else(|err| {
+                        errors.push(err);
+                        None
+                    })
+                });
+
+                let mut chunk = saved
+                    .map(|saved| saved.into_chunk(&gen, biomes.clone(), pos, level))
                     .unwrap_or_else(|| Chunk::generate(&gen, biomes.clone(), pos, level));
+                chunk.set_seams(seams[i]);
 
-                let vertices = chunk.generate_vertices(&gen, pos, level);
+                let mesh = chunk.generate_mesh(&gen, pos, level);
 
-                (chunk, vertices)
+                (chunk, mesh)
             })
             .collect();
 
@@ -97,6 +127,7 @@ fn detail_chunk(
             level,
             chunks,
             prev_chunk_entity: entity,
+            errors,
         };
 
         tx.send(Box::new(data))
@@ -108,10 +139,13 @@ fn detail_chunk(
     Some(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn chunk_details_system(
     mut world: ResMut<GameWorld>,
     gen: Res<WorldGenerator>,
     meta: Res<GameWorldMeta>,
+    storage: Res<GameWorldStorage>,
+    worker: Res<SaveWorker>,
     chunk_load_enabled: Res<ChunkLoadingEnabled>,
     player_transform_q: Query<&Transform, With<PlayerComponent>>,
     mut commands: Commands,
@@ -145,16 +179,21 @@ pub fn chunk_details_system(
                 chunk.chunk.clone(),
                 gen.clone(),
                 &meta,
+                &storage,
+                worker.unsaved(),
             );
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_detailed_chunk_system(
     mut world: ResMut<GameWorld>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     assets: Res<GameAssets>,
+    mut save_errors: EventWriter<SaveErrorEvent>,
+    worker: Res<SaveWorker>,
     tasks_q: Query<(Entity, &mut ComputeTask<ComputeChunkDetailedData>)>,
     mut objects_q: Query<(Entity, &mut Transform, &GlobalTransform), With<GameWorldObject>>,
     chunk_children_q: Query<&Children, With<ChunkComponent>>,
@@ -166,29 +205,37 @@ pub fn spawn_detailed_chunk_system(
                 prev_chunk_entity,
                 level,
                 pos,
+                errors,
             } = *data;
 
+            if !errors.is_empty() {
+                worker.recover();
+            }
+
+            for err in errors {
+                save_errors.send(SaveErrorEvent::new(
+                    format!("Failed to load chunk at {:?}-{}", pos, level + 1),
+                    err,
+                ));
+            }
+
             let spawned_chunks = chunks
                 .into_iter()
                 .enumerate()
-                .map(|(i, (chunk, vertices))| {
+                .map(|(i, (chunk, mesh))| {
                     let sub_pos = ChunkPos::from_index(i, 2);
                     let chunk = ChunkPointer::new(chunk, pos * 2 + sub_pos, level + 1);
 
                     (
                         chunk.clone(),
-                        spawn_chunk(
-                            &mut commands,
-                            &mut meshes,
-                            &assets,
-                            &mut world,
-                            chunk,
-                            vertices,
-                        ),
+                        spawn_chunk(&mut commands, &mut meshes, &assets, &mut world, chunk, mesh),
                     )
                 })
                 .collect::<Vec<_>>();
 
+            update_seams(&world, pos, level);
+            update_aprons(&world, pos, level);
+
             if let Ok(children) = chunk_children_q.get(prev_chunk_entity) {
                 update_objects_parent(children, &mut commands, spawned_chunks, &mut objects_q)
                     .unwrap();
@@ -6,15 +6,28 @@ use crate::{
     plugins::{
         chunks::{
             components::{ComputeChunkCreateData, ComputeTask},
-            helpers::spawn_chunk::spawn_chunk,
+            helpers::{
+                spawn_chunk::spawn_chunk, update_aprons::update_aprons, update_seams::update_seams,
+            },
             resources::ChunkLoadingEnabled,
         },
-        game_world::resources::{meta::GameWorldMeta, GameWorld},
+        game_world::{
+            events::SaveErrorEvent,
+            resources::{
+                meta::GameWorldMeta, save_worker::SaveWorker, storage::GameWorldStorage, GameWorld,
+            },
+        },
         inspector::components::InspectorDisabled,
         loading::resources::GameAssets,
         objects::resources::objects_registry::ObjectsRegistry,
         player::components::PlayerComponent,
-        world_generator::resources::WorldGenerator,
+        world_generator::{
+            internal::{
+                biomes::{caves::spawn_cave_objects, spawn_biomes_objects},
+                structures::spawn_structures_objects,
+            },
+            resources::WorldGenerator,
+        },
     },
 };
 use bevy::prelude::*;
@@ -62,17 +75,19 @@ pub fn region_loading_system(
 
             let biomes = biomes.clone();
             let gen = gen.clone();
+            let seams = world.get_chunk_seams(pos, level);
 
             std::thread::spawn(move || {
                 let mut chunk = Chunk::generate(&gen, biomes.clone(), pos, level);
-                let vertices = chunk.generate_vertices(&gen, pos, level);
+                chunk.set_seams(seams);
+                let mesh = chunk.generate_mesh(&gen, pos, level);
                 chunk.set_need_redraw(false);
 
                 let data = ComputeChunkCreateData {
                     biomes,
                     chunk,
                     pos,
-                    vertices,
+                    mesh,
                 };
 
                 tx.send(Box::new(data)).unwrap();
@@ -87,11 +102,14 @@ pub fn region_loading_system(
 pub fn handle_region_loaded_system(
     mut world: ResMut<GameWorld>,
     meta: Res<GameWorldMeta>,
+    storage: Res<GameWorldStorage>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     assets: Res<GameAssets>,
     gen: Res<WorldGenerator>,
     registry: Res<ObjectsRegistry>,
+    mut save_errors: EventWriter<SaveErrorEvent>,
+    worker: Res<SaveWorker>,
     tasks_q: Query<(Entity, &mut ComputeTask<ComputeChunkCreateData>)>,
 ) {
     for (task_e, ComputeTask(rx)) in tasks_q.iter() {
@@ -100,7 +118,7 @@ pub fn handle_region_loaded_system(
                 biomes,
                 chunk,
                 pos,
-                vertices,
+                mesh,
             } = *data;
 
             let chunk = ChunkPointer::new(chunk, pos, 0);
@@ -108,10 +126,33 @@ pub fn handle_region_loaded_system(
             let region_pos = chunk.get_pos();
             let chunk_offset = region_pos * GameWorld::REGION_SIZE as i64;
 
-            if let Some(loaded_objects) = meta.load_objects(region_pos) {
+            // objects could be unloaded recently and not written yet
+            let loaded_objects = worker.unsaved().get_objects(region_pos).or_else(|| {
+                meta.load_objects(&storage, region_pos)
+                    .unwrap_or_else(|err| {
+                        // objects will be generated again
+                        worker.recover();
+                        save_errors.send(SaveErrorEvent::new(
+                            format!("Failed to load objects of region {:?}", region_pos),
+                            err,
+                        ));
+                        None
+                    })
+            });
+
+            if let Some(loaded_objects) = loaded_objects {
                 loaded_objects.into_iter().for_each(|o| {
                     let chunk_offset = GameWorld::region_pos_to_translation(region_pos);
-                    let spawner = o.to_spawner(&registry, chunk_offset);
+                    let spawner = match o.to_spawner(&registry, chunk_offset) {
+                        Ok(spawner) => spawner,
+                        Err(err) => {
+                            save_errors.send(SaveErrorEvent::new(
+                                format!("Failed to load object in region {:?}", region_pos),
+                                err,
+                            ));
+                            return;
+                        }
+                    };
                     let name = Name::new(format!("object_spawner:{}", spawner.id()));
 
                     commands.spawn((spawner, InspectorDisabled, name));
@@ -119,9 +160,11 @@ pub fn handle_region_loaded_system(
             } else {
                 for i in 0..GameWorld::REGION_VOLUME {
                     let chunk_pos = ChunkPos::from_index(i, GameWorld::REGION_SIZE) + chunk_offset;
-                    gen.get_biome(chunk_pos)
-                        .spawn_objects(&biomes, chunk_pos, &mut commands, &gen);
+                    spawn_biomes_objects(&biomes, chunk_pos, &mut commands, &gen);
+                    spawn_cave_objects(&biomes, chunk_pos, &mut commands, &gen);
                 }
+
+                spawn_structures_objects(&gen, region_pos, &mut commands);
             }
 
             spawn_chunk(
@@ -130,9 +173,12 @@ pub fn handle_region_loaded_system(
                 &assets,
                 &mut world,
                 chunk.clone(),
-                vertices,
+                mesh,
             );
 
+            update_seams(&world, region_pos, 0);
+            update_aprons(&world, region_pos, 0);
+
             commands.entity(task_e).despawn_recursive();
         }
     }
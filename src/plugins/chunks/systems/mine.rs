@@ -1,15 +1,18 @@
 use crate::{
-    internal::chunks::Chunk,
+    internal::chunks::{brush::Brush, Chunk},
     plugins::{
-        chunks::components::{
-            ChunkComponent, ChunkMeshComponent, ChunkSmoothMining, RealChunkComponent,
+        chunks::{
+            components::{
+                ChunkComponent, ChunkMeshComponent, ChunkSmoothMining, RealChunkComponent,
+            },
+            helpers::{update_aprons::update_aprons, update_chunk_mesh::update_chunk_mesh},
         },
         game_world::resources::GameWorld,
+        loading::resources::GameAssets,
         player::{
             events::MineEvent,
-            resources::{look_at::PlayerLookAt, PlayerStats},
+            resources::{look_at::PlayerLookAt, PlayerBrush, PlayerStats},
         },
-        static_mesh::components::StaticMeshComponent,
         world_generator::resources::WorldGenerator,
     },
 };
@@ -37,10 +40,9 @@ fn handle_single_mining(
 
         let chunk_offset = Chunk::pos_to_translation(pos);
 
-        chunk.lock().mine(
+        chunk.lock().apply_brush(
             translation - chunk_offset,
-            modification.get_radius(),
-            delta_str,
+            &modification.get_brush(delta_str),
         );
     }
 
@@ -55,9 +57,12 @@ pub fn handle_mining_system(
     time: Res<Time>,
     mut modify_q: Query<(Entity, &GlobalTransform, &mut ChunkSmoothMining)>,
     mut meshes: ResMut<Assets<Mesh>>,
-    chunks_q: Query<&Children>,
-    chunks_to_redraw_q: Query<(Entity, &ChunkComponent), With<RealChunkComponent>>,
-    meshes_q: Query<(Entity, &Handle<Mesh>), With<StaticMeshComponent>>,
+    assets: Res<GameAssets>,
+    chunks_to_redraw_q: Query<
+        (Entity, &ChunkComponent, Option<&Children>),
+        With<RealChunkComponent>,
+    >,
+    blocks_q: Query<(Entity, &ChunkMeshComponent, &Handle<Mesh>)>,
 ) {
     let mut modified = false;
     for (entity, transform, mut modification) in modify_q.iter_mut() {
@@ -76,8 +81,18 @@ pub fn handle_mining_system(
         return;
     }
 
-    // redraw chunks immediately to prevent mesh flickering
-    for (entity, chunk) in chunks_to_redraw_q.iter() {
+    // edited voxels on the border are behind the faces of the neighbors
+    let edited = chunks_to_redraw_q
+        .iter()
+        .filter(|(_, chunk, _)| chunk.chunk.lock().is_need_redraw())
+        .map(|(_, chunk, _)| chunk.chunk.get_pos())
+        .collect::<Vec<_>>();
+    for pos in edited {
+        update_aprons(&world, pos, GameWorld::MAX_DETAIL_LEVEL);
+    }
+
+    // redraw chunks immediately to prevent mesh flickering, only the edited blocks are uploaded
+    for (entity, chunk, children) in chunks_to_redraw_q.iter() {
         let pos = chunk.chunk.get_pos();
         let mut chunk = chunk.chunk.lock();
 
@@ -85,14 +100,17 @@ pub fn handle_mining_system(
             continue;
         }
 
-        let children = if let Ok(children) = chunks_q.get(entity) {
-            children
-        } else {
-            continue;
-        };
-
-        let vertices = chunk.generate_vertices(&gen, pos, GameWorld::MAX_DETAIL_LEVEL);
-        StaticMeshComponent::update(children, &mut commands, &mut meshes, &meshes_q, vertices);
+        let mesh = chunk.generate_mesh(&gen, pos, GameWorld::MAX_DETAIL_LEVEL);
+        update_chunk_mesh(
+            &mut commands,
+            &mut meshes,
+            &assets,
+            entity,
+            children,
+            &blocks_q,
+            mesh,
+            GameWorld::MAX_DETAIL_LEVEL,
+        );
         chunk.set_need_redraw(false);
     }
 }
@@ -103,6 +121,7 @@ pub fn mine_system(
     mut mine_e: EventReader<MineEvent>,
     chunk_q: Query<&ChunkMeshComponent>,
     player_stats: Res<PlayerStats>,
+    player_brush: Res<PlayerBrush>,
     look_at: Res<PlayerLookAt>,
     windows: Res<Windows>,
 ) {
@@ -122,8 +141,12 @@ pub fn mine_system(
                 ChunkSmoothMining::new(
                     &time,
                     Duration::from_millis(200),
-                    player_stats.mining_strength,
-                    player_stats.mining_radius,
+                    Brush::new(
+                        player_brush.mode,
+                        player_brush.shape,
+                        player_stats.mining_radius,
+                        player_stats.mining_strength,
+                    ),
                 ),
                 TransformBundle::from_transform(Transform::from_translation(look_at.position)),
             ));
@@ -1,4 +1,5 @@
 pub mod details;
 pub mod loading;
 pub mod mine;
+pub mod redraw;
 pub mod unload;
@@ -0,0 +1,116 @@
+use crate::plugins::{
+    chunks::{
+        components::{
+            ChunkComponent, ChunkMeshComponent, ComputeChunkRedrawData, ComputeTask,
+            RedrawingChunkComponent, UnloadingChunkComponent,
+        },
+        helpers::update_chunk_mesh::update_chunk_mesh,
+    },
+    inspector::components::InspectorDisabled,
+    loading::resources::GameAssets,
+    world_generator::resources::WorldGenerator,
+};
+use bevy::prelude::*;
+use crossbeam_channel::unbounded;
+
+/// Redraw chunks marked to be redrawn, e.g. when their neighbor changed detail level.
+///
+/// Meshes are built in background, chunks edited by the player are redrawn immediately by the mining system.
+pub fn redraw_chunks_system(
+    mut commands: Commands,
+    gen: Res<WorldGenerator>,
+    chunks_q: Query<
+        (Entity, &ChunkComponent),
+        (
+            Without<UnloadingChunkComponent>,
+            Without<RedrawingChunkComponent>,
+        ),
+    >,
+) {
+    for (entity, chunk) in chunks_q.iter() {
+        let chunk = chunk.chunk.clone();
+
+        {
+            let mut chunk = chunk.lock();
+            if !chunk.is_need_redraw() {
+                continue;
+            }
+            chunk.set_need_redraw(false);
+        }
+
+        let gen = gen.clone();
+        let (tx, rx) = unbounded();
+
+        std::thread::spawn(move || {
+            let pos = chunk.get_pos();
+            let level = chunk.get_level();
+            let mut chunk = chunk.lock();
+
+            let mesh = chunk.generate_mesh(&gen, pos, level);
+
+            let data = ComputeChunkRedrawData {
+                chunk_entity: entity,
+                level,
+                mesh,
+                revision: chunk.mesh_revision(),
+            };
+
+            tx.send(Box::new(data))
+                .expect("failed to send chunk mesh after redraw");
+        });
+
+        commands.entity(entity).insert(RedrawingChunkComponent);
+        commands.spawn((ComputeTask(rx), InspectorDisabled));
+    }
+}
+
+pub fn handle_redraw_task_system(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    assets: Res<GameAssets>,
+    tasks_q: Query<(Entity, &ComputeTask<ComputeChunkRedrawData>)>,
+    chunks_q: Query<(&ChunkComponent, Option<&Children>), Without<UnloadingChunkComponent>>,
+    blocks_q: Query<(Entity, &ChunkMeshComponent, &Handle<Mesh>)>,
+) {
+    for (e, ComputeTask(rx)) in tasks_q.iter() {
+        if let Ok(data) = rx.try_recv() {
+            commands.entity(e).despawn_recursive();
+
+            let ComputeChunkRedrawData {
+                chunk_entity,
+                level,
+                mesh,
+                revision,
+            } = *data;
+
+            // chunk was unloaded or detailed while its mesh was built
+            let (chunk, children) = match chunks_q.get(chunk_entity) {
+                Ok(chunk) => chunk,
+                Err(_) => continue,
+            };
+
+            commands
+                .entity(chunk_entity)
+                .remove::<RedrawingChunkComponent>();
+
+            // blocks could be built again in the meantime, e.g. after mining
+            let mesh = {
+                let chunk = chunk.chunk.lock();
+                mesh.into_iter()
+                    .filter(|(block, _)| chunk.is_mesh_block_current(*block, revision))
+                    .collect()
+            };
+
+            update_chunk_mesh(
+                &mut commands,
+                &mut meshes,
+                &assets,
+                chunk_entity,
+                children,
+                &blocks_q,
+                mesh,
+                level,
+            );
+        }
+    }
+}
@@ -6,10 +6,13 @@ use crate::{
                 ChunkComponent, ComputeChunkUnloadData, ComputeTask, DetailingChunkComponent,
                 UnloadingChunkComponent,
             },
-            helpers::{spawn_chunk::spawn_chunk, update_objects_parent::update_objects_parent},
+            helpers::{
+                spawn_chunk::spawn_chunk, update_aprons::update_aprons,
+                update_objects_parent::update_objects_parent, update_seams::update_seams,
+            },
             resources::ChunkLoadingEnabled,
         },
-        game_world::resources::{meta::GameWorldMeta, GameWorld},
+        game_world::resources::{meta::GameWorldMeta, save_worker::SaveWorker, GameWorld},
         inspector::components::InspectorDisabled,
         loading::resources::GameAssets,
         objects::components::GameWorldObject,
@@ -24,7 +27,7 @@ use crossbeam_channel::unbounded;
 fn unload_chunk(
     commands: &mut Commands,
     world: &mut GameWorld,
-    meta: &GameWorldMeta,
+    worker: &SaveWorker,
     gen: WorldGenerator,
     chunk_e: Entity,
     chunk: ChunkPointer,
@@ -46,7 +49,7 @@ fn unload_chunk(
             })
             .collect::<Vec<_>>();
 
-        meta.save_objects(pos, objects);
+        worker.save_objects(pos, objects);
 
         world
             .remove_region(pos)
@@ -59,19 +62,11 @@ fn unload_chunk(
     let parent_level = level - 1;
 
     // save subchunks
-    {
-        let start = std::time::Instant::now();
-        let saved_chunks_count = meta.save_chunks(world, parent_pos, parent_level);
-        if saved_chunks_count > 0 {
-            info!(
-                "Saved {} chunks at {:?}-{} in {}ms",
-                saved_chunks_count,
-                parent_pos,
-                parent_level,
-                start.elapsed().as_millis()
-            );
-        }
-    }
+    worker.save_chunks(GameWorldMeta::snapshot_chunks(
+        world,
+        parent_pos,
+        parent_level,
+    ));
 
     let chunk_to_simplify = if let Some(chunk) = world.get_chunk_mut(parent_pos, parent_level) {
         chunk
@@ -101,20 +96,23 @@ fn unload_chunk(
         .1
         .clone();
 
+    let seams = world.get_chunk_seams(parent_pos, parent_level);
+
     let (tx, rx) = unbounded();
 
     std::thread::spawn(move || {
-        let chunk = if let Some(voxels) = old_chunk.simplify() {
+        let mut chunk = if let Some(voxels) = old_chunk.simplify() {
             Chunk::generate_with_modified(voxels, &gen, biomes, parent_pos, parent_level)
         } else {
             Chunk::generate(&gen, biomes, parent_pos, parent_level)
         };
-        let vertices = chunk.generate_vertices(&gen, parent_pos, parent_level);
+        chunk.set_seams(seams);
+        let mesh = chunk.generate_mesh(&gen, parent_pos, parent_level);
 
         let data = ComputeChunkUnloadData {
             unloaded_chunks,
             chunk,
-            vertices,
+            mesh,
             pos: parent_pos,
             level: parent_level,
         };
@@ -143,7 +141,7 @@ pub fn handle_unload_task_system(
 
             let ComputeChunkUnloadData {
                 chunk,
-                vertices,
+                mesh,
                 pos,
                 level,
                 unloaded_chunks,
@@ -156,9 +154,12 @@ pub fn handle_unload_task_system(
                 &assets,
                 &mut world,
                 chunk_pointer.clone(),
-                vertices,
+                mesh,
             );
 
+            update_seams(&world, pos, level);
+            update_aprons(&world, pos, level);
+
             for entity in unloaded_chunks {
                 if let Ok(children) = chunk_children_q.get(entity) {
                     if let Err(err) = update_objects_parent(
@@ -196,7 +197,7 @@ pub fn unload_system(
     player_transform_q: Query<&Transform, With<PlayerComponent>>,
     mut world: ResMut<GameWorld>,
     gen: Res<WorldGenerator>,
-    meta: Res<GameWorldMeta>,
+    worker: Res<SaveWorker>,
     chunk_load_enabled: Res<ChunkLoadingEnabled>,
 ) {
     if !chunk_load_enabled.0 {
@@ -218,7 +219,7 @@ pub fn unload_system(
             unload_chunk(
                 &mut commands,
                 &mut world,
-                &meta,
+                &worker,
                 gen.clone(),
                 entity,
                 chunk.chunk.clone(),
@@ -0,0 +1,18 @@
+use super::utils::save_error::SaveError;
+
+/// Sent when the world failed to save or load, shown to the player
+#[derive(Debug)]
+pub struct SaveErrorEvent {
+    /// What was being done when the error happened
+    pub action: String,
+    pub error: SaveError,
+}
+
+impl SaveErrorEvent {
+    pub fn new(action: impl Into<String>, error: SaveError) -> Self {
+        Self {
+            action: action.into(),
+            error,
+        }
+    }
+}
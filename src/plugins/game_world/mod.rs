@@ -1,10 +1,17 @@
 use self::{
     components::WorldSun,
-    resources::{meta::GameWorldMeta, GameWorld},
+    events::SaveErrorEvent,
+    resources::{
+        meta::GameWorldMeta, save_worker::SaveWorker, storage::GameWorldStorage, GameWorld,
+    },
     systems::{
         create_world::{start_world_creating, world_creating_progress},
         load_world::world_loading_system,
-        save::save_system,
+        save::{
+            backup_system, flush_save_worker_on_exit_system, save_system, start_save_worker_system,
+            stop_save_worker_system,
+        },
+        save_errors::show_save_errors_system,
         setup_world::setup_world,
         sun_to_player::move_sun_to_player,
     },
@@ -13,8 +20,10 @@ use crate::states::game_state::GameState;
 use bevy::{pbr::DirectionalLightShadowMap, prelude::*};
 
 pub mod components;
+pub mod events;
 pub mod resources;
 mod systems;
+pub mod utils;
 
 pub struct GameWorldPlugin;
 
@@ -23,11 +32,16 @@ impl Plugin for GameWorldPlugin {
         app.add_system_set(
             SystemSet::on_enter(GameState::WorldCreating).with_system(start_world_creating),
         )
+        .add_system_set(
+            SystemSet::on_enter(GameState::InGame).with_system(start_save_worker_system),
+        )
         .add_system_set(
             SystemSet::on_update(GameState::InGame)
                 .with_system(move_sun_to_player)
-                .with_system(save_system),
+                .with_system(save_system)
+                .with_system(backup_system),
         )
+        .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(stop_save_worker_system))
         .add_system_set(
             SystemSet::on_update(GameState::WorldCreating).with_system(world_creating_progress),
         )
@@ -39,7 +53,12 @@ impl Plugin for GameWorldPlugin {
         .register_type::<WorldSun>()
         .register_type::<GameWorldMeta>()
         .register_type::<GameWorld>()
+        .add_event::<SaveErrorEvent>()
+        .add_system(show_save_errors_system)
+        .add_system_to_stage(CoreStage::Last, flush_save_worker_on_exit_system)
         .add_startup_system(setup_world)
-        .insert_resource(GameWorldMeta::default());
+        .insert_resource(GameWorldMeta::default())
+        .insert_resource(GameWorldStorage::default())
+        .insert_resource(SaveWorker::default());
     }
 }
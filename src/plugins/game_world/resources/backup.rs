@@ -0,0 +1,261 @@
+use super::{meta::GameWorldMeta, storage::GameWorldStorage};
+use crate::plugins::game_world::utils::{
+    atomic_file::TMP_EXTENSION,
+    save_error::SaveError,
+    save_format::{SaveHeader, SaveKind},
+};
+use bevy::prelude::*;
+use std::{
+    io::{BufWriter, Seek, SeekFrom, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Files of the world with keys relative to the world directory
+type BackupFiles = Vec<(String, Vec<u8>)>;
+
+/// Snapshot of all files of the world
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorldBackup {
+    pub key: String,
+    /// Milliseconds since unix epoch
+    pub created_at: u64,
+}
+
+impl WorldBackup {
+    const EXTENSION: &str = "backup";
+
+    fn from_key(key: String) -> Option<Self> {
+        let file_name = key.rsplit('/').next()?;
+        let created_at = file_name
+            .strip_suffix(Self::EXTENSION)?
+            .strip_suffix('.')?
+            .parse()
+            .ok()?;
+
+        Some(Self { key, created_at })
+    }
+
+    pub fn age(&self) -> Duration {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        now.saturating_sub(Duration::from_millis(self.created_at))
+    }
+}
+
+impl GameWorldMeta {
+    const BACKUPS_DIR: &str = "backups";
+
+    /// Count of backups kept for each world, older ones are removed after a new backup is made
+    pub const BACKUPS_TO_KEEP: usize = 10;
+
+    fn get_backups_path(&self) -> String {
+        format!("{}/{}/", Self::BACKUPS_DIR, self.id)
+    }
+
+    /// Pack all files of the world into a single compressed file.
+    ///
+    /// Files are read and compressed one by one, so the world is never loaded in memory at once.
+    /// While the world is played it is called by the save worker after queued data is written.
+    pub fn backup(&self, storage: &GameWorldStorage) -> Result<WorldBackup, SaveError> {
+        let start = std::time::Instant::now();
+        let keys = self.get_world_keys(storage)?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        // backups made in the same millisecond should not overwrite each other
+        let created_at = match self.get_backups(storage)?.first() {
+            Some(last) => now.max(last.created_at + 1),
+            None => now,
+        };
+
+        let backup = WorldBackup {
+            key: format!(
+                "{}{}.{}",
+                self.get_backups_path(),
+                created_at,
+                WorldBackup::EXTENSION
+            ),
+            created_at,
+        };
+
+        // archive is written to the temporary file, so unfinished backup is never listed
+        let tmp_key = format!(
+            "{}{}.{}",
+            self.get_backups_path(),
+            WorldBackup::EXTENSION,
+            TMP_EXTENSION
+        );
+
+        let size = self
+            .write_backup(storage, &keys, &tmp_key)
+            .and_then(|size| storage.rename(&tmp_key, &backup.key).map(|_| size))
+            .map_err(|err| {
+                storage.delete(&tmp_key).ok();
+                err.in_file(&backup.key)
+            })?;
+
+        info!(
+            "World {} backed up to {} ({} files, {} bytes) in {}ms",
+            self.id,
+            backup.key,
+            keys.len(),
+            size,
+            start.elapsed().as_millis()
+        );
+
+        Ok(backup)
+    }
+
+    /// Write files at `keys` to the archive at `archive_key`, returns size of the archive.
+    ///
+    /// Archive has the same layout as encoded [`BackupFiles`], so it is read with `decode`.
+    fn write_backup(
+        &self,
+        storage: &GameWorldStorage,
+        keys: &[String],
+        archive_key: &str,
+    ) -> Result<u64, SaveError> {
+        let root = self.get_path("");
+
+        // left after a crash during the previous backup
+        storage.delete(archive_key)?;
+
+        let mut file = storage.open_or_create(archive_key)?;
+        file.write_all(&SaveHeader::current(SaveKind::Backup, true).encode())?;
+
+        let mut encoder = zstd::stream::Encoder::new(BufWriter::new(&mut file), 0)?;
+
+        bincode::serialize_into(&mut encoder, &(keys.len() as u64)).map_err(SaveError::Encode)?;
+
+        for key in keys {
+            let data = storage.get(key)?.ok_or_else(|| {
+                SaveError::Corrupted(format!("{} was removed during the backup", key))
+            })?;
+            let relative = key.strip_prefix(&root).unwrap_or(key);
+
+            bincode::serialize_into(&mut encoder, &(relative, data)).map_err(SaveError::Encode)?;
+        }
+
+        encoder.finish()?.flush()?;
+        file.sync()?;
+
+        Ok(file.seek(SeekFrom::End(0))?)
+    }
+
+    /// Get all backups of the world, newest first
+    pub fn get_backups(&self, storage: &GameWorldStorage) -> Result<Vec<WorldBackup>, SaveError> {
+        let mut backups = storage
+            .list(&self.get_backups_path())?
+            .into_iter()
+            .filter_map(WorldBackup::from_key)
+            .collect::<Vec<_>>();
+
+        backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+
+        Ok(backups)
+    }
+
+    /// Remove all backups except for `keep` newest ones, returns count of removed backups
+    pub fn prune_backups(
+        &self,
+        storage: &GameWorldStorage,
+        keep: usize,
+    ) -> Result<usize, SaveError> {
+        let backups = self.get_backups(storage)?;
+
+        let mut removed = 0;
+        for backup in backups.iter().skip(keep) {
+            storage.delete(&backup.key)?;
+            removed += 1;
+        }
+
+        Ok(removed)
+    }
+
+    /// Make a backup and remove the old ones
+    pub fn backup_and_prune(&self, storage: &GameWorldStorage) -> Result<WorldBackup, SaveError> {
+        let backup = self.backup(storage)?;
+        self.prune_backups(storage, Self::BACKUPS_TO_KEEP)?;
+
+        Ok(backup)
+    }
+
+    /// Replace all files of the world with the files from the `backup`.
+    ///
+    /// Current state of the world is backed up first, so restore can be undone.
+    /// Should not be called while the world is loaded.
+    pub fn restore_backup(
+        &self,
+        storage: &GameWorldStorage,
+        backup: &WorldBackup,
+    ) -> Result<(), SaveError> {
+        let bytes = storage
+            .get(&backup.key)?
+            .ok_or_else(|| SaveError::Corrupted("backup doesn't exist".to_string()))
+            .map_err(|err| err.in_file(&backup.key))?;
+
+        let files: BackupFiles = self
+            .decode(&bytes, SaveKind::Backup, true)
+            .map_err(|err| err.in_file(&backup.key))?;
+
+        self.backup(storage)?;
+
+        for key in self.get_world_keys(storage)? {
+            storage.delete(&key)?;
+        }
+
+        for (path, data) in files.iter() {
+            storage.put(&self.get_path(path), data)?;
+        }
+
+        info!(
+            "World {} restored from {} ({} files)",
+            self.id,
+            backup.key,
+            files.len()
+        );
+
+        Ok(())
+    }
+}
+
+#[test]
+fn backup_and_restore() {
+    use crate::plugins::{
+        game_world::utils::storage::MemoryStorage, player::components::save::PlayerSave,
+    };
+
+    let storage = GameWorldStorage::new(MemoryStorage::new());
+
+    let mut meta = GameWorldMeta::default();
+    meta.reset();
+    meta.save_self(&storage).unwrap();
+
+    let backup = meta.backup(&storage).unwrap();
+    assert_eq!(meta.get_backups(&storage).unwrap(), vec![backup.clone()]);
+
+    meta.save_player(&storage, PlayerSave::default()).unwrap();
+    assert!(meta.load_player(&storage).unwrap().is_some());
+
+    meta.restore_backup(&storage, &backup).unwrap();
+    assert!(
+        meta.load_player(&storage).unwrap().is_none(),
+        "Files created after the backup should be removed"
+    );
+    assert_eq!(GameWorldMeta::get_saves(&storage).unwrap().len(), 1);
+
+    let backups = meta.get_backups(&storage).unwrap();
+    assert_eq!(backups.len(), 2, "State before restore should be backed up");
+    assert_eq!(backups[1], backup);
+
+    assert_eq!(meta.prune_backups(&storage, 1).unwrap(), 1);
+    assert_eq!(
+        meta.get_backups(&storage).unwrap(),
+        vec![backups[0].clone()]
+    );
+}
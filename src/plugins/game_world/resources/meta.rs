@@ -1,20 +1,99 @@
-use super::GameWorld;
+use super::{storage::GameWorldStorage, GameWorld};
 use crate::internal::chunks::pointer::ChunkPointer;
-use crate::internal::{chunks::Chunk, pos::ChunkPos};
+use crate::internal::{chunks::sparse::SparseChunk, pos::ChunkPos};
+use crate::plugins::game_world::utils::atomic_file;
+use crate::plugins::game_world::utils::region_file::{RegionChunkKey, RegionFile, RegionLocks};
+use crate::plugins::game_world::utils::save_error::SaveError;
+use crate::plugins::game_world::utils::save_format::{SaveHeader, SaveKind, SaveMigrations};
+use crate::plugins::game_world::utils::storage::StorageFile;
 use crate::plugins::objects::utils::object_save::GameWorldObjectSave;
 use crate::plugins::player::components::save::PlayerSave;
+use crate::plugins::world_generator::internal::biomes::{
+    caves::data::DataCaveBiome, data::BiomeSource,
+};
+use crate::plugins::world_generator::resources::settings::{
+    GeneratorParams, GeneratorSettings, GeneratorVersion,
+};
 use crate::plugins::world_generator::resources::WorldSeed;
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 use bevy_inspector_egui::InspectorOptions;
 use bevy_reflect::Uuid;
 use pariter::IteratorExt;
 use serde::{Deserialize, Serialize};
-use std::borrow::BorrowMut;
 use std::collections::LinkedList;
-use std::{
-    fs,
-    io::{BufReader, BufWriter, Write},
-};
+use std::io::{BufReader, BufWriter, Read};
+use std::sync::Arc;
+
+/// Modified voxels of the chunk taken at the moment of saving
+#[derive(Clone)]
+pub struct ChunkSnapshot {
+    chunk: ChunkPointer,
+    data: Arc<SparseChunk>,
+}
+
+impl ChunkSnapshot {
+    pub fn get_pos(&self) -> ChunkPos {
+        self.chunk.get_pos()
+    }
+
+    pub fn get_level(&self) -> usize {
+        self.chunk.get_level()
+    }
+
+    pub fn get_data(&self) -> &Arc<SparseChunk> {
+        &self.data
+    }
+}
+
+/// Meta saved before the biome definitions were pinned in the generator settings
+#[derive(Serialize, Deserialize)]
+struct MetaV2 {
+    name: String,
+    seed: WorldSeed,
+    id: String,
+    generator_version: GeneratorVersion,
+    generator_params: GeneratorParams,
+}
+
+/// Meta saved before the terrain graph was pinned in the generator settings
+#[derive(Serialize, Deserialize)]
+struct MetaV3 {
+    name: String,
+    seed: WorldSeed,
+    id: String,
+    generator_version: GeneratorVersion,
+    generator_params: GeneratorParams,
+    generator_biomes: Vec<BiomeSource>,
+}
+
+/// Meta saved before the cave biome definitions were pinned in the generator settings
+#[derive(Serialize, Deserialize)]
+struct MetaV4 {
+    name: String,
+    seed: WorldSeed,
+    id: String,
+    generator_version: GeneratorVersion,
+    generator_params: GeneratorParams,
+    generator_biomes: Vec<BiomeSource>,
+    generator_terrain: Option<String>,
+}
+
+/// Count and total size of saved chunks at one detail level
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LevelStats {
+    pub chunks: usize,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RegionStats {
+    pub region_pos: ChunkPos,
+    pub file_size: u64,
+    pub free_size: u64,
+    /// Stats of chunks at each detail level
+    pub levels: Vec<LevelStats>,
+}
 
 #[derive(Resource, Debug, Clone, Reflect, Default, InspectorOptions, Serialize, Deserialize)]
 #[reflect(Resource)]
@@ -22,65 +101,230 @@ pub struct GameWorldMeta {
     pub name: String,
     pub seed: WorldSeed,
     pub id: String,
+    /// Generator the world was created with, chunks are always generated with it
+    pub generator: GeneratorSettings,
+    #[serde(skip)]
+    #[reflect(ignore)]
+    region_locks: RegionLocks,
+    #[serde(skip)]
+    #[reflect(ignore)]
+    migrations: Arc<SaveMigrations>,
+    /// Set by [`GameWorldMeta::get_saves`] if the world was saved by an older version of the game
+    #[serde(skip)]
+    pub needs_upgrade: bool,
+    /// Files of the world should be fully checked by [`GameWorldMeta::recover`] in the save worker
+    #[serde(skip)]
+    pub needs_recovery: bool,
 }
 
 impl GameWorldMeta {
     pub fn reset(&mut self) {
         self.name = "New World".to_string();
         self.id = Uuid::new_v4().to_string();
+        self.generator = GeneratorSettings::current();
     }
 
-    const SAVE_DIR: &str = "saves";
+    /// Upgrade meta saved with 32-bit seed and without generator settings
+    pub fn migrate_from_v1(data: Vec<u8>) -> Result<Vec<u8>, String> {
+        #[derive(Deserialize)]
+        struct MetaV1 {
+            name: String,
+            seed: u32,
+            id: String,
+        }
+
+        let meta: MetaV1 = bincode::deserialize(&data).map_err(|err| err.to_string())?;
+        let generator = GeneratorSettings::legacy();
+
+        let meta = MetaV2 {
+            name: meta.name,
+            seed: meta.seed as WorldSeed,
+            id: meta.id,
+            generator_version: generator.version,
+            generator_params: generator.params,
+        };
 
-    fn get_path(&self, path: &str) -> String {
-        format!("{}/{}/{}", Self::SAVE_DIR, self.id, path)
+        bincode::serialize(&meta).map_err(|err| err.to_string())
     }
 
-    pub fn save<T: Serialize>(&self, data: &T, path: &str, compress: bool) {
-        let path = self.get_path(path);
+    /// Upgrade meta saved without biome definitions, they are pinned when the world is loaded
+    pub fn migrate_from_v2(data: Vec<u8>) -> Result<Vec<u8>, String> {
+        let meta: MetaV2 = bincode::deserialize(&data).map_err(|err| err.to_string())?;
+
+        let meta = MetaV3 {
+            name: meta.name,
+            seed: meta.seed,
+            id: meta.id,
+            generator_version: meta.generator_version,
+            generator_params: meta.generator_params,
+            generator_biomes: Vec::new(),
+        };
+
+        bincode::serialize(&meta).map_err(|err| err.to_string())
+    }
+
+    /// Upgrade meta saved without terrain graph, such worlds use the default terrain
+    pub fn migrate_from_v3(data: Vec<u8>) -> Result<Vec<u8>, String> {
+        let meta: MetaV3 = bincode::deserialize(&data).map_err(|err| err.to_string())?;
+
+        let meta = MetaV4 {
+            name: meta.name,
+            seed: meta.seed,
+            id: meta.id,
+            generator_version: meta.generator_version,
+            generator_params: meta.generator_params,
+            generator_biomes: meta.generator_biomes,
+            generator_terrain: None,
+        };
+
+        bincode::serialize(&meta).map_err(|err| err.to_string())
+    }
+
+    /// Upgrade meta saved without cave biome definitions.
+    ///
+    /// Worlds with cave biomes were generated with the built-in ones, so they are pinned.
+    pub fn migrate_from_v4(data: Vec<u8>) -> Result<Vec<u8>, String> {
+        let meta: MetaV4 = bincode::deserialize(&data).map_err(|err| err.to_string())?;
+
+        let cave_biomes = match meta.generator_version {
+            v if v >= GeneratorSettings::V4 => DataCaveBiome::builtin_sources(),
+            _ => Vec::new(),
+        };
 
+        let meta = GameWorldMeta {
+            name: meta.name,
+            seed: meta.seed,
+            id: meta.id,
+            generator: GeneratorSettings {
+                version: meta.generator_version,
+                params: meta.generator_params,
+                biomes: meta.generator_biomes,
+                cave_biomes,
+                terrain: meta.generator_terrain,
+            },
+            ..Default::default()
+        };
+
+        bincode::serialize(&meta).map_err(|err| err.to_string())
+    }
+
+    /// Get storage key of the world file at `path`
+    pub(super) fn get_path(&self, path: &str) -> String {
+        format!("{}/{}", self.id, path)
+    }
+
+    pub(super) fn encode<T: Serialize>(
+        data: &T,
+        kind: SaveKind,
+        compress: bool,
+    ) -> Result<Vec<u8>, SaveError> {
         let mut bytes = Vec::new();
         {
             let mut writer = BufWriter::new(&mut bytes);
-            bincode::serialize_into(&mut writer, data).unwrap();
+            bincode::serialize_into(&mut writer, data).map_err(SaveError::Encode)?;
         }
 
-        let compressed = if compress {
-            let mut compressed = Vec::new();
-            zstd::stream::copy_encode(&mut &bytes[..], &mut compressed, 0).unwrap();
-            compressed
+        let mut result = SaveHeader::current(kind, compress).encode().to_vec();
+
+        if compress {
+            zstd::stream::copy_encode(&mut &bytes[..], &mut result, 0)?;
         } else {
-            bytes
-        };
+            result.append(&mut bytes);
+        }
 
-        // create directory if not exists
-        let dir = std::path::Path::new(&path).parent().unwrap();
-        fs::create_dir_all(dir).unwrap();
-        let file = fs::File::create(path).unwrap();
-        let mut writer = BufWriter::new(file);
+        Ok(result)
+    }
 
-        writer.write_all(&compressed).unwrap();
+    /// Decode data written by [`GameWorldMeta::encode`] and upgrade it to the current version.
+    ///
+    /// `compressed` is only used for files written before save header was introduced.
+    pub(super) fn decode<T: for<'de> serde::Deserialize<'de>>(
+        &self,
+        bytes: &[u8],
+        kind: SaveKind,
+        compressed: bool,
+    ) -> Result<T, SaveError> {
+        self.decode_versioned(bytes, kind, compressed)
+            .map(|(data, _)| data)
     }
 
-    fn load<T: for<'de> serde::Deserialize<'de>>(&self, path: &str, compressed: bool) -> Option<T> {
-        let file_path = self.get_path(path);
+    /// Same as [`GameWorldMeta::decode`], also returns `true` if the data was written in an older format
+    fn decode_versioned<T: for<'de> serde::Deserialize<'de>>(
+        &self,
+        bytes: &[u8],
+        kind: SaveKind,
+        compressed: bool,
+    ) -> Result<(T, bool), SaveError> {
+        let (header, payload) = SaveHeader::decode(bytes)
+            .unwrap_or_else(|| (SaveHeader::legacy(kind, compressed), bytes));
 
-        let file = fs::File::open(file_path.clone()).ok()?;
-        let mut reader = BufReader::new(file);
+        if header.kind != kind {
+            return Err(SaveError::Corrupted(format!(
+                "expected {:?} but got {:?}",
+                kind, header.kind
+            )));
+        }
 
-        let c = if compressed {
+        let payload = if header.compressed {
             let mut decompressed = Vec::new();
-            zstd::stream::copy_decode(&mut reader, &mut decompressed).unwrap();
+            zstd::stream::copy_decode(payload, &mut decompressed)
+                .map_err(|err| SaveError::Corrupted(err.to_string()))?;
+            decompressed
+        } else {
+            payload.to_vec()
+        };
 
-            let reader = BufReader::new(&decompressed[..]);
+        let payload = self.migrations.migrate(header, payload)?;
 
-            bincode::deserialize_from(reader)
-        } else {
-            bincode::deserialize_from(reader)
+        bincode::deserialize_from(BufReader::new(&payload[..]))
+            .map(|data| (data, header.is_outdated()))
+            .map_err(SaveError::Decode)
+    }
+
+    pub fn save<T: Serialize>(
+        &self,
+        storage: &GameWorldStorage,
+        data: &T,
+        path: &str,
+        kind: SaveKind,
+        compress: bool,
+    ) -> Result<(), SaveError> {
+        let path = self.get_path(path);
+
+        Self::encode(data, kind, compress)
+            .and_then(|bytes| storage.put(&path, &bytes))
+            .map_err(|err| err.in_file(path))
+    }
+
+    /// Load data from the file at `path`, returns `None` if the file doesn't exist.
+    ///
+    /// Files written in an older format are rewritten in the current one.
+    fn load<T: Serialize + for<'de> serde::Deserialize<'de>>(
+        &self,
+        storage: &GameWorldStorage,
+        path: &str,
+        kind: SaveKind,
+        compressed: bool,
+    ) -> Result<Option<T>, SaveError> {
+        let file_path = self.get_path(path);
+
+        let bytes = match storage.get(&file_path)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+
+        let (data, outdated) = self
+            .decode_versioned(&bytes, kind, compressed)
+            .map_err(|err| err.in_file(&file_path))?;
+
+        if outdated {
+            info!("Upgrading {} to the current save format", file_path);
+            if let Err(err) = self.save(storage, &data, path, kind, compressed) {
+                warn!("Failed to upgrade {}: {}", file_path, err);
+            }
         }
-        .unwrap_or_else(|err| panic!("Can't load file {}: {}", file_path, err));
 
-        Some(c)
+        Ok(Some(data))
     }
 
     /// Get save path for region at given position
@@ -91,8 +335,24 @@ impl GameWorldMeta {
         )
     }
 
+    /// Get path of the file that contains all chunks of the region
+    fn get_region_file_path(region_pos: ChunkPos) -> String {
+        format!("{}chunks.region", Self::get_region_path(region_pos))
+    }
+
+    /// Get region position and key inside of the region file for chunk at given `pos` at given `level`
+    fn get_chunk_key(pos: ChunkPos, level: usize) -> (ChunkPos, RegionChunkKey) {
+        let region_pos = GameWorld::level_pos_to_level_pos(pos, level, 0);
+
+        let in_region_pos = pos - GameWorld::level_pos_to_level_pos(region_pos, 0, level);
+
+        (region_pos, RegionChunkKey::new(in_region_pos.into(), level))
+    }
+
     /// Get save path for chunk at given `pos` at given `level`
-    fn get_chunk_path(pos: ChunkPos, level: usize) -> String {
+    ///
+    /// Used by saves created before chunks were packed into region files.
+    fn get_legacy_chunk_path(pos: ChunkPos, level: usize) -> String {
         let region_pos = GameWorld::level_pos_to_level_pos(pos, level, 0);
 
         let region_path = Self::get_region_path(region_pos);
@@ -110,30 +370,179 @@ impl GameWorldMeta {
         format!("{}objects", region_path)
     }
 
-    fn save_chunk(&self, chunk: ChunkPointer) -> bool {
-        let pos = chunk.get_pos();
-        let level = chunk.get_level();
+    /// Open region file for writing, creating it if it doesn't exist
+    fn open_region_file(
+        &self,
+        storage: &GameWorldStorage,
+        region_pos: ChunkPos,
+    ) -> Result<RegionFile<StorageFile>, SaveError> {
+        let path = self.get_path(&Self::get_region_file_path(region_pos));
+
+        let file = storage.open_or_create(&path)?;
 
-        let path = Self::get_chunk_path(pos, level);
+        RegionFile::open(file).map_err(|err| SaveError::from(err).in_file(path))
+    }
 
-        let mut chunk = chunk.lock();
+    /// Rewrite region file without unused space
+    fn compact_region_file(
+        &self,
+        storage: &GameWorldStorage,
+        region_pos: ChunkPos,
+        mut region: RegionFile<StorageFile>,
+    ) -> Result<(), SaveError> {
+        let path = self.get_path(&Self::get_region_file_path(region_pos));
 
-        if !chunk.is_need_save() {
-            return false;
+        let start = std::time::Instant::now();
+        let prev_size = region.file_size();
+
+        let mut compacted = Vec::new();
+        region
+            .compact_into(&mut compacted)
+            .map_err(|err| SaveError::from(err).in_file(&path))?;
+        drop(region);
+
+        storage.put(&path, &compacted)?;
+
+        info!(
+            "Region {:?} compacted from {} to {} bytes in {}ms",
+            region_pos,
+            prev_size,
+            compacted.len(),
+            start.elapsed().as_millis()
+        );
+
+        Ok(())
+    }
+
+    /// Write encoded chunks to the region file
+    fn write_region_chunks(
+        &self,
+        storage: &GameWorldStorage,
+        region_pos: ChunkPos,
+        blobs: &[(RegionChunkKey, Vec<u8>)],
+    ) -> Result<(), SaveError> {
+        let lock = self.region_locks.get(region_pos);
+        let _guard = lock.lock().unwrap();
+
+        let mut region = self.open_region_file(storage, region_pos)?;
+
+        let path = self.get_path(&Self::get_region_file_path(region_pos));
+
+        blobs
+            .iter()
+            .try_for_each(|(key, data)| region.write(*key, data))
+            .and_then(|_| region.commit())
+            .map_err(|err| SaveError::from(err).in_file(&path))?;
+
+        if region.need_compact() {
+            self.compact_region_file(storage, region_pos, region)?;
         }
 
-        let chunk: &mut Chunk = chunk.borrow_mut();
+        Ok(())
+    }
+
+    /// Save snapshots of chunks of one region, returns count of saved chunks
+    fn save_region_chunks(
+        &self,
+        storage: &GameWorldStorage,
+        region_pos: ChunkPos,
+        snapshots: Vec<ChunkSnapshot>,
+    ) -> Result<usize, SaveError> {
+        let result = snapshots
+            .iter()
+            .map(|snapshot| {
+                let (_, key) =
+                    Self::get_chunk_key(snapshot.chunk.get_pos(), snapshot.chunk.get_level());
 
-        chunk.set_need_save(false);
+                Ok((
+                    key,
+                    Self::encode(snapshot.data.as_ref(), SaveKind::Chunk, true)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, SaveError>>()
+            .and_then(|blobs| self.write_region_chunks(storage, region_pos, &blobs));
+
+        if let Err(err) = result {
+            // try again on the next save
+            for snapshot in snapshots {
+                snapshot.chunk.lock().set_need_save(true);
+            }
 
-        self.save(chunk, &path, true);
+            return Err(err);
+        }
 
-        true
+        Ok(snapshots.len())
     }
 
-    pub fn save_all_chunks(&self, world: &mut GameWorld) -> usize {
-        // prepare chunks for saving
-        let regions = world
+    /// Save chunk snapshots in parallel, each region is written by a single thread.
+    ///
+    /// If some regions failed to save the rest of them are still saved
+    /// and the first error is returned.
+    pub fn save_chunk_snapshots(
+        &self,
+        storage: &GameWorldStorage,
+        snapshots: Vec<ChunkSnapshot>,
+    ) -> Result<usize, SaveError> {
+        let mut regions: HashMap<ChunkPos, Vec<ChunkSnapshot>> = HashMap::new();
+
+        for snapshot in snapshots {
+            let (region_pos, _) =
+                Self::get_chunk_key(snapshot.chunk.get_pos(), snapshot.chunk.get_level());
+            regions.entry(region_pos).or_default().push(snapshot);
+        }
+
+        let results = regions
+            .into_iter()
+            .map(|(region_pos, snapshots)| (region_pos, snapshots, self.clone(), storage.clone()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .parallel_map(|(region_pos, snapshots, meta, storage)| {
+                meta.save_region_chunks(&storage, region_pos, snapshots)
+            })
+            .collect::<Vec<_>>();
+
+        let mut saved = 0;
+        let mut error = None;
+
+        for result in results {
+            match result {
+                Ok(count) => saved += count,
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            }
+        }
+
+        match error {
+            Some(err) => Err(err),
+            None => Ok(saved),
+        }
+    }
+
+    /// Take snapshots of chunks that need to be saved and mark them as saved
+    fn snapshot_chunks_list(chunks: LinkedList<ChunkPointer>) -> Vec<ChunkSnapshot> {
+        chunks
+            .into_iter()
+            .filter_map(|chunk| {
+                let data = {
+                    let mut chunk = chunk.lock();
+
+                    if !chunk.is_need_save() {
+                        return None;
+                    }
+
+                    chunk.set_need_save(false);
+                    Arc::new(SparseChunk::from_chunk(&chunk))
+                };
+
+                Some(ChunkSnapshot { chunk, data })
+            })
+            .collect()
+    }
+
+    /// Take snapshots of all loaded chunks that need to be saved
+    pub fn snapshot_all_chunks(world: &GameWorld) -> Vec<ChunkSnapshot> {
+        let chunks = world
             .get_all_regions()
             .into_iter()
             .flat_map(|pos| {
@@ -141,7 +550,7 @@ impl GameWorldMeta {
                 for i in 0..8 {
                     let sub_pos = ChunkPos::from_index(i, 2);
 
-                    let pos = pos.clone() * 2 + sub_pos;
+                    let pos = pos * 2 + sub_pos;
 
                     let mut sub_chunks = world.get_all_subchunks(pos, 1);
 
@@ -150,96 +559,710 @@ impl GameWorldMeta {
 
                 result
             })
-            .map(|chunk| -> (ChunkPointer, Self) { (chunk, self.clone()) })
-            .collect::<Vec<_>>();
+            .collect::<LinkedList<_>>();
 
-        // actually save chunks in parallel
-        regions
-            .into_iter()
-            .parallel_filter(|(chunk, meta)| meta.save_chunk(chunk.clone()))
-            .count()
+        Self::snapshot_chunks_list(chunks)
     }
 
-    /// Recursively save all subchunks of chunk at given `pos` at given `level`
-    pub fn save_chunks(&self, world: &mut GameWorld, pos: ChunkPos, level: usize) -> usize {
-        let chunks = world
-            .get_all_subchunks(pos, level)
-            .into_iter()
-            .map(|chunk| -> (ChunkPointer, Self) { (chunk, self.clone()) })
-            .collect::<Vec<_>>();
+    /// Recursively take snapshots of all subchunks of chunk at given `pos` at given `level`
+    pub fn snapshot_chunks(world: &GameWorld, pos: ChunkPos, level: usize) -> Vec<ChunkSnapshot> {
+        Self::snapshot_chunks_list(world.get_all_subchunks(pos, level))
+    }
 
-        chunks
-            .into_iter()
-            .parallel_filter(|(chunk, meta)| meta.save_chunk(chunk.clone()))
-            .count()
+    /// Load modified voxels of the chunk at given `pos` at given `level`
+    pub fn load_chunk(
+        &self,
+        storage: &GameWorldStorage,
+        pos: ChunkPos,
+        level: usize,
+    ) -> Result<Option<SparseChunk>, SaveError> {
+        let (region_pos, key) = Self::get_chunk_key(pos, level);
+        let path = self.get_path(&Self::get_region_file_path(region_pos));
+
+        let data = {
+            let lock = self.region_locks.get(region_pos);
+            let _guard = lock.lock().unwrap();
+
+            match storage.open(&path)? {
+                Some(file) => RegionFile::open(file)
+                    .and_then(|mut region| region.read(key))
+                    .map_err(|err| SaveError::from(err).in_file(&path))?,
+                None => None,
+            }
+        };
+
+        let chunk = match data {
+            Some(data) => {
+                let (chunk, outdated) = self
+                    .decode_versioned::<SparseChunk>(&data, SaveKind::Chunk, true)
+                    .map_err(|err| err.in_file(&path))?;
+
+                if outdated {
+                    self.upgrade_chunk(storage, region_pos, key, &chunk, None);
+                }
+
+                Some(chunk)
+            }
+            None => self.load_legacy_chunk(storage, region_pos, key, pos, level)?,
+        };
+
+        match chunk {
+            Some(chunk) if !chunk.is_valid() => Err(SaveError::Corrupted(format!(
+                "chunk {:?}-{} has voxels out of bounds",
+                pos, level
+            ))),
+            chunk => Ok(chunk),
+        }
     }
 
-    pub fn load_chunk(&self, region_pos: ChunkPos, level: usize) -> Option<Chunk> {
-        let path = Self::get_chunk_path(region_pos, level);
+    /// Load chunk saved before chunks were packed into region files and move it to the region file
+    fn load_legacy_chunk(
+        &self,
+        storage: &GameWorldStorage,
+        region_pos: ChunkPos,
+        key: RegionChunkKey,
+        pos: ChunkPos,
+        level: usize,
+    ) -> Result<Option<SparseChunk>, SaveError> {
+        let path = self.get_path(&Self::get_legacy_chunk_path(pos, level));
+
+        let bytes = match storage.get(&path)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+
+        let chunk = self
+            .decode::<SparseChunk>(&bytes, SaveKind::Chunk, true)
+            .map_err(|err| err.in_file(&path))?;
+
+        self.upgrade_chunk(storage, region_pos, key, &chunk, Some(&path));
 
-        self.load::<Chunk>(&path, true)
+        Ok(Some(chunk))
     }
 
-    pub fn save_objects(&self, region_pos: ChunkPos, objects: Vec<GameWorldObjectSave>) {
+    /// Rewrite chunk loaded from an older format in the current one, `legacy_path` is removed after that.
+    ///
+    /// Failures are only logged, the chunk is still loaded and will be upgraded on the next load.
+    fn upgrade_chunk(
+        &self,
+        storage: &GameWorldStorage,
+        region_pos: ChunkPos,
+        key: RegionChunkKey,
+        chunk: &SparseChunk,
+        legacy_path: Option<&str>,
+    ) {
+        let result = Self::encode(chunk, SaveKind::Chunk, true)
+            .and_then(|data| self.write_region_chunks(storage, region_pos, &[(key, data)]))
+            .and_then(|_| legacy_path.map_or(Ok(()), |path| storage.delete(path)));
+
+        if let Err(err) = result {
+            warn!(
+                "Failed to upgrade chunk {:?} of region {:?}: {}",
+                key, region_pos, err
+            );
+        }
+    }
+
+    pub fn save_objects(
+        &self,
+        storage: &GameWorldStorage,
+        region_pos: ChunkPos,
+        objects: &[GameWorldObjectSave],
+    ) -> Result<(), SaveError> {
         let path = Self::get_objects_path(region_pos);
 
-        self.save(&(objects), &path, true);
+        self.save(storage, &objects, &path, SaveKind::Objects, true)
     }
 
-    pub fn load_objects(&self, region_pos: ChunkPos) -> Option<Vec<GameWorldObjectSave>> {
+    pub fn load_objects(
+        &self,
+        storage: &GameWorldStorage,
+        region_pos: ChunkPos,
+    ) -> Result<Option<Vec<GameWorldObjectSave>>, SaveError> {
         let path = Self::get_objects_path(region_pos);
 
-        self.load::<Vec<GameWorldObjectSave>>(&path, true)
+        self.load::<Vec<GameWorldObjectSave>>(storage, &path, SaveKind::Objects, true)
     }
 
-    pub fn save_self(&self) {
-        self.save(self, "meta", false);
+    pub fn save_self(&self, storage: &GameWorldStorage) -> Result<(), SaveError> {
+        self.save(storage, self, "meta", SaveKind::Meta, false)
     }
 
-    pub fn get_saves() -> Vec<GameWorldMeta> {
+    /// Get all saved worlds, corrupted worlds are skipped
+    pub fn get_saves(storage: &GameWorldStorage) -> Result<Vec<GameWorldMeta>, SaveError> {
         let mut saves = Vec::new();
 
-        let save_dir = std::path::Path::new(Self::SAVE_DIR);
+        let loader = Self::default();
+
+        for id in storage.list_dirs("")? {
+            let key = format!("{}/meta", id);
+
+            let bytes = match storage.get(&key)? {
+                Some(bytes) => bytes,
+                None => continue,
+            };
+
+            let mut meta: GameWorldMeta = match loader.decode(&bytes, SaveKind::Meta, false) {
+                Ok(meta) => meta,
+                Err(err) => {
+                    warn!("Skipping corrupted world {}: {}", key, err);
+                    continue;
+                }
+            };
 
-        if !save_dir.exists() {
-            return saves;
+            meta.needs_upgrade =
+                SaveHeader::decode(&bytes).map_or(true, |(header, _)| header.is_outdated());
+
+            saves.push(meta);
         }
 
-        for entry in fs::read_dir(save_dir).unwrap() {
-            let entry = entry.unwrap();
-            let path = entry.path();
+        Ok(saves)
+    }
+
+    pub(super) const QUARANTINE_DIR: &str = "quarantine";
 
-            if !path.is_dir() {
+    /// File that exists while the world is played, it is left after crashes
+    const SESSION_MARKER: &str = "session";
+
+    /// Mark the world as being played, returns `true` if the previous session was not ended
+    pub fn begin_session(&self, storage: &GameWorldStorage) -> Result<bool, SaveError> {
+        let path = self.get_path(Self::SESSION_MARKER);
+        let dirty = storage.get(&path)?.is_some();
+
+        storage.put(&path, &[])?;
+
+        Ok(dirty)
+    }
+
+    /// Mark the world as cleanly closed, called after all its data is written
+    pub fn end_session(&self, storage: &GameWorldStorage) -> Result<(), SaveError> {
+        storage.delete(&self.get_path(Self::SESSION_MARKER))
+    }
+
+    /// Move corrupted file at `key` to the quarantine directory of the world
+    fn quarantine(&self, storage: &GameWorldStorage, key: &str, reason: &SaveError) {
+        let root = self.get_path("");
+        let relative = key.strip_prefix(&root).unwrap_or(key);
+        let target = self.get_path(&format!("{}/{}", Self::QUARANTINE_DIR, relative));
+
+        warn!("Quarantine corrupted file {}: {}", key, reason);
+
+        if let Err(err) = storage.rename(key, &target) {
+            error!("Can't quarantine file {}: {}", key, err);
+        }
+    }
+
+    /// Get keys of all files of the world except for the temporary and quarantined ones
+    pub(super) fn get_world_keys(
+        &self,
+        storage: &GameWorldStorage,
+    ) -> Result<Vec<String>, SaveError> {
+        let tmp_suffix = format!(".{}", atomic_file::TMP_EXTENSION);
+
+        Ok(self
+            .get_world_keys_with_tmp(storage)?
+            .into_iter()
+            .filter(|key| !key.ends_with(&tmp_suffix))
+            .collect())
+    }
+
+    /// Get keys of all files of the world including leftovers of interrupted writes
+    fn get_world_keys_with_tmp(
+        &self,
+        storage: &GameWorldStorage,
+    ) -> Result<Vec<String>, SaveError> {
+        let quarantine = self.get_path(&format!("{}/", Self::QUARANTINE_DIR));
+        let marker = self.get_path(Self::SESSION_MARKER);
+
+        Ok(storage
+            .list(&self.get_path(""))?
+            .into_iter()
+            .filter(|key| !key.starts_with(&quarantine) && *key != marker)
+            .collect())
+    }
+
+    /// Split file name at `key` into name and extension
+    fn split_file_name(key: &str) -> (&str, Option<&str>) {
+        let file_name = key.rsplit('/').next().unwrap_or(key);
+
+        match file_name.split_once('.') {
+            Some((name, ext)) => (name, Some(ext)),
+            None => (file_name, None),
+        }
+    }
+
+    /// Kind of the data stored in the file at `key`, `None` for region and unknown files
+    fn get_file_kind(key: &str) -> Option<SaveKind> {
+        match Self::split_file_name(key) {
+            ("meta", None) => Some(SaveKind::Meta),
+            ("player", None) => Some(SaveKind::Player),
+            ("objects", None) => Some(SaveKind::Objects),
+            (_, Some("chunk")) => Some(SaveKind::Chunk),
+            _ => None,
+        }
+    }
+
+    /// Check that the file starts with a header of the expected kind and supported version.
+    ///
+    /// Files without header were written by old versions and can only be checked by decoding.
+    fn check_header(file: &mut StorageFile, kind: SaveKind) -> bool {
+        let mut bytes = [0u8; SaveHeader::SIZE];
+        if file.read_exact(&mut bytes).is_err() {
+            return false;
+        }
+
+        match SaveHeader::decode(&bytes) {
+            Some((header, _)) => header.kind == kind && header.version <= kind.current_version(),
+            None => true,
+        }
+    }
+
+    /// Fast check of the world files done on every load.
+    ///
+    /// Only headers of the files and indexes of the region files are read,
+    /// leftovers of interrupted writes are removed.
+    /// Returns `false` if some files are damaged and [`GameWorldMeta::recover`] should be run.
+    pub fn quick_check(&self, storage: &GameWorldStorage) -> Result<bool, SaveError> {
+        let tmp_suffix = format!(".{}", atomic_file::TMP_EXTENSION);
+        let mut valid = true;
+
+        for key in self.get_world_keys_with_tmp(storage)? {
+            // original file is still intact
+            if key.ends_with(&tmp_suffix) {
+                warn!("Removing partially written file {}", key);
+                storage.delete(&key)?;
                 continue;
             }
 
-            let meta_path = path.join("meta");
+            let mut file = match storage.open(&key)? {
+                Some(file) => file,
+                None => continue,
+            };
+
+            let file_valid = match Self::get_file_kind(&key) {
+                Some(kind) => Self::check_header(&mut file, kind),
+                None if Self::split_file_name(&key).1 == Some("region") => {
+                    RegionFile::open(file).is_ok()
+                }
+                None => true,
+            };
+
+            if !file_valid {
+                warn!("Damaged file {}, world will be recovered", key);
+                valid = false;
+            }
+        }
 
-            if !meta_path.exists() {
+        Ok(valid)
+    }
+
+    /// Check that chunk data from the region file can be loaded
+    fn check_chunk(&self, data: &[u8]) -> Result<(), SaveError> {
+        let chunk = self.decode::<SparseChunk>(data, SaveKind::Chunk, true)?;
+
+        if !chunk.is_valid() {
+            return Err(SaveError::Corrupted(
+                "chunk has voxels out of bounds".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Check that file at `key` with given `bytes` can be loaded, region files are not checked
+    fn check_file(&self, key: &str, bytes: &[u8]) -> Result<(), SaveError> {
+        match Self::split_file_name(key) {
+            ("meta", None) => self
+                .decode::<GameWorldMeta>(bytes, SaveKind::Meta, false)
+                .map(|_| ()),
+            ("player", None) => self
+                .decode::<PlayerSave>(bytes, SaveKind::Player, false)
+                .map(|_| ()),
+            ("objects", None) => self
+                .decode::<Vec<GameWorldObjectSave>>(bytes, SaveKind::Objects, true)
+                .map(|_| ()),
+            (_, Some("chunk")) => self.check_chunk(bytes),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove corrupted chunks from the region file, returns count of removed chunks
+    fn recover_region_file(
+        &self,
+        storage: &GameWorldStorage,
+        key: &str,
+    ) -> Result<usize, SaveError> {
+        let file = match storage.open(key)? {
+            Some(file) => file,
+            None => return Ok(0),
+        };
+
+        let mut region = RegionFile::open(file)?;
+
+        let chunk_keys = region.keys().copied().collect::<Vec<_>>();
+        let mut removed = 0;
+
+        for chunk_key in chunk_keys {
+            let valid = match region.read(chunk_key) {
+                Ok(Some(data)) => self.check_chunk(&data).is_ok(),
+                _ => false,
+            };
+
+            if !valid {
+                warn!(
+                    "Corrupted chunk {:?} in {} will be regenerated",
+                    chunk_key, key
+                );
+                region.remove(chunk_key);
+                removed += 1;
+            }
+        }
+
+        region.commit()?;
+
+        Ok(removed)
+    }
+
+    /// Check all files of the world and get rid of partially written or corrupted ones.
+    ///
+    /// Corrupted files are moved to the quarantine directory and are treated as
+    /// missing, so chunks and objects are regenerated by the world generator and
+    /// the player is respawned. Corrupted meta is rewritten from `self`.
+    ///
+    /// Every file is decoded, so it is only run by the save worker after a crash
+    /// or a failed read, see [`GameWorldMeta::needs_recovery`].
+    ///
+    /// Returns count of recovered files and chunks.
+    pub fn recover(&self, storage: &GameWorldStorage) -> Result<usize, SaveError> {
+        let tmp_suffix = format!(".{}", atomic_file::TMP_EXTENSION);
+        let mut recovered = 0;
+
+        for key in self.get_world_keys_with_tmp(storage)? {
+            // leftovers of interrupted writes, original file is still intact
+            if key.ends_with(&tmp_suffix) {
+                warn!("Removing partially written file {}", key);
+                storage.delete(&key)?;
+                recovered += 1;
                 continue;
             }
 
-            let file = fs::File::open(meta_path).unwrap();
-            let reader = BufReader::new(file);
+            let (name, ext) = Self::split_file_name(&key);
+
+            if ext == Some("region") {
+                match self.recover_region_file(storage, &key) {
+                    Ok(removed) => recovered += removed,
+                    Err(err) => {
+                        self.quarantine(storage, &key, &err);
+                        recovered += 1;
+                    }
+                }
+                continue;
+            }
 
-            let meta: GameWorldMeta = bincode::deserialize_from(reader).unwrap();
+            let bytes = match storage.get(&key)? {
+                Some(bytes) => bytes,
+                None => continue,
+            };
 
-            saves.push(meta);
+            match self.check_file(&key, &bytes) {
+                Ok(_) => {}
+                // meta is already loaded, so it can be just written again
+                Err(err) if (name, ext) == ("meta", None) => {
+                    warn!("Rewriting corrupted world meta {}: {}", key, err);
+                    self.save_self(storage)?;
+                    recovered += 1;
+                }
+                Err(err) => {
+                    self.quarantine(storage, &key, &err);
+                    recovered += 1;
+                }
+            }
         }
 
-        saves
+        Ok(recovered)
     }
 
-    pub fn save_player(&self, player: PlayerSave) {
-        self.save(&player, "player", false);
+    /// Check all files of the world without changing them, returns corrupted files with errors.
+    ///
+    /// Corrupted chunks of region files are reported as `<region file key>:<chunk key>`.
+    pub fn validate(
+        &self,
+        storage: &GameWorldStorage,
+    ) -> Result<Vec<(String, SaveError)>, SaveError> {
+        let mut errors = Vec::new();
+
+        for key in self.get_world_keys(storage)? {
+            if Self::split_file_name(&key).1 != Some("region") {
+                let result = match storage.get(&key)? {
+                    Some(bytes) => self.check_file(&key, &bytes),
+                    None => continue,
+                };
+
+                if let Err(err) = result {
+                    errors.push((key, err));
+                }
+                continue;
+            }
+
+            let mut region = match storage.open(&key)?.map(RegionFile::open) {
+                Some(Ok(region)) => region,
+                Some(Err(err)) => {
+                    errors.push((key, err.into()));
+                    continue;
+                }
+                None => continue,
+            };
+
+            let chunk_keys = region.keys().copied().collect::<Vec<_>>();
+
+            for chunk_key in chunk_keys {
+                let result = match region.read(chunk_key) {
+                    Ok(Some(data)) => self.check_chunk(&data),
+                    Ok(None) => continue,
+                    Err(err) => Err(err.into()),
+                };
+
+                if let Err(err) = result {
+                    errors.push((format!("{}:{:?}", key, chunk_key), err));
+                }
+            }
+        }
+
+        Ok(errors)
+    }
+
+    /// Get size of all region files of the world and their chunks
+    pub fn get_region_stats(
+        &self,
+        storage: &GameWorldStorage,
+    ) -> Result<Vec<RegionStats>, SaveError> {
+        let mut stats = Vec::new();
+
+        for key in self.get_world_keys(storage)? {
+            if Self::split_file_name(&key).1 != Some("region") {
+                continue;
+            }
+
+            let region_pos = match Self::parse_region_pos(&key) {
+                Some(pos) => pos,
+                None => continue,
+            };
+
+            let region = match storage.open(&key)? {
+                Some(file) => {
+                    RegionFile::open(file).map_err(|err| SaveError::from(err).in_file(&key))?
+                }
+                None => continue,
+            };
+
+            let mut levels = vec![LevelStats::default(); GameWorld::MAX_DETAIL_LEVEL + 1];
+
+            for chunk_key in region.keys() {
+                let level = chunk_key.level as usize;
+                if level >= levels.len() {
+                    levels.resize(level + 1, LevelStats::default());
+                }
+
+                levels[level].chunks += 1;
+                levels[level].size += region.slot(*chunk_key).map_or(0, |slot| slot.len);
+            }
+
+            stats.push(RegionStats {
+                region_pos,
+                file_size: region.file_size(),
+                free_size: region.free_size(),
+                levels,
+            });
+        }
+
+        stats.sort_by_key(|stats| (stats.region_pos.x, stats.region_pos.y, stats.region_pos.z));
+
+        Ok(stats)
+    }
+
+    /// Parse region position from the key of the region file
+    fn parse_region_pos(key: &str) -> Option<ChunkPos> {
+        let mut parts = key.rsplit('/').nth(1)?.split('_').map(|v| v.parse().ok());
+
+        Some(ChunkPos::new(
+            parts.next()??,
+            parts.next()??,
+            parts.next()??,
+        ))
     }
 
-    pub fn load_player(&self) -> Option<PlayerSave> {
-        self.load::<PlayerSave>("player", false)
+    /// Save generated chunks with all their voxels, so they are loaded without generation
+    pub fn save_baked_chunks(
+        &self,
+        storage: &GameWorldStorage,
+        chunks: Vec<ChunkPointer>,
+    ) -> Result<usize, SaveError> {
+        let snapshots = chunks
+            .into_iter()
+            .map(|chunk| {
+                let data = Arc::new(SparseChunk::bake(&chunk.lock()));
+                ChunkSnapshot { chunk, data }
+            })
+            .collect();
+
+        self.save_chunk_snapshots(storage, snapshots)
+    }
+
+    pub fn save_player(
+        &self,
+        storage: &GameWorldStorage,
+        player: PlayerSave,
+    ) -> Result<(), SaveError> {
+        self.save(storage, &player, "player", SaveKind::Player, false)
+    }
+
+    pub fn load_player(&self, storage: &GameWorldStorage) -> Result<Option<PlayerSave>, SaveError> {
+        self.load::<PlayerSave>(storage, "player", SaveKind::Player, false)
     }
 }
 
 #[test]
 fn get_saved_worlds() {
-    println!("{:?}", GameWorldMeta::get_saves());
+    use crate::plugins::game_world::utils::storage::MemoryStorage;
+
+    let storage = GameWorldStorage::new(MemoryStorage::new());
+
+    let mut meta = GameWorldMeta::default();
+    meta.reset();
+    meta.save_self(&storage).unwrap();
+    meta.save_player(&storage, PlayerSave::default()).unwrap();
+
+    let saves = GameWorldMeta::get_saves(&storage).unwrap();
+
+    assert_eq!(saves.len(), 1);
+    assert_eq!(saves[0].id, meta.id);
+    assert!(!saves[0].needs_upgrade);
+}
+
+#[test]
+fn save_and_load_chunks() {
+    use crate::internal::chunks::Chunk;
+    use crate::plugins::game_world::utils::storage::MemoryStorage;
+
+    let storage = GameWorldStorage::new(MemoryStorage::new());
+
+    let mut meta = GameWorldMeta::default();
+    meta.reset();
+
+    let pos = ChunkPos::new(3, -1, 2);
+    let level = 2;
+
+    let mut chunk = Chunk::default();
+    chunk.set_need_save(true);
+    let chunk = ChunkPointer::new(chunk, pos, level);
+
+    let mut chunks = LinkedList::new();
+    chunks.push_back(chunk.clone());
+
+    let snapshots = GameWorldMeta::snapshot_chunks_list(chunks);
+    assert_eq!(snapshots.len(), 1);
+    assert!(!chunk.lock().is_need_save());
+
+    assert_eq!(meta.save_chunk_snapshots(&storage, snapshots).unwrap(), 1);
+
+    assert!(meta.load_chunk(&storage, pos, level).unwrap().is_some());
+    assert!(meta
+        .load_chunk(&storage, pos + ChunkPos::new(1, 0, 0), level)
+        .unwrap()
+        .is_none());
+
+    assert_eq!(meta.recover(&storage).unwrap(), 0);
+}
+
+#[test]
+fn migrate_meta_from_v1() {
+    let mut payload = bincode::serialize(&("Old World".to_string(), 42u32)).unwrap();
+    payload.extend(bincode::serialize(&"old-id".to_string()).unwrap());
+
+    let meta: GameWorldMeta = bincode::deserialize(
+        &SaveMigrations::new()
+            .migrate(SaveHeader::legacy(SaveKind::Meta, false), payload)
+            .unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(meta.name, "Old World");
+    assert_eq!(meta.seed, 42);
+    assert_eq!(meta.id, "old-id");
+    assert_eq!(meta.generator, GeneratorSettings::legacy());
+}
+
+#[test]
+fn migrate_meta_from_v4_pins_cave_biomes() {
+    let settings = GeneratorSettings::current();
+    let payload = bincode::serialize(&MetaV4 {
+        name: "Cave World".to_string(),
+        seed: 42,
+        id: "cave-id".to_string(),
+        generator_version: settings.version,
+        generator_params: settings.params,
+        generator_biomes: BiomeSource::builtin(),
+        generator_terrain: None,
+    })
+    .unwrap();
+
+    let header = SaveHeader {
+        kind: SaveKind::Meta,
+        version: 4,
+        compressed: false,
+    };
+    let meta: GameWorldMeta =
+        bincode::deserialize(&SaveMigrations::new().migrate(header, payload).unwrap()).unwrap();
+
+    assert_eq!(meta.generator.biomes, BiomeSource::builtin());
+    assert_eq!(meta.generator.cave_biomes, DataCaveBiome::builtin_sources());
+}
+
+#[test]
+fn legacy_files_are_upgraded_on_load() {
+    use crate::plugins::game_world::utils::storage::MemoryStorage;
+
+    let storage = GameWorldStorage::new(MemoryStorage::new());
+
+    let mut meta = GameWorldMeta::default();
+    meta.reset();
+
+    // player saved before the save header was introduced
+    let path = meta.get_path("player");
+    let legacy = bincode::serialize(&PlayerSave::default()).unwrap();
+    storage.put(&path, &legacy).unwrap();
+
+    assert!(meta.load_player(&storage).unwrap().is_some());
+
+    let bytes = storage.get(&path).unwrap().unwrap();
+    let (header, _) = SaveHeader::decode(&bytes).unwrap();
+    assert!(!header.is_outdated());
+}
+
+#[test]
+fn quick_check_finds_damaged_files() {
+    use crate::plugins::game_world::utils::storage::MemoryStorage;
+
+    let storage = GameWorldStorage::new(MemoryStorage::new());
+
+    let mut meta = GameWorldMeta::default();
+    meta.reset();
+    meta.save_self(&storage).unwrap();
+    meta.save_player(&storage, PlayerSave::default()).unwrap();
+
+    assert!(!meta.begin_session(&storage).unwrap());
+    assert!(
+        meta.begin_session(&storage).unwrap(),
+        "session was not ended"
+    );
+    meta.end_session(&storage).unwrap();
+
+    assert!(meta.quick_check(&storage).unwrap());
+
+    // objects file with the header of a chunk
+    let bytes = GameWorldMeta::encode(&Vec::<u8>::new(), SaveKind::Chunk, true).unwrap();
+    let path = meta.get_path(&GameWorldMeta::get_objects_path(ChunkPos::zero()));
+    storage.put(&path, &bytes).unwrap();
+
+    assert!(!meta.quick_check(&storage).unwrap());
 }
@@ -1,14 +1,22 @@
 use crate::{
     internal::{
-        chunks::{in_world_chunk::InWorldChunk, pointer::ChunkPointer, Chunk},
+        chunks::{
+            apron::ChunkApron, in_world_chunk::InWorldChunk, pointer::ChunkPointer,
+            seams::ChunkSeams, Chunk,
+        },
+        direction::Direction,
         pos::{ChunkPos, VoxelPos},
     },
     plugins::world_generator::{internal::biomes::ChunkBiomes, resources::WorldGenerator},
 };
 use bevy::{prelude::*, reflect::Reflect, utils::HashMap};
 use std::collections::LinkedList;
+use strum::IntoEnumIterator;
 
+pub mod backup;
 pub mod meta;
+pub mod save_worker;
+pub mod storage;
 
 #[derive(Resource, Debug, Default, Reflect, FromReflect)]
 #[reflect(Resource)]
@@ -180,6 +188,78 @@ impl GameWorld {
         chunk.get_all_sub_chunks()
     }
 
+    /// Get loaded chunk (at any level) right behind the `dir` face of chunk at given `pos` at given `level`
+    fn get_chunk_behind_face(
+        &self,
+        pos: ChunkPos,
+        level: usize,
+        dir: Direction,
+    ) -> Option<&ChunkPointer> {
+        let scale = Self::level_to_scale(level) as i64;
+        let offset = ChunkPos::from(dir);
+
+        // corner of the chunk in max detail level chunks, shifted over the face
+        let step = if offset.x + offset.y + offset.z > 0 {
+            scale
+        } else {
+            1
+        };
+        let detailest_pos = pos * scale + offset * step;
+
+        self.get_detailest_chunk(detailest_pos)
+            .map(|(chunk, _)| chunk)
+    }
+
+    /// Get faces of chunk at given `pos` at given `level` bordering loaded chunks of another level
+    ///
+    /// Neighbors are split into sub chunks all at once, so single chunk behind the face is enough
+    pub fn get_chunk_seams(&self, pos: ChunkPos, level: usize) -> ChunkSeams {
+        Direction::iter().fold(ChunkSeams::NONE, |seams, dir| {
+            match self.get_chunk_behind_face(pos, level, dir) {
+                Some(chunk) if chunk.get_level() != level => seams.with(dir),
+                _ => seams,
+            }
+        })
+    }
+
+    /// Get values of the voxels behind the faces of chunk at given `pos` at given `level`
+    ///
+    /// Only neighbors of the same level are used, voxels of other levels have another scale
+    pub fn get_chunk_apron(&self, pos: ChunkPos, level: usize) -> ChunkApron {
+        let mut apron = ChunkApron::default();
+
+        for dir in Direction::iter() {
+            match self.get_chunk_behind_face(pos, level, dir) {
+                Some(chunk) if chunk.get_level() == level => {
+                    apron.set_face(dir, Some(&chunk.lock()))
+                }
+                _ => apron.set_face(dir, None),
+            }
+        }
+
+        apron
+    }
+
+    /// Get all loaded chunks of the face neighbors of chunk at given `pos` at given `level`
+    ///
+    /// Neighbors split into sub chunks are returned with all their sub chunks
+    pub fn get_face_neighbors(&self, pos: ChunkPos, level: usize) -> LinkedList<ChunkPointer> {
+        let mut result = LinkedList::new();
+
+        for dir in Direction::iter() {
+            match self.get_chunk(pos + ChunkPos::from(dir), level) {
+                Some(chunk) => result.append(&mut chunk.get_all_sub_chunks()),
+                None => {
+                    if let Some(chunk) = self.get_chunk_behind_face(pos, level, dir) {
+                        result.push_back(chunk.clone());
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
     pub fn remove_region(&mut self, pos: ChunkPos) -> Option<(InWorldChunk, ChunkBiomes)> {
         self.regions.remove(&pos)
     }
@@ -339,3 +419,46 @@ fn test_level_transforms() {
         VoxelPos::new(GameWorld::REGION_SIZE - 1, 1, 1),
     );
 }
+
+#[test]
+fn chunk_seams() {
+    let gen = WorldGenerator::new(123);
+    let mut world = GameWorld::new();
+
+    let detailed_pos = ChunkPos::new(0, 0, 0);
+    let simple_pos = ChunkPos::new(1, 0, 0);
+
+    world.create_chunk(detailed_pos, &gen).unwrap();
+    world.create_chunk(simple_pos, &gen).unwrap();
+
+    *world.get_chunk_mut(detailed_pos, 0).unwrap() =
+        InWorldChunk::SubChunks(vec![InWorldChunk::Loading; 8]);
+
+    for i in 0..8 {
+        let pos = ChunkPos::from_index(i, 2);
+        let chunk = ChunkPointer::new(Chunk::empty(), pos, 1);
+        world
+            .update_chunk(chunk, Entity::from_raw(i as u32))
+            .unwrap();
+    }
+
+    let chunk = ChunkPointer::new(Chunk::empty(), simple_pos, 0);
+    world.update_chunk(chunk, Entity::from_raw(8)).unwrap();
+
+    // sub chunk at the border of the simple region
+    assert_eq!(
+        world.get_chunk_seams(ChunkPos::new(1, 0, 0), 1),
+        ChunkSeams::NONE.with(Direction::EAST)
+    );
+    // sub chunk inside of the detailed region
+    assert_eq!(
+        world.get_chunk_seams(ChunkPos::new(0, 1, 1), 1),
+        ChunkSeams::NONE
+    );
+    assert_eq!(
+        world.get_chunk_seams(simple_pos, 0),
+        ChunkSeams::NONE.with(Direction::WEST)
+    );
+
+    assert_eq!(world.get_face_neighbors(simple_pos, 0).len(), 8);
+}
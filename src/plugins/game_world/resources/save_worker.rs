@@ -0,0 +1,540 @@
+use super::{
+    meta::{ChunkSnapshot, GameWorldMeta},
+    storage::GameWorldStorage,
+};
+use crate::{
+    internal::{chunks::sparse::SparseChunk, pos::ChunkPos},
+    plugins::{
+        game_world::events::SaveErrorEvent, objects::utils::object_save::GameWorldObjectSave,
+        player::components::save::PlayerSave,
+    },
+};
+use bevy::{prelude::*, utils::HashMap};
+use crossbeam_channel::{bounded, unbounded, Receiver, RecvTimeoutError, Sender, TrySendError};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+/// Max count of jobs waiting for the worker, more jobs are merged until the worker catches up
+const SAVE_QUEUE_SIZE: usize = 64;
+
+/// Delay before data that failed to be written is written again
+const SAVE_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+pub enum SaveJob {
+    Player(PlayerSave),
+    Objects {
+        region_pos: ChunkPos,
+        objects: Arc<Vec<GameWorldObjectSave>>,
+    },
+    Chunks(Vec<ChunkSnapshot>),
+    /// Back up the world after all previous jobs are written, skipped if nothing changed since the last one
+    Backup,
+    /// Fully check files of the world after a failed read, see [`GameWorldMeta::recover`]
+    Recover,
+    /// Notify the sender when all previous jobs are written
+    Flush(Sender<()>),
+}
+
+/// Jobs received by the worker since the last write.
+///
+/// Only the latest data of the player, region objects or chunk is kept.
+#[derive(Default)]
+struct PendingSaves {
+    jobs: usize,
+    player: Option<PlayerSave>,
+    objects: HashMap<ChunkPos, Arc<Vec<GameWorldObjectSave>>>,
+    chunks: HashMap<(ChunkPos, usize), ChunkSnapshot>,
+    backup: bool,
+    recover: bool,
+    flushes: Vec<Sender<()>>,
+}
+
+impl PendingSaves {
+    fn push(&mut self, job: SaveJob) {
+        self.jobs += 1;
+
+        match job {
+            SaveJob::Player(player) => self.player = Some(player),
+            SaveJob::Objects {
+                region_pos,
+                objects,
+            } => {
+                self.objects.insert(region_pos, objects);
+            }
+            SaveJob::Chunks(chunks) => {
+                for chunk in chunks {
+                    self.chunks
+                        .insert((chunk.get_pos(), chunk.get_level()), chunk);
+                }
+            }
+            SaveJob::Backup => self.backup = true,
+            SaveJob::Recover => self.recover = true,
+            SaveJob::Flush(tx) => self.flushes.push(tx),
+        }
+    }
+
+    /// Merge jobs received after these ones, newer data replaces the older one
+    fn merge(&mut self, newer: PendingSaves) {
+        self.jobs += newer.jobs;
+        if newer.player.is_some() {
+            self.player = newer.player;
+        }
+        self.objects.extend(newer.objects);
+        self.chunks.extend(newer.chunks);
+        self.backup |= newer.backup;
+        self.recover |= newer.recover;
+        self.flushes.extend(newer.flushes);
+    }
+
+    /// Check if there is data to write, backups and recovery are not counted
+    fn has_data(&self) -> bool {
+        self.player.is_some() || !self.objects.is_empty() || !self.chunks.is_empty()
+    }
+
+    /// Write the data, returns data that failed to be written, so it can be written again later.
+    ///
+    /// Unsaved data is removed only after it is written, so it still can be loaded until then.
+    fn write(
+        self,
+        meta: &GameWorldMeta,
+        storage: &GameWorldStorage,
+        unsaved: &UnsavedData,
+        errors: &Sender<SaveErrorEvent>,
+        changed: &mut bool,
+    ) -> PendingSaves {
+        let mut failed = PendingSaves::default();
+
+        if self.has_data() {
+            *changed = true;
+        }
+
+        let report = |event: SaveErrorEvent| {
+            errors.send(event).ok();
+        };
+
+        if let Some(player) = self.player {
+            if let Err(err) = meta.save_player(storage, player.clone()) {
+                report(SaveErrorEvent::new("Failed to save player", err));
+                failed.player = Some(player);
+            }
+        }
+
+        if !self.objects.is_empty() {
+            let start = std::time::Instant::now();
+            let count = self.objects.len();
+
+            for (region_pos, objects) in self.objects {
+                match meta.save_objects(storage, region_pos, &objects) {
+                    Ok(()) => unsaved.remove_objects(region_pos, &objects),
+                    Err(err) => {
+                        report(SaveErrorEvent::new(
+                            format!("Failed to save objects of region {:?}", region_pos),
+                            err,
+                        ));
+                        failed.objects.insert(region_pos, objects);
+                    }
+                }
+            }
+
+            info!(
+                "Objects in {} regions saved in {}ms",
+                count,
+                start.elapsed().as_millis()
+            );
+        }
+
+        if !self.chunks.is_empty() {
+            let start = std::time::Instant::now();
+
+            match meta.save_chunk_snapshots(storage, self.chunks.values().cloned().collect()) {
+                Ok(count) => {
+                    for (key, snapshot) in self.chunks.iter() {
+                        unsaved.remove_chunk(*key, snapshot.get_data());
+                    }
+
+                    info!(
+                        "Saved {} chunks in {}ms",
+                        count,
+                        start.elapsed().as_millis()
+                    );
+                }
+                Err(err) => {
+                    report(SaveErrorEvent::new("Failed to save chunks", err));
+                    failed.chunks = self.chunks;
+                }
+            }
+        }
+
+        if self.recover {
+            recover(meta, storage, errors);
+        }
+
+        if self.backup && *changed {
+            *changed = !backup(meta, storage, errors);
+        }
+
+        for flush in self.flushes {
+            flush.send(()).ok();
+        }
+
+        failed
+    }
+}
+
+fn recover(meta: &GameWorldMeta, storage: &GameWorldStorage, errors: &Sender<SaveErrorEvent>) {
+    let start = std::time::Instant::now();
+
+    match meta.recover(storage) {
+        Ok(0) => info!("World checked in {}ms", start.elapsed().as_millis()),
+        Ok(recovered) => warn!("Recovered {} corrupted save entries", recovered),
+        Err(err) => {
+            errors
+                .send(SaveErrorEvent::new("Failed to recover world", err))
+                .ok();
+        }
+    }
+}
+
+/// Data that is sent to the worker but not written yet.
+///
+/// Unloaded chunks and objects can be loaded again before the worker writes them,
+/// so it is checked before reading their files. All clones share the same data.
+#[derive(Default, Clone)]
+pub struct UnsavedData(Arc<Mutex<UnsavedEntries>>);
+
+#[derive(Default)]
+struct UnsavedEntries {
+    objects: HashMap<ChunkPos, Arc<Vec<GameWorldObjectSave>>>,
+    chunks: HashMap<(ChunkPos, usize), Arc<SparseChunk>>,
+}
+
+impl UnsavedData {
+    fn insert_objects(&self, region_pos: ChunkPos, objects: Arc<Vec<GameWorldObjectSave>>) {
+        self.0.lock().unwrap().objects.insert(region_pos, objects);
+    }
+
+    fn insert_chunks(&self, chunks: &[ChunkSnapshot]) {
+        let mut entries = self.0.lock().unwrap();
+
+        for chunk in chunks {
+            entries.chunks.insert(
+                (chunk.get_pos(), chunk.get_level()),
+                chunk.get_data().clone(),
+            );
+        }
+    }
+
+    /// Remove written objects, unless newer ones were sent after them
+    fn remove_objects(&self, region_pos: ChunkPos, written: &Arc<Vec<GameWorldObjectSave>>) {
+        let mut entries = self.0.lock().unwrap();
+
+        if let Some(objects) = entries.objects.get(&region_pos) {
+            if Arc::ptr_eq(objects, written) {
+                entries.objects.remove(&region_pos);
+            }
+        }
+    }
+
+    /// Remove written chunk, unless a newer one was sent after it
+    fn remove_chunk(&self, key: (ChunkPos, usize), written: &Arc<SparseChunk>) {
+        let mut entries = self.0.lock().unwrap();
+
+        if let Some(chunk) = entries.chunks.get(&key) {
+            if Arc::ptr_eq(chunk, written) {
+                entries.chunks.remove(&key);
+            }
+        }
+    }
+
+    fn clear(&self) {
+        let mut entries = self.0.lock().unwrap();
+
+        entries.objects.clear();
+        entries.chunks.clear();
+    }
+
+    /// Get objects of the region at `region_pos` that are not written yet
+    pub fn get_objects(&self, region_pos: ChunkPos) -> Option<Vec<GameWorldObjectSave>> {
+        self.0
+            .lock()
+            .unwrap()
+            .objects
+            .get(&region_pos)
+            .map(|objects| objects.as_ref().clone())
+    }
+
+    /// Get modified voxels of the chunk at `pos` at `level` that are not written yet
+    pub fn get_chunk(&self, pos: ChunkPos, level: usize) -> Option<SparseChunk> {
+        self.0
+            .lock()
+            .unwrap()
+            .chunks
+            .get(&(pos, level))
+            .map(|chunk| chunk.as_ref().clone())
+    }
+}
+
+/// Back up the world and remove old backups, returns `true` if the backup is made
+fn backup(
+    meta: &GameWorldMeta,
+    storage: &GameWorldStorage,
+    errors: &Sender<SaveErrorEvent>,
+) -> bool {
+    match meta.backup_and_prune(storage) {
+        Ok(_) => true,
+        Err(err) => {
+            errors
+                .send(SaveErrorEvent::new("Failed to back up world", err))
+                .ok();
+            false
+        }
+    }
+}
+
+struct SaveWorkerThread {
+    tx: Sender<SaveJob>,
+    /// Jobs sent while the queue is full, so sending never waits for the worker.
+    ///
+    /// When it is not empty, newer jobs are added to it too, so the order of the jobs is kept.
+    overflow: Arc<Mutex<PendingSaves>>,
+    errors: Receiver<SaveErrorEvent>,
+    thread: JoinHandle<()>,
+}
+
+/// Writes world data in the background thread.
+///
+/// Started when the world is entered and stopped when it is left.
+#[derive(Resource, Default)]
+pub struct SaveWorker {
+    worker: Option<SaveWorkerThread>,
+    queue_len: Arc<AtomicUsize>,
+    unsaved: UnsavedData,
+}
+
+impl SaveWorker {
+    /// Start writing data of the world described by `meta`, previous worker is stopped.
+    ///
+    /// World is recovered first if [`GameWorldMeta::needs_recovery`] is set and then backed up,
+    /// jobs sent meanwhile are merged without blocking the sender.
+    /// Its session is ended when the worker is stopped.
+    pub fn start(&mut self, meta: GameWorldMeta, storage: GameWorldStorage) {
+        self.stop();
+
+        let (tx, rx) = bounded::<SaveJob>(SAVE_QUEUE_SIZE);
+        let (errors_tx, errors) = unbounded();
+        let overflow = Arc::new(Mutex::new(PendingSaves::default()));
+        let queue_len = self.queue_len.clone();
+        let unsaved = self.unsaved.clone();
+
+        let worker_overflow = overflow.clone();
+        let thread = std::thread::spawn(move || {
+            if meta.needs_recovery {
+                recover(&meta, &storage, &errors_tx);
+            }
+
+            // state before the session, so it can be restored if something goes wrong
+            let mut changed = !backup(&meta, &storage, &errors_tx);
+
+            let mut failed = PendingSaves::default();
+
+            loop {
+                let received = if failed.has_data() {
+                    rx.recv_timeout(SAVE_RETRY_DELAY)
+                } else {
+                    rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                };
+
+                let stopped = matches!(received, Err(RecvTimeoutError::Disconnected));
+                if stopped && !failed.has_data() {
+                    break;
+                }
+
+                // data that failed to be written is older than the received jobs
+                let mut pending = std::mem::take(&mut failed);
+                if let Ok(job) = received {
+                    pending.push(job);
+                }
+
+                // merge jobs that were sent while the previous ones were written
+                {
+                    let mut overflow = worker_overflow.lock().unwrap();
+                    for job in rx.try_iter() {
+                        pending.push(job);
+                    }
+                    pending.merge(std::mem::take(&mut *overflow));
+                }
+
+                let jobs = pending.jobs;
+                failed = pending.write(&meta, &storage, &unsaved, &errors_tx, &mut changed);
+
+                queue_len.fetch_sub(jobs, Ordering::Relaxed);
+
+                // the last attempt, errors of the data that is still not written are reported
+                if stopped {
+                    break;
+                }
+            }
+
+            if let Err(err) = meta.end_session(&storage) {
+                errors_tx
+                    .send(SaveErrorEvent::new("Failed to close world", err))
+                    .ok();
+            }
+        });
+
+        self.worker = Some(SaveWorkerThread {
+            tx,
+            overflow,
+            errors,
+            thread,
+        });
+    }
+
+    /// Write all queued jobs and stop the worker, returns errors that were not taken yet
+    pub fn stop(&mut self) -> Vec<SaveErrorEvent> {
+        let SaveWorkerThread {
+            tx, errors, thread, ..
+        } = match self.worker.take() {
+            Some(worker) => worker,
+            None => return Vec::new(),
+        };
+
+        drop(tx);
+
+        if thread.join().is_err() {
+            error!("Save worker panicked");
+        }
+
+        self.queue_len.store(0, Ordering::Relaxed);
+        self.unsaved.clear();
+
+        errors.try_iter().collect()
+    }
+
+    /// Queue the job without blocking, when the queue is full the job is merged with other waiting ones
+    pub fn send(&self, job: SaveJob) {
+        let worker = match &self.worker {
+            Some(worker) => worker,
+            None => {
+                error!("Save worker is not started");
+                return;
+            }
+        };
+
+        self.queue_len.fetch_add(1, Ordering::Relaxed);
+
+        let mut overflow = worker.overflow.lock().unwrap();
+
+        let job = if overflow.jobs == 0 {
+            match worker.tx.try_send(job) {
+                Ok(()) => return,
+                Err(TrySendError::Full(job)) => job,
+                Err(TrySendError::Disconnected(_)) => {
+                    self.queue_len.fetch_sub(1, Ordering::Relaxed);
+                    error!("Save worker is stopped");
+                    return;
+                }
+            }
+        } else {
+            job
+        };
+
+        overflow.push(job);
+    }
+
+    pub fn save_player(&self, player: PlayerSave) {
+        self.send(SaveJob::Player(player));
+    }
+
+    pub fn save_objects(&self, region_pos: ChunkPos, objects: Vec<GameWorldObjectSave>) {
+        let objects = Arc::new(objects);
+
+        // added before sending, so the worker can't remove it before it is added
+        self.unsaved.insert_objects(region_pos, objects.clone());
+
+        self.send(SaveJob::Objects {
+            region_pos,
+            objects,
+        });
+    }
+
+    pub fn save_chunks(&self, chunks: Vec<ChunkSnapshot>) {
+        if !chunks.is_empty() {
+            self.unsaved.insert_chunks(&chunks);
+            self.send(SaveJob::Chunks(chunks));
+        }
+    }
+
+    pub fn backup(&self) {
+        self.send(SaveJob::Backup);
+    }
+
+    /// Check and recover the world after data failed to load
+    pub fn recover(&self) {
+        self.send(SaveJob::Recover);
+    }
+
+    /// Wait until all queued jobs are written, data that failed to be written is retried later
+    pub fn flush(&self) {
+        if self.worker.is_none() {
+            return;
+        }
+
+        let (tx, rx) = bounded(1);
+        self.send(SaveJob::Flush(tx));
+
+        // returns error if the worker is stopped, so there is nothing to wait
+        rx.recv().ok();
+    }
+
+    /// Data that is sent but not written yet, loading should check it before reading files
+    pub fn unsaved(&self) -> UnsavedData {
+        self.unsaved.clone()
+    }
+
+    /// Count of jobs that are not written yet
+    pub fn queue_len(&self) -> usize {
+        self.queue_len.load(Ordering::Relaxed)
+    }
+
+    /// Take errors that happened since the last call
+    pub fn take_errors(&self) -> Vec<SaveErrorEvent> {
+        match &self.worker {
+            Some(worker) => worker.errors.try_iter().collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Drop for SaveWorker {
+    fn drop(&mut self) {
+        for event in self.stop() {
+            error!("{}: {}", event.action, event.error);
+        }
+    }
+}
+
+#[test]
+fn unsaved_objects_are_kept_until_written() {
+    let unsaved = UnsavedData::default();
+    let region_pos = ChunkPos::new(1, 0, 0);
+
+    let old = Arc::new(Vec::new());
+    let new = Arc::new(Vec::new());
+
+    unsaved.insert_objects(region_pos, old.clone());
+    unsaved.insert_objects(region_pos, new.clone());
+
+    // older objects are written, but the newer ones are still pending
+    unsaved.remove_objects(region_pos, &old);
+    assert!(unsaved.get_objects(region_pos).is_some());
+
+    unsaved.remove_objects(region_pos, &new);
+    assert!(unsaved.get_objects(region_pos).is_none());
+}
@@ -0,0 +1,22 @@
+use crate::plugins::game_world::utils::storage::{FsStorage, WorldStorage};
+use bevy::prelude::*;
+use std::sync::Arc;
+
+/// Storage used to save and load worlds
+#[derive(Resource, Clone, Deref)]
+pub struct GameWorldStorage(pub Arc<dyn WorldStorage>);
+
+impl GameWorldStorage {
+    /// Directory where worlds are saved by default
+    pub const SAVE_DIR: &str = "saves";
+
+    pub fn new(storage: impl WorldStorage) -> Self {
+        Self(Arc::new(storage))
+    }
+}
+
+impl Default for GameWorldStorage {
+    fn default() -> Self {
+        Self::new(FsStorage::new(Self::SAVE_DIR))
+    }
+}
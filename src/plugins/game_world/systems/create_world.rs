@@ -1,5 +1,11 @@
 use crate::{
-    plugins::game_world::resources::{meta::GameWorldMeta, GameWorld},
+    plugins::{
+        game_world::{
+            events::SaveErrorEvent,
+            resources::{meta::GameWorldMeta, storage::GameWorldStorage, GameWorld},
+        },
+        world_generator::resources::WorldGenerator,
+    },
     states::game_state::GameState,
 };
 use bevy::prelude::*;
@@ -9,8 +15,18 @@ pub fn start_world_creating(mut commands: Commands) {
     commands.insert_resource(world);
 }
 
-pub fn world_creating_progress(mut game_state: ResMut<State<GameState>>, meta: Res<GameWorldMeta>) {
-    meta.save_self();
+pub fn world_creating_progress(
+    mut game_state: ResMut<State<GameState>>,
+    mut meta: ResMut<GameWorldMeta>,
+    generator: Res<WorldGenerator>,
+    storage: Res<GameWorldStorage>,
+    mut save_errors: EventWriter<SaveErrorEvent>,
+) {
+    meta.generator = generator.pinned_settings();
+
+    if let Err(err) = meta.save_self(&storage) {
+        save_errors.send(SaveErrorEvent::new("Failed to save world", err));
+    }
 
     game_state.set(GameState::InGame).unwrap();
 }
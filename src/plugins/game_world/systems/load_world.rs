@@ -1,38 +1,84 @@
 use crate::{
     plugins::{
-        game_world::resources::{meta::GameWorldMeta, GameWorld},
+        game_world::{
+            events::SaveErrorEvent,
+            resources::{meta::GameWorldMeta, storage::GameWorldStorage, GameWorld},
+        },
         loading::resources::GameAssets,
         objects::resources::objects_registry::ObjectsRegistry,
         player::{
             components::{PlayerComponent, PlayerHand, PlayerHeadComponent},
             resources::PlayerStats,
         },
+        world_generator::resources::WorldGenerator,
     },
     states::game_state::GameState,
 };
 use bevy::prelude::*;
 
+#[allow(clippy::too_many_arguments)]
 pub fn world_loading_system(
     objects_registry: Res<ObjectsRegistry>,
     assets: Res<GameAssets>,
     mut commands: Commands,
     mut game_state: ResMut<State<GameState>>,
 
-    meta: Res<GameWorldMeta>,
+    mut meta: ResMut<GameWorldMeta>,
+    generator: Res<WorldGenerator>,
+    storage: Res<GameWorldStorage>,
     mut player_q: Query<(&mut Transform, &mut PlayerComponent)>,
     player_hand_q: Query<Entity, With<PlayerHand>>,
     mut head_q: Query<&mut Transform, (With<PlayerHeadComponent>, Without<PlayerComponent>)>,
     mut player_stats: ResMut<PlayerStats>,
+    mut save_errors: EventWriter<SaveErrorEvent>,
 ) {
     let world = GameWorld::new();
     commands.insert_resource(world);
 
-    if let Some(player_save) = meta.load_player() {
+    // full recovery is slow, so it is done by the save worker only if the world looks damaged
+    let crashed = meta.begin_session(&storage).unwrap_or_else(|err| {
+        save_errors.send(SaveErrorEvent::new("Failed to open world", err));
+        false
+    });
+    meta.needs_recovery = crashed
+        || !meta.quick_check(&storage).unwrap_or_else(|err| {
+            save_errors.send(SaveErrorEvent::new("Failed to check world", err));
+            false
+        });
+
+    if crashed {
+        warn!(
+            "World {} was not closed properly and will be checked",
+            meta.id
+        );
+    }
+
+    // worlds saved before the biomes were pinned keep the biomes they are loaded with now
+    if meta.generator.biomes.is_empty() {
+        meta.generator = generator.pinned_settings();
+        meta.needs_upgrade = true;
+    }
+
+    if meta.needs_upgrade {
+        info!("Upgrading world {} to the current save format", meta.id);
+        match meta.save_self(&storage) {
+            Ok(_) => meta.needs_upgrade = false,
+            Err(err) => save_errors.send(SaveErrorEvent::new("Failed to upgrade world", err)),
+        }
+    }
+
+    let player_save = meta.load_player(&storage).unwrap_or_else(|err| {
+        save_errors.send(SaveErrorEvent::new("Failed to load player", err));
+        meta.needs_recovery = true;
+        None
+    });
+
+    if let Some(player_save) = player_save {
         let player = player_q.single_mut();
         let mut head = head_q.single_mut();
         let hand = player_hand_q.single();
 
-        player_save.apply_to_player(
+        let result = player_save.apply_to_player(
             &objects_registry,
             &assets,
             &mut commands,
@@ -41,6 +87,10 @@ pub fn world_loading_system(
             &mut head,
             &mut player_stats,
         );
+
+        if let Err(err) = result {
+            save_errors.send(SaveErrorEvent::new("Failed to load item in hand", err));
+        }
     } else {
         warn!("No player save found, creating new player");
     }
@@ -1,5 +1,6 @@
 pub mod create_world;
 pub mod load_world;
 pub mod save;
+pub mod save_errors;
 pub mod setup_world;
 pub mod sun_to_player;
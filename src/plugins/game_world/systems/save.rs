@@ -1,4 +1,5 @@
 use bevy::{
+    app::AppExit,
     prelude::*,
     time::{Timer, TimerMode},
     utils::HashMap,
@@ -8,7 +9,12 @@ use std::time::Duration;
 use crate::{
     internal::pos::ChunkPos,
     plugins::{
-        game_world::resources::{meta::GameWorldMeta, GameWorld},
+        game_world::{
+            events::SaveErrorEvent,
+            resources::{
+                meta::GameWorldMeta, save_worker::SaveWorker, storage::GameWorldStorage, GameWorld,
+            },
+        },
         objects::{
             components::{items::ItemGrabbed, GameWorldObject},
             utils::object_save::GameWorldObjectSave,
@@ -21,6 +27,7 @@ use crate::{
 };
 
 const SAVE_INTERVAL_SECS: u64 = 5;
+const BACKUP_INTERVAL_SECS: u64 = 10 * 60;
 
 pub struct SaveTimer(pub Timer);
 
@@ -33,10 +40,58 @@ impl Default for SaveTimer {
     }
 }
 
+pub struct BackupTimer(pub Timer);
+
+impl Default for BackupTimer {
+    fn default() -> Self {
+        Self(Timer::new(
+            Duration::from_secs(BACKUP_INTERVAL_SECS),
+            TimerMode::Repeating,
+        ))
+    }
+}
+
+pub fn start_save_worker_system(
+    mut worker: ResMut<SaveWorker>,
+    meta: Res<GameWorldMeta>,
+    storage: Res<GameWorldStorage>,
+) {
+    worker.start(meta.clone(), storage.clone());
+}
+
+/// Write everything that is queued before leaving the world
+pub fn stop_save_worker_system(
+    mut worker: ResMut<SaveWorker>,
+    mut save_errors: EventWriter<SaveErrorEvent>,
+) {
+    worker.flush();
+
+    save_errors.send_batch(worker.take_errors());
+    save_errors.send_batch(worker.stop());
+}
+
+/// Make sure that queued data is written before the game is closed
+pub fn flush_save_worker_on_exit_system(
+    mut exit_events: EventReader<AppExit>,
+    mut worker: ResMut<SaveWorker>,
+) {
+    if exit_events.iter().next().is_none() {
+        return;
+    }
+
+    worker.flush();
+
+    for event in worker.take_errors().into_iter().chain(worker.stop()) {
+        error!("{}: {}", event.action, event.error);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn save_system(
     mut timer: Local<SaveTimer>,
-    mut world: ResMut<GameWorld>,
-    meta: Res<GameWorldMeta>,
+    mut save_errors: EventWriter<SaveErrorEvent>,
+    world: Res<GameWorld>,
+    worker: Res<SaveWorker>,
     items: Query<(&GlobalTransform, &GameWorldObject), Without<ItemGrabbed>>,
     time: Res<Time>,
     player_stats: Res<PlayerStats>,
@@ -44,12 +99,12 @@ pub fn save_system(
     head_q: Query<&GlobalTransform, With<PlayerHeadComponent>>,
     item_grabbed_q: Query<(&GameWorldObject, &Transform), With<ItemGrabbed>>,
 ) {
+    save_errors.send_batch(worker.take_errors());
+
     if !timer.0.tick(time.delta()).just_finished() {
         return;
     }
 
-    // TODO save in parallel
-
     // saving player data
     {
         let player = player_q.single();
@@ -57,13 +112,11 @@ pub fn save_system(
 
         let hand_item = item_grabbed_q.iter().next();
 
-        meta.save_player(PlayerSave::new(&player_stats, player, head, hand_item));
+        worker.save_player(PlayerSave::new(&player_stats, player, head, hand_item));
     }
 
     // saving objects
     {
-        let start = std::time::Instant::now();
-
         // objects divided by regions
         let mut objects_to_save: HashMap<ChunkPos, Vec<GameWorldObjectSave>> = HashMap::new();
 
@@ -83,35 +136,18 @@ pub fn save_system(
             objects.push(obj.to_saveable(transform));
         }
 
-        let count = objects_to_save.len();
-
-        // TODO add multithreading
-        // save objects
         for (region_pos, objects) in objects_to_save {
-            meta.save_objects(region_pos, objects);
-        }
-
-        if count > 0 {
-            info!(
-                "Objects in {} regions saved in {}ms",
-                count,
-                start.elapsed().as_millis()
-            );
+            worker.save_objects(region_pos, objects);
         }
     }
 
     // saving chunks
-    {
-        let start = std::time::Instant::now();
-
-        let saved_chunks_count = meta.save_all_chunks(&mut world);
+    worker.save_chunks(GameWorldMeta::snapshot_all_chunks(&world));
+}
 
-        if saved_chunks_count > 0 {
-            info!(
-                "Saved {} chunks in {}ms",
-                saved_chunks_count,
-                start.elapsed().as_millis()
-            );
-        }
+/// Periodically back up the world, backup is made by the save worker after queued data is written
+pub fn backup_system(mut timer: Local<BackupTimer>, time: Res<Time>, worker: Res<SaveWorker>) {
+    if timer.0.tick(time.delta()).just_finished() {
+        worker.backup();
     }
 }
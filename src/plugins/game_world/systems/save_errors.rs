@@ -0,0 +1,39 @@
+use crate::plugins::game_world::events::SaveErrorEvent;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+/// Maximum count of errors shown at once, older errors are dropped
+const MAX_SHOWN_ERRORS: usize = 10;
+
+pub fn show_save_errors_system(
+    mut events: EventReader<SaveErrorEvent>,
+    mut errors: Local<Vec<String>>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    for SaveErrorEvent { action, error } in events.iter() {
+        error!("{}: {}", action, error);
+
+        errors.push(format!("{}: {}", action, error));
+    }
+
+    if errors.is_empty() {
+        return;
+    }
+
+    if errors.len() > MAX_SHOWN_ERRORS {
+        let count = errors.len() - MAX_SHOWN_ERRORS;
+        errors.drain(0..count);
+    }
+
+    egui::Window::new("Save error")
+        .collapsible(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            for error in errors.iter() {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+
+            if ui.button("Ok").clicked() {
+                errors.clear();
+            }
+        });
+}
@@ -0,0 +1,65 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Extension of temporary files created by [`write_atomic`]
+pub const TMP_EXTENSION: &str = "tmp";
+
+/// Path of the temporary file used to write `path`
+pub fn tmp_path(path: &Path) -> PathBuf {
+    let mut result = path.as_os_str().to_owned();
+    result.push(".");
+    result.push(TMP_EXTENSION);
+    result.into()
+}
+
+/// Flush directory entry changes (created or renamed files) to the disk
+pub fn sync_dir(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+
+    // directories can't be opened as files on other platforms
+    #[cfg(not(unix))]
+    let _ = dir;
+
+    Ok(())
+}
+
+/// Replace content of the file at `path` with `bytes`.
+///
+/// Data is written to a temporary file first and then renamed over `path`,
+/// so if the game is killed in the middle of the save the file contains
+/// either old or new data but never a part of it.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(dir)?;
+
+    let tmp_path = tmp_path(path);
+
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp_path, path)?;
+    sync_dir(dir)
+}
+
+#[test]
+fn write_atomic_replaces_file() {
+    let dir = std::env::temp_dir().join(format!("atomic_file_test_{}", std::process::id()));
+    let path = dir.join("nested/data");
+
+    write_atomic(&path, &[1, 2, 3]).unwrap();
+    write_atomic(&path, &[4, 5]).unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), vec![4, 5]);
+    assert!(
+        !tmp_path(&path).exists(),
+        "Temporary file should be renamed"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
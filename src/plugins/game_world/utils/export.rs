@@ -0,0 +1,448 @@
+use super::save_error::SaveError;
+use crate::{
+    internal::{
+        chunks::{apron::ChunkApron, pointer::ChunkPointer, Chunk},
+        direction::Direction,
+        pos::ChunkPos,
+    },
+    plugins::{
+        game_world::resources::{meta::GameWorldMeta, storage::GameWorldStorage, GameWorld},
+        objects::resources::objects_registry::ObjectsRegistry,
+        static_mesh::components::IndexedMesh,
+        world_generator::{internal::biomes::ChunkBiomes, resources::WorldGenerator},
+    },
+};
+use bevy::{prelude::*, utils::HashMap};
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use strum::IntoEnumIterator;
+
+/// Terrain mesh of the chunk
+pub struct ExportChunk {
+    pub pos: ChunkPos,
+    pub level: usize,
+    pub translation: Vec3,
+    /// Indexed triangle list in chunk space
+    pub mesh: IndexedMesh,
+}
+
+/// Placed object, exported as a reference to its model file
+pub struct ExportObject {
+    pub id: String,
+    /// Path of the model inside of the assets directory
+    pub model: &'static str,
+    pub transform: Transform,
+}
+
+/// Terrain and objects of a box of chunks prepared for writing to glTF or OBJ files
+#[derive(Default)]
+pub struct WorldExport {
+    pub chunks: Vec<ExportChunk>,
+    pub objects: Vec<ExportObject>,
+}
+
+impl WorldExport {
+    /// Build meshes of all chunks between `from` and `to` (inclusive) at given `level`.
+    ///
+    /// Saved changes are applied on top of the generated terrain.
+    /// Objects are exported only if `registry` is set and only objects of visited regions are saved.
+    pub fn collect(
+        meta: &GameWorldMeta,
+        storage: &GameWorldStorage,
+        gen: &WorldGenerator,
+        registry: Option<&ObjectsRegistry>,
+        from: ChunkPos,
+        to: ChunkPos,
+        level: usize,
+    ) -> Result<Self, SaveError> {
+        let min = ChunkPos::new(from.x.min(to.x), from.y.min(to.y), from.z.min(to.z));
+        let max = ChunkPos::new(from.x.max(to.x), from.y.max(to.y), from.z.max(to.z));
+
+        let mut result = Self::default();
+        let mut regions: HashMap<ChunkPos, ChunkBiomes> = HashMap::new();
+        let mut chunks = Vec::new();
+
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    let pos = ChunkPos::new(x, y, z);
+                    let region_pos = GameWorld::level_pos_to_level_pos(pos, level, 0);
+
+                    let biomes = regions
+                        .entry(region_pos)
+                        .or_insert_with(|| ChunkBiomes::new(gen, region_pos))
+                        .clone();
+
+                    let chunk = match meta.load_chunk(storage, pos, level)? {
+                        Some(saved) => saved.into_chunk(gen, biomes, pos, level),
+                        None => Chunk::generate(gen, biomes, pos, level),
+                    };
+
+                    chunks.push((pos, chunk));
+                }
+            }
+        }
+
+        // neighbors inside of the box give the same normals on both sides of the border
+        let indices = chunks
+            .iter()
+            .enumerate()
+            .map(|(i, (pos, _))| (*pos, i))
+            .collect::<HashMap<_, _>>();
+        let aprons = chunks
+            .iter()
+            .map(|(pos, _)| {
+                let mut apron = ChunkApron::default();
+                for dir in Direction::iter() {
+                    let neighbor_pos = *pos + ChunkPos::from(dir);
+                    let neighbor = indices.get(&neighbor_pos).map(|i| &chunks[*i].1);
+                    apron.set_face(dir, neighbor);
+                }
+                apron
+            })
+            .collect::<Vec<_>>();
+
+        for ((pos, mut chunk), apron) in chunks.into_iter().zip(aprons) {
+            chunk.set_apron(apron);
+
+            let blocks = chunk.generate_mesh(gen, pos, level);
+            let mesh = IndexedMesh::join(blocks.iter().map(|(_, mesh)| mesh));
+            if mesh.is_empty() {
+                continue;
+            }
+
+            result.chunks.push(ExportChunk {
+                pos,
+                level,
+                translation: ChunkPointer::new(chunk, pos, level).get_translation(),
+                mesh,
+            });
+        }
+
+        let registry = match registry {
+            Some(registry) => registry,
+            None => return Ok(result),
+        };
+
+        let scale = GameWorld::level_to_scale(level) as f32 * Chunk::REAL_SIZE;
+        let bounds_min = (min.to_vec3()) * scale;
+        let bounds_max = (max.to_vec3() + Vec3::ONE) * scale;
+
+        for region_pos in regions.into_keys() {
+            let objects = match meta.load_objects(storage, region_pos)? {
+                Some(objects) => objects,
+                None => continue,
+            };
+
+            let offset = GameWorld::region_pos_to_translation(region_pos);
+
+            for object in objects {
+                // objects that can't be loaded are skipped by the game too
+                let spawner = match object.to_spawner(registry, offset) {
+                    Ok(spawner) => spawner,
+                    Err(err) => {
+                        warn!("Skipping object in region {:?}: {}", region_pos, err);
+                        continue;
+                    }
+                };
+                let translation = spawner.transform.translation;
+
+                if translation.cmplt(bounds_min).any() || translation.cmpge(bounds_max).any() {
+                    continue;
+                }
+
+                if let Some(object) = spawner.object {
+                    result.objects.push(ExportObject {
+                        id: spawner.id,
+                        model: object.model_path(),
+                        transform: spawner.transform,
+                    });
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Write terrain as Wavefront OBJ with vertex colors, objects are written as comments
+    pub fn write_obj<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "# exported from primitive engineering")?;
+
+        let mut offset = 1;
+
+        for chunk in self.chunks.iter() {
+            writeln!(out, "o chunk_{:?}_{}", chunk.pos, chunk.level)?;
+
+            for vertex in chunk.mesh.vertices.iter() {
+                let pos = vertex.pos + chunk.translation;
+                let [r, g, b, _]: [f32; 4] = vertex.color.into();
+                writeln!(out, "v {} {} {} {} {} {}", pos.x, pos.y, pos.z, r, g, b)?;
+            }
+
+            for vertex in chunk.mesh.vertices.iter() {
+                let normal = vertex.normal;
+                writeln!(out, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+            }
+
+            for [a, b, c] in chunk.mesh.triangles() {
+                writeln!(
+                    out,
+                    "f {a}//{a} {b}//{b} {c}//{c}",
+                    a = offset + a as usize,
+                    b = offset + b as usize,
+                    c = offset + c as usize
+                )?;
+            }
+
+            offset += chunk.mesh.vertices.len();
+        }
+
+        for object in self.objects.iter() {
+            let t = object.transform;
+            writeln!(
+                out,
+                "# object {} {} translation {} {} {} rotation {} {} {} {} scale {} {} {}",
+                object.id,
+                object.model,
+                t.translation.x,
+                t.translation.y,
+                t.translation.z,
+                t.rotation.x,
+                t.rotation.y,
+                t.rotation.z,
+                t.rotation.w,
+                t.scale.x,
+                t.scale.y,
+                t.scale.z
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Write terrain and objects as binary glTF 2.0.
+    ///
+    /// Each chunk is a separate node, objects are empty nodes with the path of their model
+    /// in `extras.model`.
+    pub fn write_glb<W: Write>(&self, mut out: W) -> io::Result<()> {
+        const FLOAT: u32 = 5126;
+        const UNSIGNED_INT: u32 = 5125;
+        const ARRAY_BUFFER: u32 = 34962;
+        const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+        const TRIANGLES: u32 = 4;
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut buffer_views = Vec::new();
+        let mut accessors = Vec::new();
+        let mut meshes = Vec::new();
+        let mut nodes = Vec::new();
+
+        // all components are 4 bytes long, so views are always aligned
+        let mut push_accessor = |data: Vec<[u8; 4]>,
+                                 components: usize,
+                                 component_type: u32,
+                                 kind: &str,
+                                 target: u32,
+                                 bounds: Value| {
+            let offset = buffer.len();
+            for v in data.iter() {
+                buffer.extend_from_slice(v);
+            }
+
+            buffer_views.push(json!({
+                "buffer": 0,
+                "byteOffset": offset,
+                "byteLength": data.len() * 4,
+                "target": target,
+            }));
+
+            let mut accessor = json!({
+                "bufferView": buffer_views.len() - 1,
+                "componentType": component_type,
+                "count": data.len() / components,
+                "type": kind,
+            });
+
+            if let (Value::Object(accessor), Value::Object(bounds)) = (&mut accessor, bounds) {
+                accessor.extend(bounds);
+            }
+
+            accessors.push(accessor);
+            accessors.len() - 1
+        };
+
+        for chunk in self.chunks.iter() {
+            let mut min = Vec3::splat(f32::MAX);
+            let mut max = Vec3::splat(f32::MIN);
+
+            let vertices = &chunk.mesh.vertices;
+
+            let mut positions = Vec::with_capacity(vertices.len() * 3);
+            let mut normals = Vec::with_capacity(vertices.len() * 3);
+            let mut colors = Vec::with_capacity(vertices.len() * 4);
+
+            for vertex in vertices.iter() {
+                min = min.min(vertex.pos);
+                max = max.max(vertex.pos);
+
+                positions.extend(vertex.pos.to_array().map(f32::to_le_bytes));
+                normals.extend(vertex.normal.to_array().map(f32::to_le_bytes));
+                // glTF vertex colors are linear
+                colors.extend(vertex.color.as_linear_rgba_f32().map(f32::to_le_bytes));
+            }
+
+            let indices = chunk
+                .mesh
+                .indices
+                .iter()
+                .map(|i| i.to_le_bytes())
+                .collect::<Vec<_>>();
+
+            // position accessor must have bounds
+            let position = push_accessor(
+                positions,
+                3,
+                FLOAT,
+                "VEC3",
+                ARRAY_BUFFER,
+                json!({ "min": min.to_array(), "max": max.to_array() }),
+            );
+            let normal = push_accessor(normals, 3, FLOAT, "VEC3", ARRAY_BUFFER, json!({}));
+            let color = push_accessor(colors, 4, FLOAT, "VEC4", ARRAY_BUFFER, json!({}));
+            let indices = push_accessor(
+                indices,
+                1,
+                UNSIGNED_INT,
+                "SCALAR",
+                ELEMENT_ARRAY_BUFFER,
+                json!({}),
+            );
+
+            meshes.push(json!({
+                "primitives": [{
+                    "attributes": {
+                        "POSITION": position,
+                        "NORMAL": normal,
+                        "COLOR_0": color,
+                    },
+                    "indices": indices,
+                    "mode": TRIANGLES,
+                }],
+            }));
+
+            nodes.push(json!({
+                "name": format!("chunk[{:?}-{}]", chunk.pos, chunk.level),
+                "mesh": meshes.len() - 1,
+                "translation": chunk.translation.to_array(),
+            }));
+        }
+
+        for object in self.objects.iter() {
+            nodes.push(json!({
+                "name": object.id,
+                "translation": object.transform.translation.to_array(),
+                "rotation": object.transform.rotation.to_array(),
+                "scale": object.transform.scale.to_array(),
+                "extras": { "model": object.model },
+            }));
+        }
+
+        let mut gltf = json!({
+            "asset": { "version": "2.0", "generator": "primitive engineering" },
+            "scene": 0,
+            "scenes": [{ "nodes": (0..nodes.len()).collect::<Vec<_>>() }],
+            "nodes": nodes,
+        });
+
+        if !meshes.is_empty() {
+            gltf["meshes"] = json!(meshes);
+            gltf["accessors"] = json!(accessors);
+            gltf["bufferViews"] = json!(buffer_views);
+            gltf["buffers"] = json!([{ "byteLength": buffer.len() }]);
+        }
+
+        let mut json = serde_json::to_vec(&gltf)?;
+
+        // chunks should be aligned to 4 bytes
+        while json.len() % 4 != 0 {
+            json.push(b' ');
+        }
+        while buffer.len() % 4 != 0 {
+            buffer.push(0);
+        }
+
+        let bin_len = if buffer.is_empty() {
+            0
+        } else {
+            8 + buffer.len()
+        };
+        let total_len = 12 + 8 + json.len() + bin_len;
+
+        out.write_all(b"glTF")?;
+        out.write_all(&2u32.to_le_bytes())?;
+        out.write_all(&(total_len as u32).to_le_bytes())?;
+
+        out.write_all(&(json.len() as u32).to_le_bytes())?;
+        out.write_all(b"JSON")?;
+        out.write_all(&json)?;
+
+        if !buffer.is_empty() {
+            out.write_all(&(buffer.len() as u32).to_le_bytes())?;
+            out.write_all(b"BIN\0")?;
+            out.write_all(&buffer)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn export_glb_layout() {
+    use crate::plugins::{loading::resources::models, static_mesh::components::Vertex};
+
+    let export = WorldExport {
+        chunks: vec![ExportChunk {
+            pos: ChunkPos::new(0, 0, 0),
+            level: 0,
+            translation: Vec3::ZERO,
+            mesh: IndexedMesh {
+                vertices: vec![
+                    Vertex {
+                        pos: Vec3::ZERO,
+                        normal: Vec3::Y,
+                        color: Color::WHITE,
+                        texture_weights: Vec4::ZERO,
+                    };
+                    3
+                ],
+                indices: vec![0, 1, 2],
+            },
+        }],
+        objects: vec![ExportObject {
+            id: "tree".to_string(),
+            model: models::TREE,
+            transform: Transform::default(),
+        }],
+    };
+
+    let mut glb = Vec::new();
+    export.write_glb(&mut glb).unwrap();
+
+    assert_eq!(&glb[0..4], b"glTF");
+    assert_eq!(glb.len() % 4, 0);
+    assert_eq!(
+        u32::from_le_bytes(glb[8..12].try_into().unwrap()) as usize,
+        glb.len()
+    );
+
+    let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+    let gltf: Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+    assert_eq!(gltf["nodes"].as_array().unwrap().len(), 2);
+    assert_eq!(gltf["nodes"][1]["extras"]["model"], models::TREE);
+    assert_eq!(gltf["accessors"][0]["count"], 3);
+    assert_eq!(gltf["meshes"][0]["primitives"][0]["indices"], 3);
+
+    let mut obj = Vec::new();
+    export.write_obj(&mut obj).unwrap();
+    assert!(String::from_utf8(obj).unwrap().contains("f 1//1 2//2 3//3"));
+}
@@ -0,0 +1,6 @@
+pub mod atomic_file;
+pub mod export;
+pub mod region_file;
+pub mod save_error;
+pub mod save_format;
+pub mod storage;
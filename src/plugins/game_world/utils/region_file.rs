@@ -0,0 +1,473 @@
+use crate::internal::pos::{ChunkPos, VoxelPos};
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, Read, Seek, SeekFrom, Write},
+    sync::{Arc, Mutex},
+};
+
+/// Position of the chunk inside of the region file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RegionChunkKey {
+    pub level: u8,
+    pub x: u8,
+    pub y: u8,
+    pub z: u8,
+}
+
+impl RegionChunkKey {
+    /// `in_region_pos`: chunk position relative to the region at given `level`
+    pub fn new(in_region_pos: VoxelPos, level: usize) -> Self {
+        Self {
+            level: level as u8,
+            x: in_region_pos.x as u8,
+            y: in_region_pos.y as u8,
+            z: in_region_pos.z as u8,
+        }
+    }
+}
+
+const MAGIC: [u8; 8] = *b"PEREGION";
+const HEADER_SIZE: usize = 32;
+
+/// Continuous byte range inside of the region file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegionSlot {
+    pub offset: u64,
+    pub len: u64,
+}
+
+impl RegionSlot {
+    fn end(&self) -> u64 {
+        self.offset + self.len
+    }
+}
+
+/// Storage of the region file that can flush written data to the disk.
+pub trait RegionStorage: Read + Write + Seek {
+    /// Make sure that all written data is persisted
+    fn sync(&mut self) -> io::Result<()>;
+}
+
+impl RegionStorage for fs::File {
+    fn sync(&mut self) -> io::Result<()> {
+        self.sync_data()
+    }
+}
+
+impl<S: RegionStorage + ?Sized> RegionStorage for Box<S> {
+    fn sync(&mut self) -> io::Result<()> {
+        (**self).sync()
+    }
+}
+
+impl RegionStorage for io::Cursor<Vec<u8>> {
+    fn sync(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Container for all chunk blobs of a single region.
+///
+/// File layout:
+/// - header (32 bytes): magic, offset and length of the index
+/// - data area: chunk blobs and the serialized index in any order
+///
+/// Data is never overwritten in place: a new blob is written to a free gap
+/// (or appended to the end of the file) and space of the replaced blob is
+/// reused only after the index pointing to the new blob is committed.
+/// So the header always points to a consistent index.
+///
+/// Data is synced to the disk before the header is updated, so a crash in the
+/// middle of the commit leaves the previous index intact.
+pub struct RegionFile<F: RegionStorage> {
+    file: F,
+    index: HashMap<RegionChunkKey, RegionSlot>,
+    /// Slot of the last committed index
+    index_slot: Option<RegionSlot>,
+    /// Gaps that can be reused, sorted by offset
+    free: Vec<RegionSlot>,
+    /// Slots that are still referenced by the committed index
+    released: Vec<RegionSlot>,
+    /// Length of the file
+    len: u64,
+    dirty: bool,
+}
+
+impl<F: RegionStorage> RegionFile<F> {
+    /// Compact the file if more than this fraction of it is unused
+    const MAX_FREE_RATIO: f64 = 0.5;
+    /// Files smaller than this are never compacted
+    const MIN_COMPACT_SIZE: u64 = 64 * 1024;
+
+    /// Open region file, empty `file` is treated as a new region.
+    pub fn open(mut file: F) -> io::Result<Self> {
+        let len = file.seek(SeekFrom::End(0))?;
+
+        if len == 0 {
+            return Ok(Self {
+                file,
+                index: HashMap::new(),
+                index_slot: None,
+                free: Vec::new(),
+                released: Vec::new(),
+                len: 0,
+                dirty: false,
+            });
+        }
+
+        let mut header = [0u8; HEADER_SIZE];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut header)?;
+
+        if header[0..8] != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid region file header",
+            ));
+        }
+
+        let index_slot = RegionSlot {
+            offset: u64::from_le_bytes(header[8..16].try_into().unwrap()),
+            len: u64::from_le_bytes(header[16..24].try_into().unwrap()),
+        };
+
+        if index_slot.offset < HEADER_SIZE as u64 || index_slot.end() > len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "region index is out of file bounds",
+            ));
+        }
+
+        let mut index_bytes = vec![0u8; index_slot.len as usize];
+        file.seek(SeekFrom::Start(index_slot.offset))?;
+        file.read_exact(&mut index_bytes)?;
+
+        let entries: Vec<(RegionChunkKey, RegionSlot)> = bincode::deserialize(&index_bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        let mut result = Self {
+            file,
+            index: entries.into_iter().collect(),
+            index_slot: Some(index_slot),
+            free: Vec::new(),
+            released: Vec::new(),
+            len,
+            dirty: false,
+        };
+        result.free = result.collect_gaps();
+
+        Ok(result)
+    }
+
+    /// Find all unused ranges between header and the end of the file
+    fn collect_gaps(&self) -> Vec<RegionSlot> {
+        let mut used = self
+            .index
+            .values()
+            .copied()
+            .chain(self.index_slot)
+            .collect::<Vec<_>>();
+        used.sort_by_key(|slot| slot.offset);
+
+        let mut gaps = Vec::new();
+        let mut cursor = HEADER_SIZE as u64;
+        for slot in used {
+            if slot.offset > cursor {
+                gaps.push(RegionSlot {
+                    offset: cursor,
+                    len: slot.offset - cursor,
+                });
+            }
+            cursor = cursor.max(slot.end());
+        }
+
+        if self.len > cursor {
+            gaps.push(RegionSlot {
+                offset: cursor,
+                len: self.len - cursor,
+            });
+        }
+
+        gaps
+    }
+
+    /// Number of chunks stored in the region
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &RegionChunkKey> {
+        self.index.keys()
+    }
+
+    /// Get location of the chunk blob inside of the file
+    pub fn slot(&self, key: RegionChunkKey) -> Option<RegionSlot> {
+        self.index.get(&key).copied()
+    }
+
+    pub fn contains(&self, key: RegionChunkKey) -> bool {
+        self.index.contains_key(&key)
+    }
+
+    /// Size of the file in bytes
+    pub fn file_size(&self) -> u64 {
+        self.len
+    }
+
+    /// Amount of bytes that are not used by any blob
+    pub fn free_size(&self) -> u64 {
+        self.free
+            .iter()
+            .chain(self.released.iter())
+            .map(|slot| slot.len)
+            .sum()
+    }
+
+    pub fn read(&mut self, key: RegionChunkKey) -> io::Result<Option<Vec<u8>>> {
+        let slot = match self.index.get(&key) {
+            Some(slot) => *slot,
+            None => return Ok(None),
+        };
+
+        let mut data = vec![0u8; slot.len as usize];
+        self.file.seek(SeekFrom::Start(slot.offset))?;
+        self.file.read_exact(&mut data)?;
+
+        Ok(Some(data))
+    }
+
+    /// Find place for the blob with the given length, reusing free gaps if possible
+    fn allocate(&mut self, len: u64) -> u64 {
+        let start = self.len.max(HEADER_SIZE as u64);
+
+        if let Some(i) = self.free.iter().position(|gap| gap.len >= len) {
+            let gap = self.free[i];
+            if gap.len == len {
+                self.free.remove(i);
+            } else {
+                self.free[i] = RegionSlot {
+                    offset: gap.offset + len,
+                    len: gap.len - len,
+                };
+            }
+            return gap.offset;
+        }
+
+        // merge trailing gap with the new space at the end of the file
+        if let Some(last) = self.free.last().copied() {
+            if last.end() == start {
+                self.free.pop();
+                self.len = last.offset + len;
+                return last.offset;
+            }
+        }
+
+        self.len = start + len;
+        start
+    }
+
+    /// Return slot to the free list, merging it with adjacent gaps
+    fn release(&mut self, slot: RegionSlot) {
+        if slot.len == 0 {
+            return;
+        }
+
+        let i = self.free.partition_point(|gap| gap.offset < slot.offset);
+        self.free.insert(i, slot);
+
+        if i + 1 < self.free.len() && self.free[i].end() == self.free[i + 1].offset {
+            self.free[i].len += self.free[i + 1].len;
+            self.free.remove(i + 1);
+        }
+        if i > 0 && self.free[i - 1].end() == self.free[i].offset {
+            self.free[i - 1].len += self.free[i].len;
+            self.free.remove(i);
+        }
+    }
+
+    fn write_at(&mut self, offset: u64, data: &[u8]) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(data)
+    }
+
+    /// Write chunk blob, changes are visible after [`RegionFile::commit`]
+    pub fn write(&mut self, key: RegionChunkKey, data: &[u8]) -> io::Result<()> {
+        let len = data.len() as u64;
+        let offset = self.allocate(len);
+        self.write_at(offset, data)?;
+
+        if let Some(prev) = self.index.insert(key, RegionSlot { offset, len }) {
+            self.released.push(prev);
+        }
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, key: RegionChunkKey) {
+        if let Some(prev) = self.index.remove(&key) {
+            self.released.push(prev);
+            self.dirty = true;
+        }
+    }
+
+    fn encode_header(index_slot: RegionSlot) -> [u8; HEADER_SIZE] {
+        let mut header = [0u8; HEADER_SIZE];
+        header[0..8].copy_from_slice(&MAGIC);
+        header[8..16].copy_from_slice(&index_slot.offset.to_le_bytes());
+        header[16..24].copy_from_slice(&index_slot.len.to_le_bytes());
+        header
+    }
+
+    fn encode_index(&self) -> Vec<u8> {
+        let entries = self
+            .index
+            .iter()
+            .map(|(key, slot)| (*key, *slot))
+            .collect::<Vec<_>>();
+
+        bincode::serialize(&entries).expect("region index should be serializable")
+    }
+
+    /// Write index of the region and point header to it
+    pub fn commit(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let index = self.encode_index();
+        let index_slot = RegionSlot {
+            offset: self.allocate(index.len() as u64),
+            len: index.len() as u64,
+        };
+        self.write_at(index_slot.offset, &index)?;
+        self.file.flush()?;
+        self.file.sync()?;
+
+        self.write_at(0, &Self::encode_header(index_slot))?;
+        self.file.flush()?;
+        self.file.sync()?;
+
+        // previous index and replaced blobs are not referenced anymore
+        if let Some(prev) = self.index_slot.replace(index_slot) {
+            self.release(prev);
+        }
+        for slot in std::mem::take(&mut self.released) {
+            self.release(slot);
+        }
+        self.dirty = false;
+
+        Ok(())
+    }
+
+    /// Check if the file has too much unused space
+    pub fn need_compact(&self) -> bool {
+        self.len >= Self::MIN_COMPACT_SIZE
+            && self.free_size() as f64 > self.len as f64 * Self::MAX_FREE_RATIO
+    }
+
+    /// Write compacted copy of the region without gaps to `out`
+    pub fn compact_into<W: Write>(&mut self, mut out: W) -> io::Result<()> {
+        let mut entries = self
+            .index
+            .iter()
+            .map(|(key, slot)| (*key, *slot))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(_, slot)| slot.offset);
+
+        let mut compacted = RegionFile {
+            file: io::Cursor::new(Vec::new()),
+            index: HashMap::new(),
+            index_slot: None,
+            free: Vec::new(),
+            released: Vec::new(),
+            len: 0,
+            dirty: false,
+        };
+
+        for (key, _) in entries {
+            let data = self.read(key)?.expect("key is taken from the index");
+            compacted.write(key, &data)?;
+        }
+        compacted.dirty = true;
+        compacted.commit()?;
+
+        out.write_all(compacted.file.get_ref())?;
+        out.flush()
+    }
+
+    pub fn into_inner(self) -> F {
+        self.file
+    }
+}
+
+/// Locks that prevent concurrent access to the same region file.
+///
+/// All clones share the same set of locks.
+#[derive(Debug, Default, Clone)]
+pub struct RegionLocks(Arc<Mutex<HashMap<ChunkPos, Arc<Mutex<()>>>>>);
+
+impl RegionLocks {
+    pub fn get(&self, region_pos: ChunkPos) -> Arc<Mutex<()>> {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(region_pos)
+            .or_default()
+            .clone()
+    }
+}
+
+#[test]
+fn region_file_read_write() {
+    let mut region = RegionFile::open(io::Cursor::new(Vec::new())).unwrap();
+
+    let a = RegionChunkKey::new(VoxelPos::new(0, 0, 0), 0);
+    let b = RegionChunkKey::new(VoxelPos::new(31, 2, 7), 5);
+
+    region.write(a, &[1, 2, 3]).unwrap();
+    region.write(b, &[4; 100]).unwrap();
+    region.commit().unwrap();
+
+    let mut region = RegionFile::open(region.into_inner()).unwrap();
+
+    assert_eq!(region.len(), 2);
+    assert_eq!(region.read(a).unwrap(), Some(vec![1, 2, 3]));
+    assert_eq!(region.read(b).unwrap(), Some(vec![4; 100]));
+    assert_eq!(
+        region
+            .read(RegionChunkKey::new(VoxelPos::new(1, 0, 0), 1))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn region_file_reuse_free_space() {
+    let mut region = RegionFile::open(io::Cursor::new(Vec::new())).unwrap();
+    let key = RegionChunkKey::new(VoxelPos::new(1, 1, 1), 3);
+
+    for i in 0..100u8 {
+        region.write(key, &[i; 256]).unwrap();
+        region.commit().unwrap();
+    }
+
+    assert!(
+        region.file_size() < 4 * 1024,
+        "space of rewritten chunk should be reused, file size: {}",
+        region.file_size()
+    );
+
+    let mut compacted = Vec::new();
+    region.compact_into(&mut compacted).unwrap();
+
+    let mut region = RegionFile::open(io::Cursor::new(compacted)).unwrap();
+    assert_eq!(region.free_size(), 0);
+    assert_eq!(region.read(key).unwrap(), Some(vec![99; 256]));
+}
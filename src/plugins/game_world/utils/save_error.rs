@@ -0,0 +1,85 @@
+use super::save_format::{SaveKind, SaveVersion};
+use std::{fmt, io};
+
+/// Error that can happen while saving or loading the world
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Encode(bincode::Error),
+    Decode(bincode::Error),
+    /// Data doesn't match the expected format
+    Corrupted(String),
+    /// Data was written by a newer version of the game
+    UnsupportedVersion {
+        kind: SaveKind,
+        version: SaveVersion,
+    },
+    Migration {
+        kind: SaveKind,
+        version: SaveVersion,
+        reason: String,
+    },
+    UnknownObject(String),
+    ObjectDeserialization {
+        id: String,
+        reason: String,
+    },
+    /// Error happened while reading or writing the file at `path`
+    File {
+        path: String,
+        error: Box<SaveError>,
+    },
+}
+
+impl SaveError {
+    /// Attach path of the file to the error
+    pub fn in_file(self, path: impl Into<String>) -> Self {
+        match self {
+            Self::File { .. } => self,
+            error => Self::File {
+                path: path.into(),
+                error: Box::new(error),
+            },
+        }
+    }
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {}", err),
+            Self::Encode(err) => write!(f, "failed to encode data: {}", err),
+            Self::Decode(err) => write!(f, "failed to decode data: {}", err),
+            Self::Corrupted(reason) => write!(f, "data is corrupted: {}", reason),
+            Self::UnsupportedVersion { kind, version } => write!(
+                f,
+                "{:?} version {} is newer than supported version {}",
+                kind,
+                version,
+                kind.current_version()
+            ),
+            Self::Migration {
+                kind,
+                version,
+                reason,
+            } => write!(
+                f,
+                "failed to upgrade {:?} from version {}: {}",
+                kind, version, reason
+            ),
+            Self::UnknownObject(id) => write!(f, "unknown object id {}", id),
+            Self::ObjectDeserialization { id, reason } => {
+                write!(f, "failed to deserialize object {}: {}", id, reason)
+            }
+            Self::File { path, error } => write!(f, "{}: {}", path, error),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
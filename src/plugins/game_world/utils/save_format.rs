@@ -0,0 +1,214 @@
+use super::save_error::SaveError;
+use crate::{
+    internal::chunks::sparse::SparseChunk, plugins::game_world::resources::meta::GameWorldMeta,
+};
+use bevy::utils::HashMap;
+
+pub type SaveVersion = u16;
+
+/// Upgrade raw (uncompressed) payload from version `N` to version `N + 1`
+pub type SaveMigrationFn = fn(Vec<u8>) -> Result<Vec<u8>, String>;
+
+/// Kind of the data stored in a save file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum SaveKind {
+    Meta = 0,
+    Player = 1,
+    Objects = 2,
+    Chunk = 3,
+    /// Archive with all files of the world
+    Backup = 4,
+}
+
+impl SaveKind {
+    /// Version of the payload written by the current build
+    pub const fn current_version(&self) -> SaveVersion {
+        match self {
+            Self::Meta => 5,
+            Self::Player => 1,
+            Self::Objects => 1,
+            Self::Chunk => 2,
+            Self::Backup => 1,
+        }
+    }
+}
+
+impl TryFrom<u8> for SaveKind {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Meta),
+            1 => Ok(Self::Player),
+            2 => Ok(Self::Objects),
+            3 => Ok(Self::Chunk),
+            4 => Ok(Self::Backup),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Header written in front of every save file.
+///
+/// Files written before the header was introduced have version `0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveHeader {
+    pub kind: SaveKind,
+    pub version: SaveVersion,
+    pub compressed: bool,
+}
+
+impl SaveHeader {
+    const MAGIC: [u8; 4] = *b"PESV";
+    pub const SIZE: usize = 8;
+
+    /// Create header for the current version of the given `kind`
+    pub fn current(kind: SaveKind, compressed: bool) -> Self {
+        Self {
+            kind,
+            version: kind.current_version(),
+            compressed,
+        }
+    }
+
+    /// Header for files without header
+    pub fn legacy(kind: SaveKind, compressed: bool) -> Self {
+        Self {
+            kind,
+            version: 0,
+            compressed,
+        }
+    }
+
+    pub fn encode(&self) -> [u8; Self::SIZE] {
+        let mut result = [0u8; Self::SIZE];
+        result[0..4].copy_from_slice(&Self::MAGIC);
+        result[4] = self.kind as u8;
+        result[5..7].copy_from_slice(&self.version.to_le_bytes());
+        result[7] = self.compressed as u8;
+        result
+    }
+
+    /// Split `bytes` into header and payload.
+    ///
+    /// Returns `None` if `bytes` doesn't start with a valid header.
+    pub fn decode(bytes: &[u8]) -> Option<(Self, &[u8])> {
+        if bytes.len() < Self::SIZE || bytes[0..4] != Self::MAGIC {
+            return None;
+        }
+
+        let header = Self {
+            kind: SaveKind::try_from(bytes[4]).ok()?,
+            version: SaveVersion::from_le_bytes([bytes[5], bytes[6]]),
+            compressed: bytes[7] != 0,
+        };
+
+        Some((header, &bytes[Self::SIZE..]))
+    }
+
+    pub fn is_outdated(&self) -> bool {
+        self.version < self.kind.current_version()
+    }
+}
+
+/// Registry of steps that upgrade old payloads to the current version
+#[derive(Debug, Clone)]
+pub struct SaveMigrations {
+    steps: HashMap<(SaveKind, SaveVersion), SaveMigrationFn>,
+}
+
+impl SaveMigrations {
+    pub fn new() -> Self {
+        let mut result = Self {
+            steps: HashMap::new(),
+        };
+
+        // Files without header have the same payload as version 1
+        result.register(SaveKind::Meta, 0, Ok);
+        result.register(SaveKind::Player, 0, Ok);
+        result.register(SaveKind::Objects, 0, Ok);
+        result.register(SaveKind::Chunk, 0, Ok);
+
+        result.register(SaveKind::Meta, 1, GameWorldMeta::migrate_from_v1);
+        result.register(SaveKind::Meta, 2, GameWorldMeta::migrate_from_v2);
+        result.register(SaveKind::Meta, 3, GameWorldMeta::migrate_from_v3);
+        result.register(SaveKind::Meta, 4, GameWorldMeta::migrate_from_v4);
+        result.register(SaveKind::Chunk, 1, SparseChunk::migrate_from_dense);
+
+        result
+    }
+
+    /// Register step that upgrades payload of the `kind` from version `from` to `from + 1`
+    pub fn register(&mut self, kind: SaveKind, from: SaveVersion, step: SaveMigrationFn) {
+        self.steps.insert((kind, from), step);
+    }
+
+    /// Upgrade `payload` written with `header` to the current version
+    pub fn migrate(&self, header: SaveHeader, mut payload: Vec<u8>) -> Result<Vec<u8>, SaveError> {
+        let target = header.kind.current_version();
+
+        if header.version > target {
+            return Err(SaveError::UnsupportedVersion {
+                kind: header.kind,
+                version: header.version,
+            });
+        }
+
+        for version in header.version..target {
+            let step =
+                self.steps
+                    .get(&(header.kind, version))
+                    .ok_or_else(|| SaveError::Migration {
+                        kind: header.kind,
+                        version,
+                        reason: "no migration registered".to_string(),
+                    })?;
+
+            payload = step(payload).map_err(|reason| SaveError::Migration {
+                kind: header.kind,
+                version,
+                reason,
+            })?;
+        }
+
+        Ok(payload)
+    }
+}
+
+impl Default for SaveMigrations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn save_header_round_trip() {
+    let header = SaveHeader::current(SaveKind::Chunk, true);
+
+    let mut bytes = header.encode().to_vec();
+    bytes.extend_from_slice(&[1, 2, 3]);
+
+    let (decoded, payload) = SaveHeader::decode(&bytes).unwrap();
+
+    assert_eq!(decoded, header);
+    assert_eq!(payload, &[1, 2, 3]);
+    assert!(!decoded.is_outdated());
+
+    assert!(
+        SaveHeader::decode(&[1, 2, 3]).is_none(),
+        "Headerless data should not be decoded"
+    );
+}
+
+#[test]
+fn migrate_legacy_payload() {
+    let mut migrations = SaveMigrations::new();
+
+    let header = SaveHeader::legacy(SaveKind::Player, false);
+    assert!(header.is_outdated());
+    assert_eq!(migrations.migrate(header, vec![1, 2]).unwrap(), vec![1, 2]);
+
+    migrations.register(SaveKind::Player, 0, |_| Err("broken".to_string()));
+    assert!(migrations.migrate(header, vec![1, 2]).is_err());
+}
@@ -0,0 +1,347 @@
+use super::{
+    atomic_file::{sync_dir, write_atomic},
+    region_file::RegionStorage,
+    save_error::SaveError,
+};
+use bevy::utils::HashMap;
+use std::{
+    fs,
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// Random access handle to the stored file
+pub type StorageFile = Box<dyn RegionStorage + Send>;
+
+/// Storage of the saved worlds.
+///
+/// Data is addressed by `/` separated keys, e.g. `<world id>/regions/0_0_0/objects`.
+pub trait WorldStorage: Send + Sync + 'static {
+    /// Replace data at `key`, write is atomic: after a crash `key` contains either old or new data
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), SaveError>;
+
+    /// Get data at `key`, returns `None` if there is no such key
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, SaveError>;
+
+    /// Get all keys that start with `prefix`, `prefix` should be empty or end with `/`
+    fn list(&self, prefix: &str) -> Result<Vec<String>, SaveError>;
+
+    /// Get names of the directories right inside of `prefix`, `prefix` should be empty or end with `/`
+    fn list_dirs(&self, prefix: &str) -> Result<Vec<String>, SaveError>;
+
+    /// Remove data at `key`, does nothing if there is no such key
+    fn delete(&self, key: &str) -> Result<(), SaveError>;
+
+    /// Open existing file at `key` for random access, returns `None` if there is no such key
+    fn open(&self, key: &str) -> Result<Option<StorageFile>, SaveError>;
+
+    /// Open file at `key` for random access, creating empty file if there is no such key
+    fn open_or_create(&self, key: &str) -> Result<StorageFile, SaveError>;
+
+    /// Move data from `from` to `to`
+    fn rename(&self, from: &str, to: &str) -> Result<(), SaveError> {
+        let data = self
+            .get(from)?
+            .ok_or_else(|| SaveError::from(io::Error::from(io::ErrorKind::NotFound)))?;
+
+        self.put(to, &data)?;
+        self.delete(from)
+    }
+}
+
+/// Stores worlds in the directory on the disk
+#[derive(Debug, Clone)]
+pub struct FsStorage {
+    root: PathBuf,
+}
+
+impl FsStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+
+    /// Recursively collect keys of all files in `dir`
+    fn collect_keys(&self, dir: &Path, keys: &mut Vec<String>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                self.collect_keys(&path, keys)?;
+                continue;
+            }
+
+            let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+
+            let key = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            keys.push(key);
+        }
+
+        Ok(())
+    }
+}
+
+impl WorldStorage for FsStorage {
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), SaveError> {
+        write_atomic(&self.path(key), data).map_err(|err| SaveError::from(err).in_file(key))
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, SaveError> {
+        match fs::read(self.path(key)) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(SaveError::from(err).in_file(key)),
+        }
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, SaveError> {
+        let dir = self.path(prefix);
+        let mut keys = Vec::new();
+
+        if !dir.is_dir() {
+            return Ok(keys);
+        }
+
+        self.collect_keys(&dir, &mut keys)
+            .map_err(|err| SaveError::from(err).in_file(prefix))?;
+
+        Ok(keys)
+    }
+
+    fn list_dirs(&self, prefix: &str) -> Result<Vec<String>, SaveError> {
+        let dir = self.path(prefix);
+
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let entries = fs::read_dir(&dir).map_err(|err| SaveError::from(err).in_file(prefix))?;
+
+        let mut dirs = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|err| SaveError::from(err).in_file(prefix))?
+                .path();
+
+            if let (true, Some(name)) = (path.is_dir(), path.file_name()) {
+                dirs.push(name.to_string_lossy().to_string());
+            }
+        }
+
+        Ok(dirs)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), SaveError> {
+        match fs::remove_file(self.path(key)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                Err(SaveError::from(err).in_file(key))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn open(&self, key: &str) -> Result<Option<StorageFile>, SaveError> {
+        match fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(self.path(key))
+        {
+            Ok(file) => Ok(Some(Box::new(file))),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(SaveError::from(err).in_file(key)),
+        }
+    }
+
+    fn open_or_create(&self, key: &str) -> Result<StorageFile, SaveError> {
+        let path = self.path(key);
+
+        let open = || {
+            fs::create_dir_all(path.parent().unwrap())?;
+
+            fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)
+        };
+
+        let file = open().map_err(|err| SaveError::from(err).in_file(key))?;
+
+        Ok(Box::new(file))
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), SaveError> {
+        let to_path = self.path(to);
+        let dir = to_path.parent().unwrap();
+
+        fs::create_dir_all(dir)
+            .and_then(|_| fs::rename(self.path(from), &to_path))
+            .and_then(|_| sync_dir(dir))
+            .map_err(|err| SaveError::from(err).in_file(from))
+    }
+}
+
+type MemoryFiles = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+/// Stores worlds in memory, all clones share the same data
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStorage {
+    files: MemoryFiles,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// File opened from the [`MemoryStorage`], changes are visible to the storage after sync
+struct MemoryFile {
+    key: String,
+    data: Cursor<Vec<u8>>,
+    files: MemoryFiles,
+}
+
+impl Read for MemoryFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.data.read(buf)
+    }
+}
+
+impl Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for MemoryFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.data.seek(pos)
+    }
+}
+
+impl RegionStorage for MemoryFile {
+    fn sync(&mut self) -> io::Result<()> {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(self.key.clone(), self.data.get_ref().clone());
+
+        Ok(())
+    }
+}
+
+impl WorldStorage for MemoryStorage {
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), SaveError> {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), data.to_vec());
+
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, SaveError> {
+        Ok(self.files.lock().unwrap().get(key).cloned())
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, SaveError> {
+        Ok(self
+            .files
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+
+    fn list_dirs(&self, prefix: &str) -> Result<Vec<String>, SaveError> {
+        let mut dirs = self
+            .files
+            .lock()
+            .unwrap()
+            .keys()
+            .filter_map(|key| key.strip_prefix(prefix)?.split_once('/'))
+            .map(|(dir, _)| dir.to_string())
+            .collect::<Vec<_>>();
+
+        dirs.sort();
+        dirs.dedup();
+
+        Ok(dirs)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), SaveError> {
+        self.files.lock().unwrap().remove(key);
+
+        Ok(())
+    }
+
+    fn open(&self, key: &str) -> Result<Option<StorageFile>, SaveError> {
+        let data = match self.get(key)? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+
+        Ok(Some(Box::new(MemoryFile {
+            key: key.to_string(),
+            data: Cursor::new(data),
+            files: self.files.clone(),
+        })))
+    }
+
+    fn open_or_create(&self, key: &str) -> Result<StorageFile, SaveError> {
+        let data = self.get(key)?.unwrap_or_default();
+
+        Ok(Box::new(MemoryFile {
+            key: key.to_string(),
+            data: Cursor::new(data),
+            files: self.files.clone(),
+        }))
+    }
+}
+
+#[test]
+fn memory_storage_random_access() {
+    let storage = MemoryStorage::new();
+
+    storage.put("world/meta", &[1, 2, 3]).unwrap();
+    assert_eq!(storage.get("world/meta").unwrap(), Some(vec![1, 2, 3]));
+    assert!(storage.open("world/region").unwrap().is_none());
+
+    let mut file = storage.open_or_create("world/region").unwrap();
+    file.write_all(&[4, 5]).unwrap();
+    assert_eq!(
+        storage.get("world/region").unwrap(),
+        None,
+        "Changes should be visible only after sync"
+    );
+    file.sync().unwrap();
+    assert_eq!(storage.get("world/region").unwrap(), Some(vec![4, 5]));
+
+    let mut keys = storage.list("world/").unwrap();
+    keys.sort();
+    assert_eq!(keys, vec!["world/meta", "world/region"]);
+    assert_eq!(storage.list_dirs("").unwrap(), vec!["world"]);
+
+    storage
+        .rename("world/meta", "world/quarantine/meta")
+        .unwrap();
+    storage.delete("world/region").unwrap();
+    assert_eq!(storage.list("").unwrap(), vec!["world/quarantine/meta"]);
+}
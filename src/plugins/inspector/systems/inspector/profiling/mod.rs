@@ -4,6 +4,8 @@ use bevy::{
 };
 use bevy_egui::egui;
 
+use crate::plugins::game_world::resources::save_worker::SaveWorker;
+
 use self::avg_samples::AvgSamples;
 
 mod avg_samples;
@@ -32,6 +34,8 @@ pub fn profiling_inspector(world: &mut World, ui: &mut egui::Ui) {
         .unwrap()
         .value;
 
+    let save_queue_len = world.get_resource::<SaveWorker>().map(|w| w.queue_len());
+
     state.low_1p_fps.update(fps as f32);
     state.low_01p_fps.update(fps as f32);
 
@@ -50,6 +54,12 @@ pub fn profiling_inspector(world: &mut World, ui: &mut egui::Ui) {
                 ui.label("low 0.1% fps:");
                 ui.label(format!("{}", state.low_01p_fps.min().ceil()))
             });
+            if let Some(save_queue_len) = save_queue_len {
+                ui.horizontal(|ui| {
+                    ui.label("save queue:");
+                    ui.label(format!("{}", save_queue_len))
+                });
+            }
         });
 
     world.insert_resource(state);
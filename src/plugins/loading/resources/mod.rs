@@ -1,6 +1,9 @@
+use crate::plugins::static_mesh::materials::TerrainMaterial;
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::Collider;
 
+pub mod models;
+
 #[derive(Default, Debug, Clone, Reflect, FromReflect)]
 pub struct PhysicsObject {
     pub scene: Handle<Scene>,
@@ -9,6 +12,13 @@ pub struct PhysicsObject {
     pub colliders: Vec<(Collider, Transform)>,
 }
 
+/// Texture layers stacked vertically in one image, converted to array by the loading
+#[derive(Default, Debug, Clone, Reflect, FromReflect)]
+pub struct TerrainTextures {
+    pub image: Handle<Image>,
+    pub material: Handle<TerrainMaterial>,
+}
+
 #[derive(Resource, Default, Reflect, FromReflect)]
 #[reflect(Resource)]
 pub struct GameAssets {
@@ -34,4 +44,5 @@ pub struct GameAssets {
     pub stone_axe_object: PhysicsObject,
 
     pub crosshair_image: Handle<Image>,
+    pub terrain: TerrainTextures,
 }
@@ -0,0 +1,18 @@
+//! Paths of object models inside of the assets directory.
+//!
+//! Used both to load the models and to reference them from exported worlds.
+
+pub const TREE: &str = "models/tree.glb";
+pub const BRANCH: &str = "models/branch.glb";
+pub const ROCK: &str = "models/rock.glb";
+pub const FIRE: &str = "models/fire.glb";
+pub const LOG: &str = "models/log.glb";
+pub const STUMP: &str = "models/stump.glb";
+pub const CACTUS: &str = "models/cactus.glb";
+pub const WOODEN_SHOVEL: &str = "models/wooden_shovel.glb";
+pub const SPRUCE: &str = "models/spruce.glb";
+pub const SPRUCE_SNOW: &str = "models/spruce-snow.glb";
+pub const FLAX: &str = "models/flax.glb";
+pub const FLAX_ITEM: &str = "models/flax-item.glb";
+pub const STONE_AXE: &str = "models/stone-axe.glb";
+pub const COARSE_STRING: &str = "models/coarse-string.glb";
@@ -1 +1,2 @@
 pub mod physics_object;
+pub mod terrain_textures;
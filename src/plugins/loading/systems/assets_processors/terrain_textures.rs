@@ -0,0 +1,57 @@
+use crate::plugins::{
+    loading::resources::TerrainTextures, static_mesh::materials::TerrainMaterial,
+};
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{AddressMode, FilterMode, SamplerDescriptor},
+        texture::ImageSampler,
+    },
+};
+
+/// Size of the terrain texture in meters
+const TEXTURE_SIZE: f32 = 2.0;
+
+/// Convert the loaded terrain image to texture array and create its material.
+///
+/// It will return `true` if the material was created or if field is not a [`TerrainTextures`].
+/// It will return `false` if the image is still loading.
+pub fn process_terrain_textures(
+    field: &mut dyn Reflect,
+    images: &mut Assets<Image>,
+    materials: &mut Assets<TerrainMaterial>,
+) -> bool {
+    let terrain = if let Some(terrain) = field.downcast_mut::<TerrainTextures>() {
+        terrain
+    } else {
+        return true;
+    };
+
+    if materials.contains(&terrain.material) {
+        return true;
+    }
+
+    let image = if let Some(image) = images.get_mut(&terrain.image) {
+        image
+    } else {
+        return false;
+    };
+
+    image.reinterpret_stacked_2d_as_array(TerrainMaterial::LAYERS);
+    image.sampler_descriptor = ImageSampler::Descriptor(SamplerDescriptor {
+        address_mode_u: AddressMode::Repeat,
+        address_mode_v: AddressMode::Repeat,
+        address_mode_w: AddressMode::Repeat,
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        mipmap_filter: FilterMode::Linear,
+        ..default()
+    });
+
+    terrain.material = materials.add(TerrainMaterial {
+        textures: terrain.image.clone(),
+        texture_size: TEXTURE_SIZE,
+    });
+
+    true
+}
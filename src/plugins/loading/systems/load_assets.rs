@@ -1,11 +1,11 @@
-use crate::plugins::loading::resources::{GameAssets, PhysicsObject};
+use crate::plugins::loading::resources::{models, GameAssets, PhysicsObject, TerrainTextures};
 use bevy::{asset::AssetPath, prelude::*};
+use std::path::Path;
 
-fn load_scene_with_physics<'a>(
-    path: impl Into<AssetPath<'a>>,
-    asset_server: &AssetServer,
-) -> PhysicsObject {
-    let scene_h: Handle<Scene> = asset_server.load(path);
+/// Load the first scene of the model at `path`, see [`models`]
+fn load_scene_with_physics(path: &str, asset_server: &AssetServer) -> PhysicsObject {
+    let scene_h: Handle<Scene> =
+        asset_server.load(AssetPath::new_ref(Path::new(path), Some("Scene0")));
 
     PhysicsObject {
         scene: scene_h,
@@ -42,28 +42,26 @@ pub fn load_assets(
             subdivisions: 9,
         })),
 
-        tree_object: load_scene_with_physics("models/tree.glb#Scene0", &asset_server),
-        branch_object: load_scene_with_physics("models/branch.glb#Scene0", &asset_server),
-        rock_object: load_scene_with_physics("models/rock.glb#Scene0", &asset_server),
-        fire_object: load_scene_with_physics("models/fire.glb#Scene0", &asset_server),
-        log_object: load_scene_with_physics("models/log.glb#Scene0", &asset_server),
-        stump_object: load_scene_with_physics("models/stump.glb#Scene0", &asset_server),
-        cactus_object: load_scene_with_physics("models/cactus.glb#Scene0", &asset_server),
-        wooden_shovel_object: load_scene_with_physics(
-            "models/wooden_shovel.glb#Scene0",
-            &asset_server,
-        ),
-        spruce_object: load_scene_with_physics("models/spruce.glb#Scene0", &asset_server),
-        spruce_snow_object: load_scene_with_physics("models/spruce-snow.glb#Scene0", &asset_server),
-        flax_object: load_scene_with_physics("models/flax.glb#Scene0", &asset_server),
-        flax_item_object: load_scene_with_physics("models/flax-item.glb#Scene0", &asset_server),
-        stone_axe_object: load_scene_with_physics("models/stone-axe.glb#Scene0", &asset_server),
-        coarse_string_object: load_scene_with_physics(
-            "models/coarse-string.glb#Scene0",
-            &asset_server,
-        ),
+        tree_object: load_scene_with_physics(models::TREE, &asset_server),
+        branch_object: load_scene_with_physics(models::BRANCH, &asset_server),
+        rock_object: load_scene_with_physics(models::ROCK, &asset_server),
+        fire_object: load_scene_with_physics(models::FIRE, &asset_server),
+        log_object: load_scene_with_physics(models::LOG, &asset_server),
+        stump_object: load_scene_with_physics(models::STUMP, &asset_server),
+        cactus_object: load_scene_with_physics(models::CACTUS, &asset_server),
+        wooden_shovel_object: load_scene_with_physics(models::WOODEN_SHOVEL, &asset_server),
+        spruce_object: load_scene_with_physics(models::SPRUCE, &asset_server),
+        spruce_snow_object: load_scene_with_physics(models::SPRUCE_SNOW, &asset_server),
+        flax_object: load_scene_with_physics(models::FLAX, &asset_server),
+        flax_item_object: load_scene_with_physics(models::FLAX_ITEM, &asset_server),
+        stone_axe_object: load_scene_with_physics(models::STONE_AXE, &asset_server),
+        coarse_string_object: load_scene_with_physics(models::COARSE_STRING, &asset_server),
 
         crosshair_image: asset_server.load("textures/crosshair.png"),
+        terrain: TerrainTextures {
+            image: asset_server.load("textures/terrain.png"),
+            ..default()
+        },
     };
 
     commands.insert_resource(game_assets);
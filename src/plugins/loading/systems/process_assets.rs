@@ -1,5 +1,10 @@
-use super::assets_processors::physics_object::process_physic_objects;
-use crate::{plugins::loading::resources::GameAssets, states::game_state::GameState};
+use super::assets_processors::{
+    physics_object::process_physic_objects, terrain_textures::process_terrain_textures,
+};
+use crate::{
+    plugins::{loading::resources::GameAssets, static_mesh::materials::TerrainMaterial},
+    states::game_state::GameState,
+};
 use bevy::prelude::*;
 
 pub fn process_assets(
@@ -7,6 +12,8 @@ pub fn process_assets(
     mut game_state: ResMut<State<GameState>>,
     mut scenes: ResMut<Assets<Scene>>,
     mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut terrain_materials: ResMut<Assets<TerrainMaterial>>,
 ) {
     let fields: Vec<_> = game_assets
         .iter_fields()
@@ -27,6 +34,10 @@ pub fn process_assets(
             return false;
         }
 
+        if !process_terrain_textures(field, &mut images, &mut terrain_materials) {
+            return false;
+        }
+
         true
     });
 
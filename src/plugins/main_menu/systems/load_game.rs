@@ -1,21 +1,91 @@
 use crate::{
     plugins::{
-        game_world::resources::meta::GameWorldMeta, world_generator::resources::WorldGenerator,
+        game_world::resources::{
+            backup::WorldBackup, meta::GameWorldMeta, save_worker::SaveWorker,
+            storage::GameWorldStorage,
+        },
+        world_generator::resources::WorldGenerator,
     },
     states::game_state::GameState,
 };
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContext};
 
+#[derive(Default)]
 pub struct SavedWorlds {
     worlds: Vec<GameWorldMeta>,
+    error: Option<String>,
+    loaded: bool,
+    /// Index of the world with opened backups list and its backups
+    backups: Option<(usize, Vec<WorldBackup>)>,
 }
 
-impl Default for SavedWorlds {
-    fn default() -> Self {
-        Self {
-            worlds: GameWorldMeta::get_saves(),
+impl SavedWorlds {
+    fn update(&mut self, storage: &GameWorldStorage) {
+        self.loaded = true;
+        self.backups = None;
+
+        match GameWorldMeta::get_saves(storage) {
+            Ok(worlds) => {
+                self.worlds = worlds;
+                self.error = None;
+            }
+            Err(err) => {
+                error!("Failed to get saved worlds: {}", err);
+                self.worlds = Vec::new();
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+
+    fn toggle_backups(&mut self, storage: &GameWorldStorage, index: usize) {
+        if matches!(self.backups, Some((i, _)) if i == index) {
+            self.backups = None;
+            return;
+        }
+
+        match self.worlds[index].get_backups(storage) {
+            Ok(backups) => self.backups = Some((index, backups)),
+            Err(err) => {
+                error!("Failed to get backups: {}", err);
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+
+    fn backup(&mut self, storage: &GameWorldStorage, index: usize) {
+        if let Err(err) = self.worlds[index].backup_and_prune(storage) {
+            error!("Failed to back up world: {}", err);
+            self.error = Some(err.to_string());
+            return;
         }
+
+        if matches!(self.backups, Some((i, _)) if i == index) {
+            self.backups = None;
+            self.toggle_backups(storage, index);
+        }
+    }
+
+    fn restore(&mut self, storage: &GameWorldStorage, index: usize, backup: &WorldBackup) {
+        if let Err(err) = self.worlds[index].restore_backup(storage, backup) {
+            error!("Failed to restore backup: {}", err);
+            self.error = Some(err.to_string());
+            return;
+        }
+
+        self.update(storage);
+    }
+}
+
+/// Format age of the backup, e.g. `5 min ago`
+fn format_backup_age(backup: &WorldBackup) -> String {
+    let secs = backup.age().as_secs();
+
+    match secs {
+        0..=59 => format!("{} s ago", secs),
+        60..=3599 => format!("{} min ago", secs / 60),
+        3600..=86399 => format!("{} h ago", secs / 3600),
+        _ => format!("{} days ago", secs / 86400),
     }
 }
 
@@ -29,25 +99,103 @@ pub fn load_game_system(
     mut saved_worlds: Local<SavedWorlds>,
     mut game_world_meta: ResMut<GameWorldMeta>,
     mut generator: ResMut<WorldGenerator>,
+    storage: Res<GameWorldStorage>,
+    worker: Res<SaveWorker>,
 ) {
+    if !saved_worlds.loaded {
+        saved_worlds.update(&storage);
+    }
+
     egui::Window::new("Load world").show(egui_context.ctx_mut(), |ui| {
+        if let Some(error) = &saved_worlds.error {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("Failed to get saves: {}", error),
+            );
+        }
+
+        let mut backup = None;
+        let mut toggle_backups = None;
+        let mut restore = None;
+
         ui.vertical(|ui| {
-            for world in saved_worlds.worlds.iter() {
+            for (i, world) in saved_worlds.worlds.iter().enumerate() {
                 ui.horizontal(|ui| {
-                    if ui.button("Load").clicked() {
+                    let supported = world.generator.is_supported();
+
+                    if ui
+                        .add_enabled(supported, egui::Button::new("Load"))
+                        .clicked()
+                    {
                         *game_world_meta = world.clone();
-                        generator.set_seed(game_world_meta.seed);
+                        generator
+                            .set_world(game_world_meta.seed, game_world_meta.generator.clone());
                         game_state.set(GameState::WorldLoading).unwrap();
                     }
 
                     ui.label(format!("{} ({})", world.name, world.id));
+
+                    if !supported {
+                        ui.colored_label(
+                            egui::Color32::RED,
+                            format!("unsupported generator v{}", world.generator.version),
+                        );
+                    }
+
+                    if world.needs_upgrade {
+                        ui.colored_label(egui::Color32::YELLOW, "needs upgrade");
+                    }
+
+                    if ui.button("Backup").clicked() {
+                        backup = Some(i);
+                    }
+
+                    if ui.button("Restore...").clicked() {
+                        toggle_backups = Some(i);
+                    }
                 });
+
+                match &saved_worlds.backups {
+                    Some((index, backups)) if *index == i => {
+                        if backups.is_empty() {
+                            ui.label("No backups");
+                        }
+
+                        for world_backup in backups {
+                            ui.horizontal(|ui| {
+                                ui.label(format_backup_age(world_backup));
+
+                                if ui.button("Restore").clicked() {
+                                    restore = Some((i, world_backup.clone()));
+                                }
+                            });
+                        }
+                    }
+                    _ => {}
+                }
             }
         });
 
+        // queued saves of the last session must be on disk before its files are copied or replaced
+        if backup.is_some() || restore.is_some() {
+            worker.flush();
+        }
+
+        if let Some(i) = backup {
+            saved_worlds.backup(&storage, i);
+        }
+
+        if let Some(i) = toggle_backups {
+            saved_worlds.toggle_backups(&storage, i);
+        }
+
+        if let Some((i, world_backup)) = restore {
+            saved_worlds.restore(&storage, i, &world_backup);
+        }
+
         ui.horizontal(|ui| {
             if ui.button("Update").clicked() {
-                saved_worlds.worlds = GameWorldMeta::get_saves();
+                saved_worlds.update(&storage);
             }
 
             if ui.button("Back").clicked() {
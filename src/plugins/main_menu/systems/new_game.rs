@@ -1,13 +1,17 @@
 use crate::{
     plugins::{
         game_world::resources::meta::GameWorldMeta,
-        world_generator::resources::{WorldGenerator, WorldSeed},
+        world_generator::resources::{
+            map_preview::{MapMode, MapPreview},
+            settings::GeneratorSettings,
+            WorldGenerator, WorldSeed,
+        },
     },
     states::game_state::GameState,
 };
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContext};
-use std::hash::{Hash, Hasher};
+use strum::IntoEnumIterator;
 
 pub fn init_new_game(mut game_world_meta: ResMut<GameWorldMeta>) {
     game_world_meta.reset();
@@ -15,16 +19,36 @@ pub fn init_new_game(mut game_world_meta: ResMut<GameWorldMeta>) {
 
 pub struct NewGameLocalState {
     pub seed: String,
+    pub map_mode: MapMode,
+    pub preview: Option<(WorldSeed, MapMode, egui::TextureHandle)>,
 }
 
 impl Default for NewGameLocalState {
     fn default() -> Self {
         Self {
             seed: rand::random::<u64>().to_string(),
+            map_mode: MapMode::default(),
+            preview: None,
         }
     }
 }
 
+/// Size of the preview image in pixels
+const PREVIEW_SIZE: usize = 128;
+/// Area covered by one pixel of the preview
+const PREVIEW_METERS_PER_PIXEL: f32 = 8.0;
+
+fn preview_to_texture(ctx: &egui::Context, preview: &MapPreview) -> egui::TextureHandle {
+    let image =
+        egui::ColorImage::from_rgba_unmultiplied([preview.size, preview.size], &preview.to_rgba8());
+
+    ctx.load_texture(
+        "new_world_map_preview",
+        image,
+        egui::TextureOptions::NEAREST,
+    )
+}
+
 pub fn new_game_system(
     mut game_state: ResMut<State<GameState>>,
     mut generator: ResMut<WorldGenerator>,
@@ -47,16 +71,39 @@ pub fn new_game_system(
                     local_state.seed = rand::random::<u64>().to_string();
                 }
 
-                let seed = local_state.seed.parse().unwrap_or_else(|_| {
-                    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-                    local_state.seed.hash(&mut hasher);
-                    hasher.finish()
-                });
+                game_world_meta.seed = WorldGenerator::seed_from_str(&local_state.seed);
+                game_world_meta.generator = GeneratorSettings::current();
+                if generator.seed() != game_world_meta.seed
+                    || generator.settings() != &game_world_meta.generator
+                {
+                    generator.set_world(game_world_meta.seed, game_world_meta.generator.clone());
+                }
+            });
 
-                game_world_meta.seed = seed as WorldSeed;
-                generator.set_seed(game_world_meta.seed);
+            ui.horizontal(|ui| {
+                ui.label("Map: ");
+                for mode in MapMode::iter() {
+                    ui.selectable_value(&mut local_state.map_mode, mode, mode.name());
+                }
             });
 
+            let seed = game_world_meta.seed;
+            let mode = local_state.map_mode;
+            let outdated = !matches!(
+                &local_state.preview,
+                Some((s, m, _)) if *s == seed && *m == mode
+            );
+
+            if outdated {
+                let preview = generator.render_map(mode, PREVIEW_SIZE, PREVIEW_METERS_PER_PIXEL);
+                let texture = preview_to_texture(ui.ctx(), &preview);
+                local_state.preview = Some((seed, mode, texture));
+            }
+
+            if let Some((_, _, texture)) = &local_state.preview {
+                ui.image(texture, texture.size_vec2());
+            }
+
             if ui.button("Generate world").clicked() {
                 game_state.set(GameState::WorldCreating).unwrap();
             }
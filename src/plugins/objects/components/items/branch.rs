@@ -1,5 +1,5 @@
 use crate::plugins::{
-    loading::resources::{GameAssets, PhysicsObject},
+    loading::resources::{models, GameAssets, PhysicsObject},
     objects::components::{GameWorldObjectTrait, ObjectDeserializationError},
 };
 
@@ -35,6 +35,10 @@ impl GameWorldObjectTrait for BranchItem {
         &assets.branch_object
     }
 
+    fn model_path(&self) -> &'static str {
+        models::BRANCH
+    }
+
     fn is_item(&self) -> bool {
         true
     }
@@ -0,0 +1,83 @@
+use crate::plugins::{
+    loading::resources::{models, GameAssets, PhysicsObject},
+    objects::components::{GameWorldObjectTrait, ObjectDeserializationError},
+};
+
+/// Kind of the [`MineralItem`], serialized as a single byte
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MineralKind {
+    #[default]
+    Flint = 0,
+    CopperOre = 1,
+    TinOre = 2,
+    Crystal = 3,
+}
+
+impl TryFrom<u8> for MineralKind {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Flint),
+            1 => Ok(Self::CopperOre),
+            2 => Ok(Self::TinOre),
+            3 => Ok(Self::Crystal),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Piece of mineral lying on the cave floor, uses the rock model until minerals get their own
+#[derive(Debug, Default, Clone)]
+pub struct MineralItem {
+    pub kind: MineralKind,
+}
+
+impl MineralItem {
+    pub const ID: &str = "mineral";
+}
+
+impl GameWorldObjectTrait for MineralItem {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn take(&mut self) -> Box<dyn GameWorldObjectTrait> {
+        Box::new(std::mem::take(self))
+    }
+
+    fn get_clone(&self) -> Box<dyn GameWorldObjectTrait> {
+        Box::new(self.clone())
+    }
+
+    fn deserialize(
+        &self,
+        data: &[u8],
+    ) -> Result<Box<dyn GameWorldObjectTrait>, ObjectDeserializationError> {
+        let kind = match data.first() {
+            Some(kind) => MineralKind::try_from(*kind).map_err(|_| {
+                ObjectDeserializationError(format!("unknown mineral kind {}", kind))
+            })?,
+            None => MineralKind::default(),
+        };
+
+        Ok(Box::new(Self { kind }))
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        vec![self.kind as u8]
+    }
+
+    fn get_model<'a>(&self, assets: &'a GameAssets) -> &'a PhysicsObject {
+        &assets.rock_object
+    }
+
+    fn model_path(&self) -> &'static str {
+        models::ROCK
+    }
+
+    fn is_item(&self) -> bool {
+        true
+    }
+}
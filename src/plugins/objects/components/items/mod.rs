@@ -6,6 +6,7 @@ pub mod branch;
 pub mod coarse_string;
 pub mod flax_item;
 pub mod log;
+pub mod mineral;
 pub mod rock;
 pub mod stone_axe;
 pub mod wooden_shovel;
@@ -1,5 +1,5 @@
 use crate::plugins::{
-    loading::resources::{GameAssets, PhysicsObject},
+    loading::resources::{models, GameAssets, PhysicsObject},
     objects::components::{GameWorldObjectTrait, ObjectDeserializationError},
 };
 
@@ -35,6 +35,10 @@ impl GameWorldObjectTrait for RockItem {
         &assets.rock_object
     }
 
+    fn model_path(&self) -> &'static str {
+        models::ROCK
+    }
+
     fn is_item(&self) -> bool {
         true
     }
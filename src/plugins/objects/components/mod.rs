@@ -43,6 +43,9 @@ pub trait GameWorldObjectTrait: Send + Sync + Debug + Any {
 
     fn get_model<'a>(&self, assets: &'a GameAssets) -> &'a PhysicsObject;
 
+    /// Path of the model file inside of the assets directory
+    fn model_path(&self) -> &'static str;
+
     /// Insert additional components to entity
     fn insert(&self, _e: &mut EntityCommands) {}
 
@@ -1,5 +1,5 @@
 use crate::plugins::{
-    loading::resources::{GameAssets, PhysicsObject},
+    loading::resources::{models, GameAssets, PhysicsObject},
     objects::components::{GameWorldObjectTrait, ObjectDeserializationError},
 };
 use bevy_reflect::{FromReflect, Reflect};
@@ -35,4 +35,8 @@ impl GameWorldObjectTrait for CactusObject {
     fn get_model<'a>(&self, assets: &'a GameAssets) -> &'a PhysicsObject {
         &assets.cactus_object
     }
+
+    fn model_path(&self) -> &'static str {
+        models::CACTUS
+    }
 }
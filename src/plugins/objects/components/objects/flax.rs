@@ -1,6 +1,6 @@
 use crate::plugins::{
     inspector::components::InspectorDisabled,
-    loading::resources::{GameAssets, PhysicsObject},
+    loading::resources::{models, GameAssets, PhysicsObject},
     objects::components::{
         items::flax_item::FlaxItem, GameWorldObject, GameWorldObjectTrait,
         ObjectDeserializationError,
@@ -67,4 +67,8 @@ impl GameWorldObjectTrait for FlaxObject {
     fn get_model<'a>(&self, assets: &'a GameAssets) -> &'a PhysicsObject {
         &assets.flax_object
     }
+
+    fn model_path(&self) -> &'static str {
+        models::FLAX
+    }
 }
@@ -1,5 +1,5 @@
 use crate::plugins::{
-    loading::resources::{GameAssets, PhysicsObject},
+    loading::resources::{models, GameAssets, PhysicsObject},
     objects::components::{GameWorldObjectTrait, ObjectDeserializationError},
 };
 use bevy_reflect::{FromReflect, Reflect};
@@ -49,4 +49,12 @@ impl GameWorldObjectTrait for SpruceObject {
             &assets.spruce_object
         }
     }
+
+    fn model_path(&self) -> &'static str {
+        if self.snow {
+            models::SPRUCE_SNOW
+        } else {
+            models::SPRUCE
+        }
+    }
 }
@@ -2,7 +2,7 @@ use std::f32::consts::PI;
 
 use crate::plugins::{
     inspector::components::InspectorDisabled,
-    loading::resources::{GameAssets, PhysicsObject},
+    loading::resources::{models, GameAssets, PhysicsObject},
     objects::components::{
         items::{branch::BranchItem, log::LogItem, stone_axe::StoneAxeItem},
         GameWorldObject, GameWorldObjectTrait, ObjectDeserializationError,
@@ -126,4 +126,8 @@ impl GameWorldObjectTrait for TreeObject {
     fn get_model<'a>(&self, assets: &'a GameAssets) -> &'a PhysicsObject {
         &assets.tree_object
     }
+
+    fn model_path(&self) -> &'static str {
+        models::TREE
+    }
 }
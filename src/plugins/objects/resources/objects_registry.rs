@@ -1,7 +1,9 @@
+use crate::plugins::game_world::utils::save_error::SaveError;
 use crate::plugins::objects::components::{
     items::{
         branch::BranchItem, coarse_string::CoarseStringItem, flax_item::FlaxItem, log::LogItem,
-        rock::RockItem, stone_axe::StoneAxeItem, wooden_shovel::WoodenShovelItem,
+        mineral::MineralItem, rock::RockItem, stone_axe::StoneAxeItem,
+        wooden_shovel::WoodenShovelItem,
     },
     objects::{
         cactus::CactusObject, fire::FireObject, flax::FlaxObject, spruce::SpruceObject,
@@ -44,6 +46,7 @@ impl ObjectsRegistry {
         result.register(CoarseStringItem::default());
         result.register(LogItem::default());
         result.register(WoodenShovelItem::default());
+        result.register(MineralItem::default());
 
         result
     }
@@ -61,9 +64,18 @@ impl ObjectsRegistry {
         &self,
         id: &str,
         data: &[u8],
-    ) -> Option<Box<dyn GameWorldObjectTrait>> {
-        let object = self.objects.get(id)?.object.clone()?;
+    ) -> Result<Box<dyn GameWorldObjectTrait>, SaveError> {
+        let object = self
+            .objects
+            .get(id)
+            .and_then(|entry| entry.object.clone())
+            .ok_or_else(|| SaveError::UnknownObject(id.to_string()))?;
 
-        Some(object.deserialize(data).unwrap())
+        object
+            .deserialize(data)
+            .map_err(|err| SaveError::ObjectDeserialization {
+                id: id.to_string(),
+                reason: err.0,
+            })
     }
 }
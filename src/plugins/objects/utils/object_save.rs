@@ -1,12 +1,15 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::plugins::objects::{
-    components::{object_spawner::ObjectSpawner, GameWorldObject},
-    resources::objects_registry::ObjectsRegistry,
+use crate::plugins::{
+    game_world::utils::save_error::SaveError,
+    objects::{
+        components::{object_spawner::ObjectSpawner, GameWorldObject},
+        resources::objects_registry::ObjectsRegistry,
+    },
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameWorldObjectSave {
     object_id: String,
     object_data: Vec<u8>,
@@ -26,7 +29,11 @@ impl GameWorldObjectSave {
         }
     }
 
-    pub fn to_spawner(self, registry: &ObjectsRegistry, offset: Vec3) -> ObjectSpawner {
+    pub fn to_spawner(
+        self,
+        registry: &ObjectsRegistry,
+        offset: Vec3,
+    ) -> Result<ObjectSpawner, SaveError> {
         let translation = Vec3::from(self.translation) + offset;
 
         let transform = Transform::from_translation(translation)
@@ -38,14 +45,12 @@ impl GameWorldObjectSave {
             ))
             .with_scale(self.scale.into());
 
-        let object = registry
-            .deserialize_object(&self.object_id, &self.object_data)
-            .unwrap_or_else(|| panic!("Failed to deserialize object with id {}", self.object_id));
+        let object = registry.deserialize_object(&self.object_id, &self.object_data)?;
 
-        ObjectSpawner {
+        Ok(ObjectSpawner {
             id: self.object_id,
             object: Some(object),
             transform,
-        }
+        })
     }
 }
@@ -1,4 +1,5 @@
 use crate::plugins::{
+    game_world::utils::save_error::SaveError,
     loading::resources::GameAssets,
     objects::{
         components::GameWorldObject, resources::objects_registry::ObjectsRegistry,
@@ -9,7 +10,7 @@ use crate::plugins::{
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Component, Default, Debug, Serialize, Deserialize)]
+#[derive(Component, Default, Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerSave {
     stats: PlayerStats,
     /// (x, y, z)
@@ -62,7 +63,7 @@ impl PlayerSave {
         mut player: (Mut<Transform>, Mut<PlayerComponent>),
         head: &mut Transform,
         player_stats: &mut PlayerStats,
-    ) {
+    ) -> Result<(), SaveError> {
         let Self {
             hand_item,
             pos,
@@ -84,8 +85,10 @@ impl PlayerSave {
 
         if let Some(hand_item) = hand_item {
             hand_item
-                .to_spawner(registry, Vec3::ZERO)
+                .to_spawner(registry, Vec3::ZERO)?
                 .spawn_to_hand(commands, assets, hand)
         }
+
+        Ok(())
     }
 }
@@ -41,3 +41,12 @@ pub struct UseGrabPlaceEvent;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect, FromReflect)]
 pub struct InteractEvent;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect, FromReflect)]
+pub struct SwitchBrushEvent;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect, FromReflect)]
+pub struct SwitchBrushShapeEvent;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect, FromReflect)]
+pub struct SwitchBrushVoxelEvent;
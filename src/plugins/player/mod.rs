@@ -1,9 +1,11 @@
 use self::components::{PlayerComponent, PlayerHand};
 use self::events::*;
 use self::resources::look_at::PlayerLookAt;
-use self::resources::{input_settings::PlayerInputSettings, PlayerStats};
+use self::resources::{input_settings::PlayerInputSettings, PlayerBrush, PlayerStats};
 use self::systems::look_at::look_at_system;
-use self::systems::{cursor::*, input::*, look::*, movements::*, spawn_item::*, *};
+use self::systems::{
+    cursor::*, input::*, look::*, movements::*, spawn_item::*, switch_brush::*, *,
+};
 use crate::states::game_state::GameState;
 use bevy::prelude::*;
 
@@ -32,11 +34,16 @@ impl Plugin for PlayerPlugin {
             .add_event::<UseGrabPlaceEvent>()
             .add_event::<InteractEvent>()
             .add_event::<ToggleFlyEvent>()
+            .add_event::<SwitchBrushEvent>()
+            .add_event::<SwitchBrushShapeEvent>()
+            .add_event::<SwitchBrushVoxelEvent>()
             .register_type::<PlayerStats>()
             .register_type::<PlayerInputSettings>()
+            .register_type::<PlayerBrush>()
             .register_type::<PlayerHand>()
             .register_type::<PlayerComponent>()
             .insert_resource(PlayerStats::default())
+            .insert_resource(PlayerBrush::default())
             .insert_resource(PlayerLookAt::default())
             .insert_resource(PlayerInputSettings::default())
             .add_startup_system(setup_player_system)
@@ -50,6 +57,7 @@ impl Plugin for PlayerPlugin {
                     .with_system(process_input_1)
                     .with_system(process_input_2)
                     .with_system(spawn_item)
+                    .with_system(switch_brush)
                     .with_system(cursor_toggle),
             )
             .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(cursor_grab))
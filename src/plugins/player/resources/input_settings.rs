@@ -88,6 +88,9 @@ pub struct PlayerInputSettings {
     pub use_place_grab: InputCondition,
     pub craft: InputCondition,
     pub interact: InputCondition,
+    pub switch_brush: InputCondition,
+    pub switch_brush_shape: InputCondition,
+    pub switch_brush_voxel: InputCondition,
 }
 
 impl Default for PlayerInputSettings {
@@ -110,6 +113,9 @@ impl Default for PlayerInputSettings {
             use_place_grab: InputCondition::key_single(KeyCode::E),
             craft: InputCondition::key_single(KeyCode::F),
             interact: InputCondition::key_single(KeyCode::E),
+            switch_brush: InputCondition::key_single(KeyCode::V),
+            switch_brush_shape: InputCondition::key_single(KeyCode::C),
+            switch_brush_voxel: InputCondition::key_single(KeyCode::X),
         }
     }
 }
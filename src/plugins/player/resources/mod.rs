@@ -1,3 +1,7 @@
+use crate::internal::{
+    chunks::brush::{BrushMode, BrushShape},
+    voxel::voxel_types::VoxelId,
+};
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -45,3 +49,74 @@ impl Default for PlayerStats {
         }
     }
 }
+
+/// Voxels that can be added and painted by the [`PlayerBrush`], in the order they are switched
+const BRUSH_VOXELS: [VoxelId; 12] = [
+    VoxelId::DIRT,
+    VoxelId::STONE,
+    VoxelId::SAND,
+    VoxelId::SAND_STONE,
+    VoxelId::SNOW,
+    VoxelId::FLINT,
+    VoxelId::CLAY,
+    VoxelId::COPPER_ORE,
+    VoxelId::TIN_ORE,
+    VoxelId::MOSS,
+    VoxelId::CRYSTAL,
+    VoxelId::BASALT,
+];
+
+/// Brush used by the mining tool, radius and strength are taken from [`PlayerStats`]
+#[derive(Resource, Clone, Copy, Debug, Reflect, FromReflect)]
+#[reflect(Resource)]
+pub struct PlayerBrush {
+    pub mode: BrushMode,
+    pub shape: BrushShape,
+    /// Voxel used by [`BrushMode::Add`] and [`BrushMode::Paint`]
+    pub voxel: VoxelId,
+}
+
+impl Default for PlayerBrush {
+    fn default() -> Self {
+        Self {
+            mode: BrushMode::default(),
+            shape: BrushShape::default(),
+            voxel: BRUSH_VOXELS[0],
+        }
+    }
+}
+
+impl PlayerBrush {
+    /// Switch to the next mode, added and painted voxels are the selected [`PlayerBrush::voxel`]
+    pub fn next_mode(&mut self) {
+        self.mode = match self.mode {
+            BrushMode::Remove => BrushMode::Add(self.voxel),
+            BrushMode::Add(_) => BrushMode::Smooth,
+            BrushMode::Smooth => BrushMode::Flatten { normal: Vec3::Y },
+            BrushMode::Flatten { .. } => BrushMode::Paint(self.voxel),
+            BrushMode::Paint(_) => BrushMode::Remove,
+        };
+    }
+
+    pub fn next_shape(&mut self) {
+        self.shape = match self.shape {
+            BrushShape::Sphere => BrushShape::Cube,
+            BrushShape::Cube => BrushShape::Sphere,
+        };
+    }
+
+    /// Switch to the next voxel, the current mode uses it right away
+    pub fn next_voxel(&mut self) {
+        let index = BRUSH_VOXELS
+            .iter()
+            .position(|id| *id == self.voxel)
+            .map_or(0, |index| (index + 1) % BRUSH_VOXELS.len());
+        self.voxel = BRUSH_VOXELS[index];
+
+        self.mode = match self.mode {
+            BrushMode::Add(_) => BrushMode::Add(self.voxel),
+            BrushMode::Paint(_) => BrushMode::Paint(self.voxel),
+            mode => mode,
+        };
+    }
+}
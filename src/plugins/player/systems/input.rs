@@ -65,6 +65,9 @@ pub fn process_input_2(
     mut use_place_grab_ew: EventWriter<UseGrabPlaceEvent>,
     mut craft_ew: EventWriter<CraftEvent>,
     mut interact_ew: EventWriter<InteractEvent>,
+    mut switch_brush_ew: EventWriter<SwitchBrushEvent>,
+    mut switch_brush_shape_ew: EventWriter<SwitchBrushShapeEvent>,
+    mut switch_brush_voxel_ew: EventWriter<SwitchBrushVoxelEvent>,
 ) {
     process(&k, &m, s.toggle_fly, &mut toggle_fly_ew);
     process(&k, &m, s.sprint, &mut sprint_ew);
@@ -73,4 +76,7 @@ pub fn process_input_2(
     process(&k, &m, s.use_place_grab, &mut use_place_grab_ew);
     process(&k, &m, s.craft, &mut craft_ew);
     process(&k, &m, s.interact, &mut interact_ew);
+    process(&k, &m, s.switch_brush, &mut switch_brush_ew);
+    process(&k, &m, s.switch_brush_shape, &mut switch_brush_shape_ew);
+    process(&k, &m, s.switch_brush_voxel, &mut switch_brush_voxel_ew);
 }
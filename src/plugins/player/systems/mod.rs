@@ -8,6 +8,7 @@ pub mod look;
 pub mod look_at;
 pub mod movements;
 pub mod spawn_item;
+pub mod switch_brush;
 
 pub const HEAD_LEVEL: f32 = 0.75;
 
@@ -0,0 +1,25 @@
+use crate::plugins::player::{
+    events::{SwitchBrushEvent, SwitchBrushShapeEvent, SwitchBrushVoxelEvent},
+    resources::PlayerBrush,
+};
+use bevy::prelude::*;
+
+pub fn switch_brush(
+    mut switch_brush_e: EventReader<SwitchBrushEvent>,
+    mut switch_brush_shape_e: EventReader<SwitchBrushShapeEvent>,
+    mut switch_brush_voxel_e: EventReader<SwitchBrushVoxelEvent>,
+    mut player_brush: ResMut<PlayerBrush>,
+) {
+    for _ in switch_brush_e.iter() {
+        player_brush.next_mode();
+        info!("Brush mode: {:?}", player_brush.mode);
+    }
+    for _ in switch_brush_shape_e.iter() {
+        player_brush.next_shape();
+        info!("Brush shape: {:?}", player_brush.shape);
+    }
+    for _ in switch_brush_voxel_e.iter() {
+        player_brush.next_voxel();
+        info!("Brush voxel: {:?}", player_brush.voxel);
+    }
+}
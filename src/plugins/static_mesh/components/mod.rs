@@ -1,5 +1,6 @@
 use crate::internal::color::Color;
 use crate::plugins::loading::resources::GameAssets;
+use crate::plugins::static_mesh::materials::TerrainMaterial;
 use bevy::prelude::*;
 use bevy::render::mesh::{self, PrimitiveTopology};
 use bevy::render::primitives::Aabb;
@@ -12,6 +13,46 @@ pub struct Vertex {
     pub pos: Vec3,
     pub normal: Vec3,
     pub color: Color,
+    /// Weights of the terrain texture layers, the color is used for the rest up to 1.
+    ///
+    /// Averaged over the cells sharing the vertex, voxels without textures add no weight
+    pub texture_weights: Vec4,
+}
+
+/// Triangle list with vertices shared between triangles
+#[derive(Debug, Clone, Default)]
+pub struct IndexedMesh {
+    pub vertices: Vec<Vertex>,
+    /// Each 3 indices are a triangle
+    pub indices: Vec<u32>,
+}
+
+impl IndexedMesh {
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Append triangles of the other mesh, vertices are not shared between the meshes
+    pub fn append(&mut self, other: &IndexedMesh) {
+        let offset = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&other.vertices);
+        self.indices
+            .extend(other.indices.iter().map(|index| index + offset));
+    }
+
+    /// Single mesh with triangles of all the meshes, e.g. of the chunk mesh blocks
+    pub fn join<'a>(meshes: impl IntoIterator<Item = &'a IndexedMesh>) -> Self {
+        let mut result = Self::default();
+        for mesh in meshes {
+            result.append(mesh);
+        }
+
+        result
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = [u32; 3]> + '_ {
+        self.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]])
+    }
 }
 
 impl StaticMeshComponent {
@@ -19,90 +60,100 @@ impl StaticMeshComponent {
         commands: &mut Commands,
         meshes: &mut Assets<Mesh>,
         assets: &GameAssets,
-        vertices: Vec<Vertex>,
+        mesh: IndexedMesh,
+        textured: bool,
     ) -> Entity {
-        let mut e = commands.spawn((
-            PbrBundle {
-                mesh: meshes.add(Self::generate_mesh(&vertices)),
-                material: assets.default_material.clone(),
-                ..default()
-            },
-            StaticMeshComponent,
-        ));
-        if let Some(collider) = Self::generate_collider(&vertices) {
+        let handle = meshes.add(Self::generate_mesh(&mesh, textured));
+        let mut e = if textured {
+            commands.spawn((
+                MaterialMeshBundle {
+                    mesh: handle,
+                    material: assets.terrain.material.clone(),
+                    ..default()
+                },
+                StaticMeshComponent,
+            ))
+        } else {
+            commands.spawn((
+                PbrBundle {
+                    mesh: handle,
+                    material: assets.default_material.clone(),
+                    ..default()
+                },
+                StaticMeshComponent,
+            ))
+        };
+        if let Some(collider) = Self::generate_collider(&mesh) {
             e.insert(collider);
         }
         e.id()
     }
 
+    /// Replace mesh and collider of the entity created by [`Self::spawn`]
     pub fn update(
-        children: &Children,
         commands: &mut Commands,
         meshes: &mut Assets<Mesh>,
-        meshes_q: &Query<(Entity, &Handle<Mesh>), With<StaticMeshComponent>>,
-        vertices: Vec<Vertex>,
+        entity: Entity,
+        handle: &Handle<Mesh>,
+        mesh: IndexedMesh,
+        textured: bool,
     ) {
-        for child in children.into_iter() {
-            if let Ok((entity, mesh)) = meshes_q.get(*child) {
-                meshes.remove(mesh);
-                let mut e = commands.entity(entity);
-                e.remove::<Handle<Mesh>>()
-                    .remove::<Collider>()
-                    .remove::<Aabb>()
-                    .insert(meshes.add(Self::generate_mesh(&vertices)));
-                if let Some(collider) = Self::generate_collider(&vertices) {
-                    e.insert(collider);
-                }
-
-                return;
-            }
+        meshes.remove(handle);
+        let mut e = commands.entity(entity);
+        e.remove::<Handle<Mesh>>()
+            .remove::<Collider>()
+            .remove::<Aabb>()
+            .insert(meshes.add(Self::generate_mesh(&mesh, textured)));
+        if let Some(collider) = Self::generate_collider(&mesh) {
+            e.insert(collider);
         }
     }
 
-    pub fn generate_collider(vertices: &Vec<Vertex>) -> Option<Collider> {
-        if vertices.is_empty() {
+    /// Trimesh collider sharing the vertices of the render mesh
+    pub fn generate_collider(mesh: &IndexedMesh) -> Option<Collider> {
+        if mesh.is_empty() {
             return None;
         }
 
-        let mut vert: Vec<Vec3> = Vec::with_capacity(vertices.len());
-        let mut indices: Vec<[u32; 3]> = Vec::new();
-
-        for vertex_i in 0..(vertices.len() / 3) {
-            let offset = vertex_i * 3;
-            indices.push([offset as u32, offset as u32 + 1, offset as u32 + 2]);
-            vert.push(vertices[offset].pos);
-            vert.push(vertices[offset + 1].pos);
-            vert.push(vertices[offset + 2].pos);
-        }
+        let vertices = mesh.vertices.iter().map(|v| v.pos).collect();
 
-        Some(Collider::trimesh(vert, indices))
+        Some(Collider::trimesh(vertices, mesh.triangles().collect()))
     }
 
-    pub fn generate_mesh(vertices: &[Vertex]) -> Mesh {
-        let mut indices_vec = Vec::new();
-
-        let mut positions: Vec<[f32; 3]> = Vec::new();
-        let mut normals: Vec<[f32; 3]> = Vec::new();
-        let mut colors: Vec<[f32; 4]> = Vec::new();
-        let mut uvs: Vec<[f32; 2]> = Vec::new();
-        for vertex in vertices.iter() {
-            indices_vec.push(positions.len() as u32);
-
+    /// Render mesh with 16 bit indices when possible.
+    ///
+    /// `textured` meshes have texture weights for [`TerrainMaterial`]
+    pub fn generate_mesh(mesh: &IndexedMesh, textured: bool) -> Mesh {
+        let mut positions: Vec<[f32; 3]> = Vec::with_capacity(mesh.vertices.len());
+        let mut normals: Vec<[f32; 3]> = Vec::with_capacity(mesh.vertices.len());
+        let mut colors: Vec<[f32; 4]> = Vec::with_capacity(mesh.vertices.len());
+        for vertex in mesh.vertices.iter() {
             positions.push(vertex.pos.into());
             normals.push(vertex.normal.into());
             colors.push(vertex.color.into());
-            uvs.push([1., 1.]);
         }
 
-        let indices = mesh::Indices::U32(indices_vec);
+        let indices = if mesh.vertices.len() <= u16::MAX as usize {
+            mesh::Indices::U16(mesh.indices.iter().map(|i| *i as u16).collect())
+        } else {
+            mesh::Indices::U32(mesh.indices.clone())
+        };
 
-        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
-        mesh.set_indices(Some(indices));
-        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        let mut result = Mesh::new(PrimitiveTopology::TriangleList);
+        result.set_indices(Some(indices));
+        result.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        result.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        result.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+
+        if textured {
+            let weights: Vec<[f32; 4]> = mesh
+                .vertices
+                .iter()
+                .map(|v| v.texture_weights.into())
+                .collect();
+            result.insert_attribute(TerrainMaterial::ATTRIBUTE_WEIGHTS, weights);
+        }
 
-        mesh
+        result
     }
 }
@@ -0,0 +1,69 @@
+use crate::plugins::game_world::resources::GameWorld;
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        mesh::{MeshVertexAttribute, MeshVertexBufferLayout},
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+            VertexFormat,
+        },
+    },
+};
+
+/// Terrain textured with triplanar mapping of the texture array layers.
+///
+/// Each vertex has weights of the [`Self::LAYERS`] layers, see [`VoxelId::get_texture_layer`](crate::internal::voxel::voxel_types::VoxelId::get_texture_layer).
+/// Vertex color is used for the rest of the weight, so voxels without texture keep their color.
+#[derive(AsBindGroup, TypeUuid, Debug, Clone)]
+#[uuid = "5b2c8f4e-1d7a-4e9b-a3c6-8f0e2d4b6a91"]
+pub struct TerrainMaterial {
+    /// Layers stacked vertically in a single image
+    #[texture(0, dimension = "2d_array")]
+    #[sampler(1)]
+    pub textures: Handle<Image>,
+    /// Size of the texture in meters
+    #[uniform(2)]
+    pub texture_size: f32,
+}
+
+impl TerrainMaterial {
+    pub const LAYERS: u32 = 4;
+
+    /// Weights of the texture layers, vertex color is used where the sum is less than 1
+    pub const ATTRIBUTE_WEIGHTS: MeshVertexAttribute =
+        MeshVertexAttribute::new("Vertex_TextureWeights", 937105762, VertexFormat::Float32x4);
+
+    /// Chunks far away are too small on the screen for textures, so only vertex color is used
+    pub fn is_textured_level(level: usize) -> bool {
+        level + 1 >= GameWorld::MAX_DETAIL_LEVEL
+    }
+}
+
+impl Material for TerrainMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/terrain.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "shaders/terrain.wgsl".into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+            Mesh::ATTRIBUTE_COLOR.at_shader_location(2),
+            Self::ATTRIBUTE_WEIGHTS.at_shader_location(3),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+
+        Ok(())
+    }
+}
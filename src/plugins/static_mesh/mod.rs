@@ -1,12 +1,14 @@
-use self::components::StaticMeshComponent;
+use self::{components::StaticMeshComponent, materials::TerrainMaterial};
 use bevy::prelude::*;
 
 pub mod components;
+pub mod materials;
 
 pub struct StaticMeshPlugin;
 
 impl Plugin for StaticMeshPlugin {
     fn build(&self, app: &mut App) {
-        app.register_type::<StaticMeshComponent>();
+        app.register_type::<StaticMeshComponent>()
+            .add_plugin(MaterialPlugin::<TerrainMaterial>::default());
     }
 }
@@ -0,0 +1,188 @@
+use super::{CaveBiome, CaveBiomeCheckInput};
+use crate::plugins::{
+    objects::resources::objects_registry::ObjectsRegistry,
+    world_generator::{
+        internal::biomes::{
+            data::{
+                intern_id, BiomeCaveDefinition, BiomeLoadError, BiomeObjectDefinition, BiomeRange,
+                BiomeSource, DataBiomeObject,
+            },
+            BiomeID, SpawnObjectInp,
+        },
+        resources::GenCaveInp,
+    },
+};
+use serde::Deserialize;
+use std::cmp::Reverse;
+
+/// Definitions of the built-in cave biomes with their file names, used until the assets are loaded
+const BUILTIN_DEFINITIONS: [(&str, &str); 3] = [
+    (
+        "crystal_caverns.json",
+        include_str!("../../../../../../assets/biomes/caves/crystal_caverns.json"),
+    ),
+    (
+        "mossy_caves.json",
+        include_str!("../../../../../../assets/biomes/caves/mossy_caves.json"),
+    ),
+    (
+        "warm_caves.json",
+        include_str!("../../../../../../assets/biomes/caves/warm_caves.json"),
+    ),
+];
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CaveBiomeCheckDefinition {
+    /// Depth of the chunk center under the surface in meters
+    #[serde(default)]
+    pub depth: BiomeRange,
+    #[serde(default)]
+    pub noise: BiomeRange,
+    /// Temperature on the surface above the chunk
+    #[serde(default)]
+    pub temperature: BiomeRange,
+}
+
+impl CaveBiomeCheckDefinition {
+    pub fn contains(&self, inp: CaveBiomeCheckInput) -> bool {
+        self.depth.contains(inp.depth)
+            && self.noise.contains(inp.noise)
+            && self.temperature.contains(inp.temperature)
+    }
+}
+
+/// Cave biome definition stored in `assets/biomes/caves/*.json`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CaveBiomeDefinition {
+    pub id: String,
+    /// Cave biomes with higher priority are checked first
+    #[serde(default)]
+    pub priority: i32,
+    /// Cave biome is used if any of the checks matches
+    pub check: Vec<CaveBiomeCheckDefinition>,
+    pub cave: BiomeCaveDefinition,
+    /// Objects spawned on the cave floor, order of the list affects object positions
+    #[serde(default)]
+    pub objects: Vec<BiomeObjectDefinition>,
+}
+
+/// Cave biome loaded from a [`CaveBiomeDefinition`]
+#[derive(Debug)]
+pub struct DataCaveBiome {
+    id: BiomeID,
+    priority: i32,
+    check: Vec<CaveBiomeCheckDefinition>,
+    cave_inp: GenCaveInp,
+    objects: Vec<DataBiomeObject>,
+}
+
+impl DataCaveBiome {
+    /// Directory with cave biome definitions relative to the directory of the surface biomes
+    pub const DIR: &str = "caves";
+
+    pub fn new(
+        def: CaveBiomeDefinition,
+        registry: &ObjectsRegistry,
+    ) -> Result<Self, BiomeLoadError> {
+        Ok(Self {
+            id: intern_id(def.id),
+            priority: def.priority,
+            check: def.check,
+            cave_inp: def.cave.to_inp(),
+            objects: DataBiomeObject::load_all(def.objects, registry)?,
+        })
+    }
+
+    pub fn from_json(data: &[u8], registry: &ObjectsRegistry) -> Result<Self, BiomeLoadError> {
+        Self::new(serde_json::from_slice(data)?, registry)
+    }
+
+    /// Load cave biomes from the `sources` in the order they should be registered:
+    /// by priority from the highest and then by file name
+    pub fn load_sources(
+        sources: &[BiomeSource],
+        registry: &ObjectsRegistry,
+    ) -> Result<Vec<Self>, BiomeLoadError> {
+        let mut sources = sources.iter().collect::<Vec<_>>();
+        sources.sort_by(|a, b| a.file.cmp(&b.file));
+
+        let mut biomes = sources
+            .into_iter()
+            .map(|source| {
+                Self::from_json(source.data.as_bytes(), registry)
+                    .map_err(|err| err.in_file(source.file.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        biomes.sort_by_key(|biome| Reverse(biome.priority));
+
+        Ok(biomes)
+    }
+
+    /// Sources of the built-in cave biomes defined in `assets/biomes/caves` at compile time
+    pub fn builtin_sources() -> Vec<BiomeSource> {
+        BUILTIN_DEFINITIONS
+            .iter()
+            .map(|(file, data)| BiomeSource {
+                file: file.to_string(),
+                data: data.to_string(),
+            })
+            .collect()
+    }
+
+    /// Built-in cave biomes, sorted like [`DataCaveBiome::load_sources`]
+    pub fn builtin(registry: &ObjectsRegistry) -> Vec<Self> {
+        Self::load_sources(&Self::builtin_sources(), registry)
+            .unwrap_or_else(|err| panic!("built-in cave biome is invalid: {}", err))
+    }
+}
+
+impl CaveBiome for DataCaveBiome {
+    fn get_id(&self) -> BiomeID {
+        self.id
+    }
+
+    fn check(&self, inp: CaveBiomeCheckInput) -> bool {
+        self.check.iter().any(|check| check.contains(inp))
+    }
+
+    fn get_cave_inp(&self) -> GenCaveInp {
+        self.cave_inp
+    }
+
+    fn get_objects(&self) -> Vec<SpawnObjectInp> {
+        self.objects
+            .iter()
+            .map(DataBiomeObject::to_spawn_inp)
+            .collect()
+    }
+}
+
+#[test]
+fn data_cave_biome_from_json() {
+    use crate::internal::voxel::voxel_types::VoxelId;
+
+    let registry = ObjectsRegistry::new();
+
+    let biomes = DataCaveBiome::builtin(&registry);
+    let ids = biomes.iter().map(|b| b.get_id()).collect::<Vec<_>>();
+    assert_eq!(ids, vec!["warm_caves", "crystal_caverns", "mossy_caves"]);
+
+    let warm = &biomes[0];
+    assert_eq!(warm.get_cave_inp().wall_id, VoxelId::BASALT);
+    assert_eq!(warm.get_objects().len(), 2);
+
+    let check = |depth, noise, temperature| {
+        warm.check(CaveBiomeCheckInput {
+            depth,
+            noise,
+            temperature,
+        })
+    };
+    assert!(check(50.0, 0.5, 0.0));
+    assert!(check(50.0, 0.0, 50.0));
+    assert!(!check(50.0, 0.0, 0.0));
+    assert!(!check(10.0, 0.5, 50.0));
+}
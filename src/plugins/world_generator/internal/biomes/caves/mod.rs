@@ -0,0 +1,90 @@
+use super::{spawn_at, BiomeID, ChunkBiomes, SpawnObjectInp};
+use crate::{
+    internal::pos::ChunkPos,
+    plugins::world_generator::resources::{GenCaveInp, WorldGenerator},
+};
+use bevy::prelude::*;
+use std::fmt::Debug;
+
+pub mod data;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CaveBiomeCheckInput {
+    /// Depth of the chunk center under the surface in meters
+    pub depth: f64,
+    /// 3d noise value between -1 and 1, used to split the underground into zones
+    pub noise: f64,
+    /// Temperature on the surface above the chunk
+    pub temperature: f64,
+}
+
+/// Underground layer that replaces caves of the surface biome
+pub trait CaveBiome: Send + Sync + Debug {
+    fn get_id(&self) -> BiomeID;
+
+    /// check if the cave biome should be used in the chunk
+    fn check(&self, inp: CaveBiomeCheckInput) -> bool;
+
+    /// Shape of the caves and material of their walls
+    fn get_cave_inp(&self) -> GenCaveInp;
+
+    /// Objects spawned on the cave floor, order of the list affects object positions
+    fn get_objects(&self) -> Vec<SpawnObjectInp>;
+}
+
+/// Spawn objects on the cave floor in the chunk, if it belongs to a cave biome
+pub fn spawn_cave_objects(
+    biomes: &ChunkBiomes,
+    chunk_pos: ChunkPos,
+    commands: &mut Commands,
+    gen: &WorldGenerator,
+) -> usize {
+    let cave = match gen.get_cave_biome(chunk_pos) {
+        Some(cave) => cave,
+        None => return 0,
+    };
+
+    let mut spawned = 0;
+
+    for (id, mut inp) in cave.get_objects().into_iter().enumerate() {
+        for i in 0..inp.amount {
+            if let Some((pos, y_angle)) =
+                gen.get_cave_floor_object_pos(biomes, chunk_pos, id, inp.chance, i)
+            {
+                spawn_at(commands, &mut inp, pos, y_angle);
+                spawned += 1;
+            }
+        }
+    }
+
+    spawned
+}
+
+#[test]
+fn cave_biomes_are_underground() {
+    use crate::plugins::world_generator::resources::settings::GeneratorSettings;
+
+    let gen = WorldGenerator::new(123);
+    let legacy = WorldGenerator::with_settings(123, GeneratorSettings::legacy());
+
+    let mut found = 0;
+
+    for i in 0..512 {
+        let pos = ChunkPos::from_index(i, 8) * ChunkPos::new(20, 1, 20) - ChunkPos::new(0, 40, 0);
+
+        assert!(legacy.get_cave_biome(pos).is_none());
+
+        if let Some(cave) = gen.get_cave_biome(pos) {
+            let center = crate::internal::chunks::Chunk::pos_to_translation(pos);
+            assert!(
+                (center.y as f64) < gen.get_surface_height(center.x as f64, center.z as f64),
+                "{} above the surface at {:?}",
+                cave.get_id(),
+                pos
+            );
+            found += 1;
+        }
+    }
+
+    assert!(found > 0, "no cave biomes found");
+}
@@ -0,0 +1,512 @@
+use super::*;
+use crate::{
+    internal::voxel::voxel_types::VoxelId,
+    plugins::{
+        game_world::utils::save_error::SaveError,
+        objects::{components::GameWorldObjectTrait, resources::objects_registry::ObjectsRegistry},
+        world_generator::resources::{
+            noise_graph::{BiomeWeights, NoiseNode},
+            GenCaveInp, GenOresInp,
+        },
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs, io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// Definitions of the built-in biomes with their file names, used until the assets are loaded
+const BUILTIN_DEFINITIONS: [(&str, &str); 3] = [
+    (
+        "desert.json",
+        include_str!("../../../../../assets/biomes/desert.json"),
+    ),
+    (
+        "plains.json",
+        include_str!("../../../../../assets/biomes/plains.json"),
+    ),
+    (
+        "tundra.json",
+        include_str!("../../../../../assets/biomes/tundra.json"),
+    ),
+];
+
+/// Ids of all biomes created so far, each id is leaked only once
+static INTERNED_IDS: Mutex<Vec<BiomeID>> = Mutex::new(Vec::new());
+
+/// Get id with the static lifetime, biomes are loaded again for each world
+pub(super) fn intern_id(id: String) -> BiomeID {
+    let mut ids = INTERNED_IDS.lock().unwrap();
+
+    if let Some(interned) = ids.iter().find(|interned| **interned == id) {
+        return interned;
+    }
+
+    let interned: BiomeID = Box::leak(id.into_boxed_str());
+    ids.push(interned);
+
+    interned
+}
+
+/// Exclusive range of the biome check value, missing bound is not checked
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BiomeRange {
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+impl BiomeRange {
+    pub fn contains(&self, value: f64) -> bool {
+        self.min.map_or(true, |min| value > min) && self.max.map_or(true, |max| value < max)
+    }
+
+    /// Suitability of the `value` with transition zones of `width` at the bounds
+    pub fn score(&self, value: f64, width: f64) -> f64 {
+        self.min.map_or(1.0, |min| score_above(value, min, width))
+            * self.max.map_or(1.0, |max| score_below(value, max, width))
+    }
+}
+
+/// Source of a [`BiomeDefinition`], pinned in the world settings so the world keeps its biomes
+/// when the files in `assets/biomes` change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BiomeSource {
+    /// File name, definitions with the same file name replace each other
+    pub file: String,
+    /// JSON of the definition
+    pub data: String,
+}
+
+impl BiomeSource {
+    pub fn builtin() -> Vec<Self> {
+        BUILTIN_DEFINITIONS
+            .iter()
+            .map(|(file, data)| Self {
+                file: file.to_string(),
+                data: data.to_string(),
+            })
+            .collect()
+    }
+
+    /// Read all biome definitions from the `dir`, sorted by file name
+    pub fn read_dir(dir: &Path) -> Result<Vec<Self>, BiomeLoadError> {
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        paths.retain(|path| {
+            path.extension().and_then(|ext| ext.to_str()) == Some(DataBiome::FILE_EXTENSION)
+        });
+        paths.sort();
+
+        paths
+            .into_iter()
+            .map(|path| {
+                let data = fs::read_to_string(&path)
+                    .map_err(|err| BiomeLoadError::from(err).in_file(path.to_string_lossy()))?;
+                let file = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                Ok(Self { file, data })
+            })
+            .collect()
+    }
+
+    /// Add `sources` to the `target`, replacing the ones with the same file name
+    pub fn merge(target: &mut Vec<Self>, sources: Vec<Self>) {
+        for source in sources {
+            target.retain(|s| s.file != source.file);
+            target.push(source);
+        }
+
+        target.sort_by(|a, b| a.file.cmp(&b.file));
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BiomeCheckDefinition {
+    #[serde(default)]
+    pub temperature: BiomeRange,
+    #[serde(default)]
+    pub humidity: BiomeRange,
+    #[serde(default)]
+    pub elevation: BiomeRange,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BiomeLayersDefinition {
+    pub first: VoxelId,
+    pub second: VoxelId,
+    pub rest: VoxelId,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BiomeCaveDefinition {
+    pub factor: f64,
+    pub offset: f64,
+    pub strength: f64,
+    /// Material of the cave walls under the surface layer
+    #[serde(default = "BiomeCaveDefinition::default_wall")]
+    pub wall: VoxelId,
+}
+
+impl BiomeCaveDefinition {
+    fn default_wall() -> VoxelId {
+        VoxelId::STONE
+    }
+
+    pub fn to_inp(&self) -> GenCaveInp {
+        GenCaveInp {
+            cave_factor: self.factor,
+            cave_offset: self.offset,
+            cave_strength: self.strength,
+            wall_id: self.wall,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BiomeObjectDefinition {
+    /// Id of the object in the [`ObjectsRegistry`]
+    pub id: String,
+    /// Serialized object data, passed to [`GameWorldObjectTrait::deserialize`]
+    #[serde(default)]
+    pub data: Vec<u8>,
+    pub chance: f32,
+    #[serde(default = "BiomeObjectDefinition::default_amount")]
+    pub amount: usize,
+    #[serde(default)]
+    pub allow_air: bool,
+    #[serde(default)]
+    pub offset: [f32; 3],
+}
+
+impl BiomeObjectDefinition {
+    fn default_amount() -> usize {
+        1
+    }
+}
+
+/// Biome definition stored in `assets/biomes/*.json`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BiomeDefinition {
+    pub id: String,
+    /// Loaded biomes with higher priority are checked first
+    #[serde(default)]
+    pub priority: i32,
+    /// Biome is used only if all of the values are in the ranges
+    #[serde(default)]
+    pub check: BiomeCheckDefinition,
+    pub layers: BiomeLayersDefinition,
+    pub cave: BiomeCaveDefinition,
+    pub bumps: f64,
+    pub height: f64,
+    /// Ore deposits, missing ores are not generated
+    #[serde(default)]
+    pub ores: GenOresInp,
+    /// Objects spawned in the biome, order of the list affects object positions
+    #[serde(default)]
+    pub objects: Vec<BiomeObjectDefinition>,
+    /// Terrain graph, default terrain of the generator is used if missing
+    #[serde(default)]
+    pub terrain: Option<NoiseNode>,
+}
+
+#[derive(Debug)]
+pub enum BiomeLoadError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    Object(SaveError),
+    /// Error happened while loading the file at `path`
+    File {
+        path: String,
+        error: Box<BiomeLoadError>,
+    },
+}
+
+impl BiomeLoadError {
+    /// Attach path of the file to the error
+    pub fn in_file(self, path: impl Into<String>) -> Self {
+        Self::File {
+            path: path.into(),
+            error: Box::new(self),
+        }
+    }
+}
+
+impl fmt::Display for BiomeLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {}", err),
+            Self::Parse(err) => write!(f, "invalid biome definition: {}", err),
+            Self::Object(err) => write!(f, "invalid biome object: {}", err),
+            Self::File { path, error } => write!(f, "{}: {}", path, error),
+        }
+    }
+}
+
+impl std::error::Error for BiomeLoadError {}
+
+impl From<io::Error> for BiomeLoadError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for BiomeLoadError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<SaveError> for BiomeLoadError {
+    fn from(err: SaveError) -> Self {
+        Self::Object(err)
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct DataBiomeObject {
+    template: Arc<dyn GameWorldObjectTrait>,
+    chance: f32,
+    amount: usize,
+    allow_air: bool,
+    offset: Vec3,
+}
+
+impl DataBiomeObject {
+    pub(super) fn load_all(
+        defs: Vec<BiomeObjectDefinition>,
+        registry: &ObjectsRegistry,
+    ) -> Result<Vec<Self>, BiomeLoadError> {
+        defs.into_iter()
+            .map(|object| {
+                let template = registry.deserialize_object(&object.id, &object.data)?;
+
+                Ok(Self {
+                    template: Arc::from(template),
+                    chance: object.chance,
+                    amount: object.amount,
+                    allow_air: object.allow_air,
+                    offset: Vec3::from(object.offset),
+                })
+            })
+            .collect()
+    }
+
+    pub(super) fn to_spawn_inp(&self) -> SpawnObjectInp {
+        let template = self.template.clone();
+
+        SpawnObjectInp {
+            chance: self.chance,
+            amount: self.amount,
+            allow_air: self.allow_air,
+            get_spawner: Box::new(move |t| template.create_spawner(t)),
+            offset: self.offset,
+        }
+    }
+}
+
+/// Biome loaded from a [`BiomeDefinition`]
+#[derive(Debug)]
+pub struct DataBiome {
+    id: BiomeID,
+    priority: i32,
+    check: BiomeCheckDefinition,
+    voxel_inp: GenVoxelInp,
+    landscape_inp: LandscapeHeightInp,
+    objects: Vec<DataBiomeObject>,
+    terrain: Option<NoiseNode>,
+}
+
+impl DataBiome {
+    pub const FILE_EXTENSION: &str = "json";
+
+    pub fn new(def: BiomeDefinition, registry: &ObjectsRegistry) -> Result<Self, BiomeLoadError> {
+        let objects = DataBiomeObject::load_all(def.objects, registry)?;
+
+        Ok(Self {
+            id: intern_id(def.id),
+            priority: def.priority,
+            check: def.check,
+            voxel_inp: GenVoxelInp {
+                cave_inp: def.cave.to_inp(),
+                ores: def.ores,
+                biomes: BiomeWeights::NONE,
+                first_layer_id: def.layers.first,
+                second_layer_id: def.layers.second,
+                rest_layers_id: def.layers.rest,
+                bumps_factor: def.bumps,
+            },
+            landscape_inp: LandscapeHeightInp { height: def.height },
+            objects,
+            terrain: def.terrain,
+        })
+    }
+
+    pub fn from_json(data: &[u8], registry: &ObjectsRegistry) -> Result<Self, BiomeLoadError> {
+        Self::new(serde_json::from_slice(data)?, registry)
+    }
+
+    /// Load all biome definitions from the `dir`.
+    ///
+    /// Biomes are sorted in the order they should be registered: by priority and then by file name.
+    pub fn load_dir(dir: &Path, registry: &ObjectsRegistry) -> Result<Vec<Self>, BiomeLoadError> {
+        Self::load_sources(&BiomeSource::read_dir(dir)?, registry)
+    }
+
+    /// Load biomes from the `sources`, sorted like [`DataBiome::load_dir`]
+    pub fn load_sources(
+        sources: &[BiomeSource],
+        registry: &ObjectsRegistry,
+    ) -> Result<Vec<Self>, BiomeLoadError> {
+        let mut sources = sources.iter().collect::<Vec<_>>();
+        sources.sort_by(|a, b| a.file.cmp(&b.file));
+
+        let biomes = sources
+            .into_iter()
+            .map(|source| {
+                Self::from_json(source.data.as_bytes(), registry)
+                    .map_err(|err| err.in_file(source.file.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self::sort(biomes))
+    }
+
+    /// Built-in biomes defined in `assets/biomes` at compile time, sorted like [`DataBiome::load_dir`]
+    pub fn builtin(registry: &ObjectsRegistry) -> Vec<Self> {
+        Self::load_sources(&BiomeSource::builtin(), registry)
+            .unwrap_or_else(|err| panic!("built-in biome is invalid: {}", err))
+    }
+
+    /// Sort biomes by priority, stable sort keeps file name order for the same priority
+    fn sort(mut biomes: Vec<Self>) -> Vec<Self> {
+        biomes.sort_by_key(|biome| biome.priority);
+        biomes
+    }
+}
+
+impl Biome for DataBiome {
+    fn get_id(&self) -> BiomeID {
+        self.id
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    fn get_terrain(&self) -> Option<NoiseNode> {
+        self.terrain.clone()
+    }
+
+    fn get_landscape_height_inp(
+        &self,
+        _gen: &WorldGenerator,
+        _pos: ChunkPos,
+    ) -> LandscapeHeightInp {
+        self.landscape_inp
+    }
+
+    fn get_generate_voxel_inp(&self, _gen: &WorldGenerator, _pos: ChunkPos) -> GenVoxelInp {
+        self.voxel_inp
+    }
+
+    fn check_pos(&self, _gen: &WorldGenerator, _pos: ChunkPos, inp: BiomeCheckInput) -> bool {
+        self.check.temperature.contains(inp.temperature)
+            && self.check.humidity.contains(inp.humidity)
+            && self.check.elevation.contains(inp.elevation)
+    }
+
+    fn suitability(&self, _gen: &WorldGenerator, _pos: ChunkPos, inp: BiomeCheckInput) -> f64 {
+        self.check
+            .temperature
+            .score(inp.temperature, BiomeCheckInput::TEMPERATURE_BLEND)
+            * self
+                .check
+                .humidity
+                .score(inp.humidity, BiomeCheckInput::HUMIDITY_BLEND)
+            * self
+                .check
+                .elevation
+                .score(inp.elevation, BiomeCheckInput::ELEVATION_BLEND)
+    }
+
+    fn get_objects(&self) -> Vec<SpawnObjectInp> {
+        self.objects
+            .iter()
+            .map(DataBiomeObject::to_spawn_inp)
+            .collect()
+    }
+}
+
+#[test]
+fn data_biome_from_json() {
+    let registry = ObjectsRegistry::new();
+    let gen = WorldGenerator::new(123);
+
+    let biome = DataBiome::from_json(
+        include_bytes!("../../../../../assets/biomes/tundra.json"),
+        &registry,
+    )
+    .unwrap();
+
+    assert_eq!(biome.get_id(), TUNDRA_ID);
+    assert_eq!(biome.objects.len(), 2);
+    assert_eq!(
+        biome.objects[0].template.model_path(),
+        "models/spruce-snow.glb"
+    );
+
+    let inp = biome.get_generate_voxel_inp(&gen, ChunkPos::new(0, 0, 0));
+    assert_eq!(inp.first_layer_id, VoxelId::SNOW);
+
+    let check = |temperature| {
+        biome.check_pos(
+            &gen,
+            ChunkPos::new(0, 0, 0),
+            BiomeCheckInput {
+                temperature,
+                humidity: 0.0,
+                elevation: 0.0,
+            },
+        )
+    };
+    assert!(check(-10.0));
+    assert!(!check(10.0));
+
+    let suitability = |temperature| {
+        biome.suitability(
+            &gen,
+            ChunkPos::new(0, 0, 0),
+            BiomeCheckInput {
+                temperature,
+                humidity: 0.0,
+                elevation: 0.0,
+            },
+        )
+    };
+    assert_eq!(suitability(-10.0), 1.0);
+    assert_eq!(suitability(0.0), 0.5);
+    assert_eq!(suitability(10.0), 0.0);
+
+    let unknown = br#"{ "id": "a", "layers": { "first": 0, "second": 1, "rest": 2 },
+        "cave": { "factor": 1.0, "offset": 0.0, "strength": 0.0 }, "bumps": 0.0, "height": 1.0,
+        "objects": [{ "id": "unknown", "chance": 1.0 }] }"#;
+    assert!(matches!(
+        DataBiome::from_json(unknown, &registry),
+        Err(BiomeLoadError::Object(SaveError::UnknownObject(_)))
+    ));
+}
@@ -7,8 +7,13 @@ use crate::{
         game_world::resources::GameWorld,
         inspector::components::InspectorDisabled,
         objects::components::object_spawner::ObjectSpawner,
-        world_generator::resources::{
-            GenVoxelInp, LandscapeHeightInp, ObjectGeneratorID, WorldGenerator,
+        world_generator::{
+            internal::random::GenRng,
+            resources::{
+                noise_graph::NoiseNode,
+                settings::{GeneratorSettings, GeneratorVersion},
+                GenVoxelInp, LandscapeHeightInp, ObjectGeneratorID, WorldGenerator,
+            },
         },
     },
 };
@@ -16,12 +21,16 @@ use bevy::prelude::*;
 use lerp::Lerp;
 use std::fmt::Debug;
 
-pub mod desert;
-pub mod plains;
-pub mod tundra;
+pub mod caves;
+pub mod data;
 
 pub type BiomeID = &'static str;
 
+/// Id of the default biome, it is used where no other biome matches
+pub const PLAINS_ID: BiomeID = "plains";
+pub const DESERT_ID: BiomeID = "desert";
+pub const TUNDRA_ID: BiomeID = "tundra";
+
 #[derive(Debug, Clone, Copy)]
 pub struct BiomeCheckInput {
     pub temperature: f64,
@@ -29,27 +38,62 @@ pub struct BiomeCheckInput {
     pub elevation: f64,
 }
 
+impl BiomeCheckInput {
+    /// Width of the transition zone between biomes in celsius
+    pub const TEMPERATURE_BLEND: f64 = 10.0;
+    pub const HUMIDITY_BLEND: f64 = 0.1;
+    pub const ELEVATION_BLEND: f64 = 0.1;
+}
+
+/// Suitability of the `value` for the biome that requires it to be above the `edge`.
+///
+/// Goes from 0 to 1 across the transition zone of `width` centered at the `edge`.
+pub fn score_above(value: f64, edge: f64, width: f64) -> f64 {
+    ((value - edge) / width + 0.5).clamp(0.0, 1.0)
+}
+
+/// Suitability of the `value` for the biome that requires it to be below the `edge`
+pub fn score_below(value: f64, edge: f64, width: f64) -> f64 {
+    score_above(-value, -edge, width)
+}
+
 pub trait Biome: Send + Sync + Debug {
     fn get_id(&self) -> BiomeID;
 
+    /// Biomes with higher priority are checked first
+    fn priority(&self) -> i32 {
+        0
+    }
+
     /// pos.y should be ignored
     fn get_landscape_height_inp(&self, gen: &WorldGenerator, pos: ChunkPos) -> LandscapeHeightInp;
 
     fn get_generate_voxel_inp(&self, gen: &WorldGenerator, pos: ChunkPos) -> GenVoxelInp;
 
+    /// Terrain graph of the biome, default terrain of the generator is used if `None`
+    fn get_terrain(&self) -> Option<NoiseNode> {
+        None
+    }
+
     /// check if the biome should be used at the given position
     fn check_pos(&self, gen: &WorldGenerator, pos: ChunkPos, inp: BiomeCheckInput) -> bool;
 
-    fn spawn_objects(
-        &self,
-        biomes: &ChunkBiomes,
-        chunk_pos: ChunkPos,
-        commands: &mut Commands,
-        gen: &WorldGenerator,
-    ) -> usize;
+    /// How well the position suits the biome, value between 0 and 1.
+    ///
+    /// Used to blend neighboring biomes, the default one has sharp borders defined by [`Biome::check_pos`].
+    fn suitability(&self, gen: &WorldGenerator, pos: ChunkPos, inp: BiomeCheckInput) -> f64 {
+        if self.check_pos(gen, pos, inp) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Objects spawned in the biome, order of the list affects object positions
+    fn get_objects(&self) -> Vec<SpawnObjectInp>;
 }
 
-pub(self) struct SpawnObjectInp {
+pub struct SpawnObjectInp {
     chance: f32,
     amount: usize,
     allow_air: bool,
@@ -89,19 +133,22 @@ pub(self) fn spawn_object(
             inp.allow_air,
         ) {
             spawned += 1;
-
-            let mut transform = Transform::from_translation(pos + inp.offset);
-            transform.rotate_y(y_angle);
-            let spawner = inp.get_spawner.as_mut()(transform);
-            let name = Name::new(format!("object_spawner:{}", spawner.id()));
-
-            commands.spawn((spawner, InspectorDisabled, name));
+            spawn_at(commands, &mut inp, pos, y_angle);
         }
     }
 
     spawned
 }
 
+pub(self) fn spawn_at(commands: &mut Commands, inp: &mut SpawnObjectInp, pos: Vec3, y_angle: f32) {
+    let mut transform = Transform::from_translation(pos + inp.offset);
+    transform.rotate_y(y_angle);
+    let spawner = inp.get_spawner.as_mut()(transform);
+    let name = Name::new(format!("object_spawner:{}", spawner.id()));
+
+    commands.spawn((spawner, InspectorDisabled, name));
+}
+
 pub(self) fn spawn_objects(
     biomes: &ChunkBiomes,
     chunk_pos: ChunkPos,
@@ -118,6 +165,29 @@ pub(self) fn spawn_objects(
     count
 }
 
+/// Spawn objects of the biomes blended at the chunk.
+///
+/// Spawn tables of the biomes are merged and chances of their objects are scaled by biome weights.
+pub fn spawn_biomes_objects(
+    biomes: &ChunkBiomes,
+    chunk_pos: ChunkPos,
+    commands: &mut Commands,
+    gen: &WorldGenerator,
+) -> usize {
+    let objects = gen
+        .get_biome_weights(chunk_pos)
+        .into_iter()
+        .flat_map(|(biome, weight)| {
+            biome.get_objects().into_iter().map(move |mut inp| {
+                inp.chance *= weight as f32;
+                inp
+            })
+        })
+        .collect();
+
+    spawn_objects(biomes, chunk_pos, commands, gen, objects)
+}
+
 /// Represents the biomes for each vertex of a chunk
 ///
 /// This can be used to get average generation-input values for specific voxel
@@ -127,6 +197,10 @@ pub struct ChunkBiomes {
     voxel_inputs: Vec<GenVoxelInp>,
     landscape_inputs: Vec<LandscapeHeightInp>,
     region_pos: ChunkPos,
+    /// Seed of the world, used to blend voxel ids of the neighboring biomes
+    seed: u64,
+    /// Generator version of the world, see [`ChunkBiomes::get_generate_voxel_inp`]
+    version: GeneratorVersion,
 }
 
 impl ChunkBiomes {
@@ -142,7 +216,7 @@ impl ChunkBiomes {
             .map(|i| {
                 let pos = chunk_offset + ChunkPos::from_index_2d(i, size_chunks);
 
-                gen.get_biome(pos).get_landscape_height_inp(gen, pos)
+                gen.get_landscape_height_inp(pos)
             })
             .collect();
 
@@ -151,7 +225,7 @@ impl ChunkBiomes {
             .map(|i| {
                 let pos = chunk_offset + ChunkPos::from_index(i, size_chunks);
 
-                gen.get_biome(pos).get_generate_voxel_inp(gen, pos)
+                gen.get_generate_voxel_inp(pos)
             })
             .collect();
 
@@ -159,6 +233,8 @@ impl ChunkBiomes {
             region_pos,
             voxel_inputs,
             landscape_inputs,
+            seed: gen.seed(),
+            version: gen.settings().version,
         }
     }
 
@@ -168,8 +244,15 @@ impl ChunkBiomes {
 
     /// Get the average generation input for a voxel in the area
     ///
+    /// Voxel ids can't be averaged, so they are taken from one of the neighboring biomes
+    /// chosen randomly with the probability of its interpolation weight.
+    /// Choice depends only on the world seed and `voxel_pos`.
+    /// Before [`GeneratorSettings::V2`] ids of the closest biome are used.
+    ///
     /// `voxel_pos`: the position of the voxel relative to the area covered by this ChunkBiomes
     pub fn get_generate_voxel_inp(&self, voxel_pos: GlobalVoxelPos) -> GenVoxelInp {
+        let mut rng = GenRng::new(self.seed).with_pos(voxel_pos);
+
         let voxel_pos = voxel_pos - self.region_pos * (GameWorld::REGION_SIZE * Chunk::SIZE) as i64;
 
         let chunk_pos: VoxelPos = Chunk::global_voxel_pos_to_chunk_pos(voxel_pos).into();
@@ -195,7 +278,25 @@ impl ChunkBiomes {
         let z0 = yz00.lerp(yz10, transition.y);
         let z1 = yz01.lerp(yz11, transition.y);
 
-        z0.lerp(z1, transition.z)
+        let mut result = z0.lerp(z1, transition.z);
+
+        if self.version < GeneratorSettings::V2 {
+            return result;
+        }
+
+        let pick = |t: f32, rng: &mut GenRng| usize::from(rng.next_f32() < t);
+        let corner = VoxelPos::new(
+            pick(transition.x, &mut rng),
+            pick(transition.y, &mut rng),
+            pick(transition.z, &mut rng),
+        );
+        let ids = self.voxel_inputs[(chunk_pos + corner).to_index(size)];
+
+        result.first_layer_id = ids.first_layer_id;
+        result.second_layer_id = ids.second_layer_id;
+        result.rest_layers_id = ids.rest_layers_id;
+
+        result
     }
 
     pub fn get_landscape_height_inp(&self, voxel_pos: GlobalVoxelPos) -> LandscapeHeightInp {
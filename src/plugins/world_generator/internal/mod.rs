@@ -1 +1,3 @@
 pub mod biomes;
+pub mod random;
+pub mod structures;
@@ -0,0 +1,89 @@
+use crate::internal::pos::Pos;
+
+/// Deterministic random number generator seeded by hashing the world seed with input values.
+///
+/// Same seed and inputs always produce the same sequence on every platform, so it can be used
+/// for anything that should be reproduced when chunk is generated again.
+///
+/// ```ignore
+/// let mut rng = gen.rng(chunk_pos, generator_id);
+/// let chance = rng.next_f64();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenRng {
+    state: u64,
+}
+
+impl GenRng {
+    /// SplitMix64 increment
+    const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Self::mix(seed),
+        }
+    }
+
+    /// SplitMix64 finalizer
+    fn mix(v: u64) -> u64 {
+        let v = (v ^ (v >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        let v = (v ^ (v >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        v ^ (v >> 31)
+    }
+
+    /// Derive new generator from this one and the `value`
+    pub fn with(self, value: u64) -> Self {
+        Self {
+            state: Self::mix(self.state ^ Self::mix(value.wrapping_add(Self::GAMMA))),
+        }
+    }
+
+    /// Derive new generator from this one and the chunk or voxel position
+    pub fn with_pos(self, pos: Pos<i64>) -> Self {
+        self.with(pos.x as u64)
+            .with(pos.y as u64)
+            .with(pos.z as u64)
+    }
+
+    /// Derive new generator from this one and the string, e.g. id of the generated feature
+    pub fn with_str(self, value: &str) -> Self {
+        value
+            .bytes()
+            .fold(self.with(value.len() as u64), |rng, b| rng.with(b as u64))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(Self::GAMMA);
+        Self::mix(self.state)
+    }
+
+    /// Returns a random value in range [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a random value in range [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[test]
+fn gen_rng_is_deterministic() {
+    let pos = Pos::<i64>::new(-3, 7, 1 << 40);
+
+    let mut a = GenRng::new(123).with_pos(pos).with(2);
+    let mut b = GenRng::new(123).with_pos(pos).with(2);
+    let mut c = GenRng::new(123).with_pos(pos).with(3);
+
+    let a = (0..16).map(|_| a.next_u64()).collect::<Vec<_>>();
+    let b = (0..16).map(|_| b.next_u64()).collect::<Vec<_>>();
+    let c = (0..16).map(|_| c.next_u64()).collect::<Vec<_>>();
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+
+    let mut rng = GenRng::new(0);
+    let avg = (0..10000).map(|_| rng.next_f64()).sum::<f64>() / 10000.0;
+    assert!((avg - 0.5).abs() < 0.02, "avg: {}", avg);
+}
@@ -0,0 +1,70 @@
+use super::*;
+use crate::plugins::{
+    objects::components::items::rock::RockItem, world_generator::internal::biomes::DESERT_ID,
+};
+
+/// Group of stone boulders half buried in the ground with small rocks around them
+#[derive(Debug, Clone)]
+pub struct BoulderFieldStructure;
+
+impl BoulderFieldStructure {
+    pub const ID: StructureID = "boulder_field";
+
+    const RADIUS: f32 = 12.0;
+
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self)
+    }
+}
+
+impl Structure for BoulderFieldStructure {
+    fn get_id(&self) -> StructureID {
+        Self::ID
+    }
+
+    fn attempts(&self) -> usize {
+        2
+    }
+
+    fn chance(&self) -> f64 {
+        0.3
+    }
+
+    fn check_biome(&self, _biome: BiomeID) -> bool {
+        true
+    }
+
+    fn plan(&self, gen: &WorldGenerator, origin: Vec3, rng: &mut GenRng) -> StructurePlan {
+        let mut plan = StructurePlan::new(Self::ID, origin);
+
+        let id = if gen.get_biome_at(origin).get_id() == DESERT_ID {
+            VoxelId::SAND_STONE
+        } else {
+            VoxelId::STONE
+        };
+
+        let boulders = 3 + (rng.next_f32() * 5.0) as usize;
+        for _ in 0..boulders {
+            let pos = random_surface_point(gen, origin, Self::RADIUS, rng);
+            let radius = random_range(rng, 0.6, 1.6);
+
+            plan.shapes.push(VoxelShape::Sphere {
+                center: pos - Vec3::Y * radius * 0.3,
+                radius,
+                id,
+            });
+        }
+
+        let rocks = 3 + (rng.next_f32() * 6.0) as usize;
+        for _ in 0..rocks {
+            let pos = random_surface_point(gen, origin, Self::RADIUS, rng);
+            let transform = Transform::from_translation(pos + Vec3::Y * 0.1).with_rotation(
+                Quat::from_rotation_y(rng.next_f32() * std::f32::consts::TAU),
+            );
+
+            plan.objects.push(StructureObject::new(RockItem, transform));
+        }
+
+        plan
+    }
+}
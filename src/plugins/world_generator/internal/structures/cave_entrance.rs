@@ -0,0 +1,55 @@
+use super::*;
+use crate::plugins::world_generator::internal::biomes::DESERT_ID;
+
+/// Tunnel going from the surface down under the ground
+#[derive(Debug, Clone)]
+pub struct CaveEntranceStructure;
+
+impl CaveEntranceStructure {
+    pub const ID: StructureID = "cave_entrance";
+
+    const STEP: f32 = 2.5;
+
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self)
+    }
+}
+
+impl Structure for CaveEntranceStructure {
+    fn get_id(&self) -> StructureID {
+        Self::ID
+    }
+
+    fn chance(&self) -> f64 {
+        0.15
+    }
+
+    fn check_biome(&self, biome: BiomeID) -> bool {
+        // sand would fill the tunnel
+        biome != DESERT_ID
+    }
+
+    fn plan(&self, _gen: &WorldGenerator, origin: Vec3, rng: &mut GenRng) -> StructurePlan {
+        let mut plan = StructurePlan::new(Self::ID, origin);
+
+        let angle = rng.next_f32() * std::f32::consts::TAU;
+        let slope = random_range(rng, 0.4, 0.8);
+        let direction = Vec3::new(angle.cos(), -slope, angle.sin()).normalize();
+
+        let steps = 6 + (rng.next_f32() * 5.0) as usize;
+        let mut center = origin;
+
+        for _ in 0..steps {
+            plan.shapes.push(VoxelShape::Carve {
+                center,
+                radius: random_range(rng, 2.0, 3.0),
+            });
+
+            // slightly bend the tunnel
+            let bend = Vec3::new(rng.next_f32() - 0.5, 0.0, rng.next_f32() - 0.5) * 0.3;
+            center += (direction + bend).normalize() * Self::STEP;
+        }
+
+        plan
+    }
+}
@@ -0,0 +1,69 @@
+use super::*;
+use crate::plugins::{
+    objects::components::items::{branch::BranchItem, log::LogItem},
+    world_generator::internal::biomes::PLAINS_ID,
+};
+
+/// Cluster of fallen logs and branches
+#[derive(Debug, Clone)]
+pub struct FallenLogsStructure;
+
+impl FallenLogsStructure {
+    pub const ID: StructureID = "fallen_logs";
+
+    const RADIUS: f32 = 6.0;
+
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self)
+    }
+}
+
+impl Structure for FallenLogsStructure {
+    fn get_id(&self) -> StructureID {
+        Self::ID
+    }
+
+    fn attempts(&self) -> usize {
+        2
+    }
+
+    fn chance(&self) -> f64 {
+        0.25
+    }
+
+    fn check_biome(&self, biome: BiomeID) -> bool {
+        biome == PLAINS_ID
+    }
+
+    fn plan(&self, gen: &WorldGenerator, origin: Vec3, rng: &mut GenRng) -> StructurePlan {
+        let mut plan = StructurePlan::new(Self::ID, origin);
+
+        // logs of the cluster fell in roughly the same direction
+        let direction = rng.next_f32() * std::f32::consts::TAU;
+
+        let logs = 2 + (rng.next_f32() * 4.0) as usize;
+        for _ in 0..logs {
+            let pos = random_surface_point(gen, origin, Self::RADIUS, rng);
+            let rotation = Quat::from_rotation_y(direction + random_range(rng, -0.4, 0.4))
+                * Quat::from_rotation_x(std::f32::consts::FRAC_PI_2);
+
+            plan.objects.push(StructureObject::new(
+                LogItem,
+                Transform::from_translation(pos + Vec3::Y * 0.3).with_rotation(rotation),
+            ));
+        }
+
+        let branches = 2 + (rng.next_f32() * 5.0) as usize;
+        for _ in 0..branches {
+            let pos = random_surface_point(gen, origin, Self::RADIUS, rng);
+            let rotation = Quat::from_rotation_y(rng.next_f32() * std::f32::consts::TAU);
+
+            plan.objects.push(StructureObject::new(
+                BranchItem,
+                Transform::from_translation(pos + Vec3::Y * 0.1).with_rotation(rotation),
+            ));
+        }
+
+        plan
+    }
+}
@@ -0,0 +1,249 @@
+use super::{biomes::BiomeID, random::GenRng};
+use crate::{
+    internal::{chunks::Chunk, pos::ChunkPos, voxel::voxel_types::VoxelId},
+    plugins::{
+        game_world::resources::GameWorld, inspector::components::InspectorDisabled,
+        objects::components::GameWorldObjectTrait, world_generator::resources::WorldGenerator,
+    },
+};
+use bevy::prelude::*;
+use std::{fmt::Debug, sync::Arc};
+
+pub mod boulder_field;
+pub mod cave_entrance;
+pub mod fallen_logs;
+pub mod ruins;
+
+pub type StructureID = &'static str;
+
+/// Feature of the world that can be larger than a chunk
+///
+/// Structures are planned per region only from the world seed and the region position,
+/// so they are the same whatever order the regions are loaded in.
+pub trait Structure: Send + Sync + Debug {
+    fn get_id(&self) -> StructureID;
+
+    /// How many times the structure will try to spawn in each region
+    fn attempts(&self) -> usize {
+        1
+    }
+
+    /// Chance of the single attempt to succeed, value between 0 and 1
+    fn chance(&self) -> f64;
+
+    /// Check if the structure can be placed in the biome
+    fn check_biome(&self, biome: BiomeID) -> bool;
+
+    /// Plan the structure at the `origin` on the surface.
+    ///
+    /// All shapes and objects should be closer than [`StructurePlan::MAX_RADIUS`] to the `origin`.
+    fn plan(&self, gen: &WorldGenerator, origin: Vec3, rng: &mut GenRng) -> StructurePlan;
+}
+
+/// Shape stamped into the voxels of the generated chunks, sizes are in meters
+#[derive(Debug, Clone, Copy)]
+pub enum VoxelShape {
+    /// Fill the sphere with voxels
+    Sphere {
+        center: Vec3,
+        radius: f32,
+        id: VoxelId,
+    },
+    /// Fill the box with voxels
+    Box { min: Vec3, max: Vec3, id: VoxelId },
+    /// Remove voxels inside of the sphere
+    Carve { center: Vec3, radius: f32 },
+}
+
+impl VoxelShape {
+    /// Extra space around the shapes used to smooth their surface
+    const MARGIN: f32 = 1.0;
+
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        match *self {
+            Self::Sphere { center, radius, .. } | Self::Carve { center, radius } => (
+                center - Vec3::splat(radius + Self::MARGIN),
+                center + Vec3::splat(radius + Self::MARGIN),
+            ),
+            Self::Box { min, max, .. } => (
+                min - Vec3::splat(Self::MARGIN),
+                max + Vec3::splat(Self::MARGIN),
+            ),
+        }
+    }
+
+    /// Signed distance to the surface of the shape, negative inside
+    fn distance(&self, pos: Vec3) -> f32 {
+        match *self {
+            Self::Sphere { center, radius, .. } | Self::Carve { center, radius } => {
+                pos.distance(center) - radius
+            }
+            Self::Box { min, max, .. } => {
+                let center = (min + max) * 0.5;
+                let half = (max - min) * 0.5;
+                let q = (pos - center).abs() - half;
+                q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+            }
+        }
+    }
+
+    /// Apply the shape to the generated voxel `value` at the `pos`
+    pub fn apply(&self, pos: Vec3, value: f64, id: VoxelId) -> (f64, VoxelId) {
+        let shape_value = WorldGenerator::normalize_value(-self.distance(pos) as f64);
+
+        match *self {
+            Self::Sphere { id: shape_id, .. } | Self::Box { id: shape_id, .. } => {
+                if shape_value > value {
+                    (shape_value, shape_id)
+                } else {
+                    (value, id)
+                }
+            }
+            Self::Carve { .. } => (value.min(-shape_value), id),
+        }
+    }
+}
+
+/// Object placed by the structure
+#[derive(Debug, Clone)]
+pub struct StructureObject {
+    pub object: Arc<dyn GameWorldObjectTrait>,
+    pub transform: Transform,
+}
+
+impl StructureObject {
+    pub fn new(object: impl GameWorldObjectTrait, transform: Transform) -> Self {
+        Self {
+            object: Arc::new(object),
+            transform,
+        }
+    }
+}
+
+/// Planned structure in world coordinates
+#[derive(Debug, Clone)]
+pub struct StructurePlan {
+    pub id: StructureID,
+    pub origin: Vec3,
+    pub shapes: Vec<VoxelShape>,
+    pub objects: Vec<StructureObject>,
+}
+
+impl StructurePlan {
+    /// Max distance from the origin to the structure parts.
+    ///
+    /// Should be less than region size, so only neighboring regions should be checked.
+    pub const MAX_RADIUS: f32 = 32.0;
+
+    pub fn new(id: StructureID, origin: Vec3) -> Self {
+        Self {
+            id,
+            origin,
+            shapes: Vec::new(),
+            objects: Vec::new(),
+        }
+    }
+
+    /// Bounding box of all the shapes and objects of the structure
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        let shapes = self.shapes.iter().map(|shape| shape.bounds());
+        let objects = self
+            .objects
+            .iter()
+            .map(|object| (object.transform.translation, object.transform.translation));
+
+        shapes
+            .chain(objects)
+            .fold((self.origin, self.origin), |(min, max), (a, b)| {
+                (min.min(a), max.max(b))
+            })
+    }
+
+    pub fn intersects(&self, min: Vec3, max: Vec3) -> bool {
+        let (a, b) = self.bounds();
+        a.cmple(max).all() && b.cmpge(min).all()
+    }
+}
+
+/// Get bounds of the region in meters
+pub fn region_bounds(region_pos: ChunkPos) -> (Vec3, Vec3) {
+    let min = GameWorld::region_pos_to_translation(region_pos);
+    (
+        min,
+        min + Vec3::splat(GameWorld::REGION_SIZE as f32 * Chunk::REAL_SIZE),
+    )
+}
+
+/// Spawn objects of all structures placed in the region.
+///
+/// Structure can intersect multiple regions, each region spawns only objects inside of it.
+pub fn spawn_structures_objects(
+    gen: &WorldGenerator,
+    region_pos: ChunkPos,
+    commands: &mut Commands,
+) -> usize {
+    let (min, max) = region_bounds(region_pos);
+    let mut count = 0;
+
+    for plan in gen.get_structures_in(min, max) {
+        for object in plan.objects {
+            let translation = object.transform.translation;
+            if translation.cmplt(min).any() || translation.cmpge(max).any() {
+                continue;
+            }
+
+            let spawner = object.object.create_spawner(object.transform);
+            let name = Name::new(format!("object_spawner:{}", spawner.id()));
+
+            commands.spawn((spawner, InspectorDisabled, name));
+            count += 1;
+        }
+    }
+
+    count
+}
+
+/// Random point in the horizontal circle of `radius` around the `center`, placed on the surface
+pub(self) fn random_surface_point(
+    gen: &WorldGenerator,
+    center: Vec3,
+    radius: f32,
+    rng: &mut GenRng,
+) -> Vec3 {
+    let angle = rng.next_f32() * std::f32::consts::TAU;
+    let dist = rng.next_f32().sqrt() * radius;
+
+    let x = center.x + angle.cos() * dist;
+    let z = center.z + angle.sin() * dist;
+
+    Vec3::new(x, gen.get_surface_height(x as f64, z as f64) as f32, z)
+}
+
+/// Random value in range [min, max)
+pub(self) fn random_range(rng: &mut GenRng, min: f32, max: f32) -> f32 {
+    min + rng.next_f32() * (max - min)
+}
+
+#[test]
+fn structures_are_deterministic() {
+    let gen = WorldGenerator::new(123);
+
+    for i in 0..27 {
+        let region_pos = ChunkPos::from_index(i, 3) - ChunkPos::new(1, 1, 1);
+
+        let plans = gen.get_region_structures(region_pos);
+        let again = WorldGenerator::new(123).get_region_structures(region_pos);
+        assert_eq!(plans.len(), again.len());
+        assert!(Arc::ptr_eq(&plans, &gen.get_region_structures(region_pos)));
+
+        for (plan, again) in plans.iter().zip(again.iter()) {
+            assert_eq!(plan.id, again.id);
+            assert_eq!(plan.origin, again.origin);
+
+            let (min, max) = plan.bounds();
+            let radius = Vec3::splat(StructurePlan::MAX_RADIUS);
+            assert!(min.cmpge(plan.origin - radius).all(), "{} is too big", plan.id);
+            assert!(max.cmple(plan.origin + radius).all(), "{} is too big", plan.id);
+        }
+    }
+}
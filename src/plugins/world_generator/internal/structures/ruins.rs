@@ -0,0 +1,85 @@
+use super::*;
+use crate::plugins::objects::components::items::rock::RockItem;
+
+/// Broken stone walls of an old square building
+#[derive(Debug, Clone)]
+pub struct RuinsStructure;
+
+impl RuinsStructure {
+    pub const ID: StructureID = "ruins";
+
+    const WALL_THICKNESS: f32 = 0.75;
+    const SEGMENT_LENGTH: f32 = 2.0;
+
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self)
+    }
+}
+
+impl Structure for RuinsStructure {
+    fn get_id(&self) -> StructureID {
+        Self::ID
+    }
+
+    fn chance(&self) -> f64 {
+        0.1
+    }
+
+    fn check_biome(&self, _biome: BiomeID) -> bool {
+        true
+    }
+
+    fn plan(&self, gen: &WorldGenerator, origin: Vec3, rng: &mut GenRng) -> StructurePlan {
+        let mut plan = StructurePlan::new(Self::ID, origin);
+
+        let half_size = random_range(rng, 3.0, 5.0);
+        let segments = (half_size * 2.0 / Self::SEGMENT_LENGTH).ceil() as usize;
+
+        // walls along x axis at -z and +z sides and along z axis at -x and +x sides
+        for side in 0..4 {
+            let sign = if side % 2 == 0 { -1.0 } else { 1.0 };
+
+            for i in 0..segments {
+                // some parts of the walls are completely destroyed
+                if rng.next_f32() < 0.25 {
+                    continue;
+                }
+
+                let along = -half_size + i as f32 * Self::SEGMENT_LENGTH;
+                let (x, z) = if side < 2 {
+                    (along + Self::SEGMENT_LENGTH * 0.5, sign * half_size)
+                } else {
+                    (sign * half_size, along + Self::SEGMENT_LENGTH * 0.5)
+                };
+
+                let pos = origin + Vec3::new(x, 0.0, z);
+                let ground = gen.get_surface_height(pos.x as f64, pos.z as f64) as f32;
+                let height = random_range(rng, 0.5, 3.0);
+
+                let half = if side < 2 {
+                    Vec3::new(Self::SEGMENT_LENGTH * 0.5, 0.0, Self::WALL_THICKNESS * 0.5)
+                } else {
+                    Vec3::new(Self::WALL_THICKNESS * 0.5, 0.0, Self::SEGMENT_LENGTH * 0.5)
+                };
+
+                plan.shapes.push(VoxelShape::Box {
+                    // walls start under the ground to not float on slopes
+                    min: Vec3::new(pos.x - half.x, ground - 1.0, pos.z - half.z),
+                    max: Vec3::new(pos.x + half.x, ground + height, pos.z + half.z),
+                    id: VoxelId::STONE,
+                });
+            }
+        }
+
+        let rocks = 2 + (rng.next_f32() * 4.0) as usize;
+        for _ in 0..rocks {
+            let pos = random_surface_point(gen, origin, half_size + 2.0, rng);
+            plan.objects.push(StructureObject::new(
+                RockItem,
+                Transform::from_translation(pos + Vec3::Y * 0.1),
+            ));
+        }
+
+        plan
+    }
+}
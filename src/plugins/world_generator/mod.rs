@@ -1,13 +1,15 @@
-use self::resources::WorldGenerator;
+use self::{resources::WorldGenerator, systems::load_biomes::load_biomes_system};
 use bevy::prelude::*;
 
 pub mod internal;
 pub mod resources;
+mod systems;
 
 pub struct WorldGeneratorPlugin;
 
 impl Plugin for WorldGeneratorPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(WorldGenerator::default());
+        app.insert_resource(WorldGenerator::default())
+            .add_startup_system(load_biomes_system);
     }
 }
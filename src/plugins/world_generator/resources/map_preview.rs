@@ -0,0 +1,148 @@
+use super::WorldGenerator;
+use crate::internal::{chunks::Chunk, color::Color};
+use bevy::prelude::*;
+use std::{fmt, path::Path, str::FromStr};
+use strum_macros::EnumIter;
+
+/// Value of the world generator shown on the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter)]
+pub enum MapMode {
+    #[default]
+    Biome,
+    Temperature,
+    Humidity,
+    Elevation,
+    Height,
+}
+
+impl MapMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Biome => "biome",
+            Self::Temperature => "temperature",
+            Self::Humidity => "humidity",
+            Self::Elevation => "elevation",
+            Self::Height => "height",
+        }
+    }
+}
+
+impl fmt::Display for MapMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for MapMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "biome" => Ok(Self::Biome),
+            "temperature" => Ok(Self::Temperature),
+            "humidity" => Ok(Self::Humidity),
+            "elevation" => Ok(Self::Elevation),
+            "height" => Ok(Self::Height),
+            _ => Err(format!("unknown map mode {}", s)),
+        }
+    }
+}
+
+/// Top down image of the world, centered at the origin
+#[derive(Debug, Clone)]
+pub struct MapPreview {
+    pub size: usize,
+    /// Colors of the pixels row by row, rows go along z axis
+    pub pixels: Vec<Color>,
+}
+
+impl MapPreview {
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|c| c.as_rgba_u32().to_le_bytes())
+            .collect()
+    }
+
+    pub fn save_png(&self, path: &Path) -> image::ImageResult<()> {
+        image::save_buffer(
+            path,
+            &self.to_rgba8(),
+            self.size as u32,
+            self.size as u32,
+            image::ColorType::Rgba8,
+        )
+    }
+}
+
+impl WorldGenerator {
+    /// Height of the landscape mapped to the darkest and the brightest pixel in meters
+    const MAP_HEIGHT_RANGE: (f64, f64) = (0.0, 40.0);
+
+    /// Render `size`x`size` image of the area around the origin, each pixel covers `meters_per_pixel`
+    pub fn render_map(&self, mode: MapMode, size: usize, meters_per_pixel: f32) -> MapPreview {
+        let half = size as f32 * meters_per_pixel * 0.5;
+
+        let pixels = (0..size * size)
+            .map(|i| {
+                let x = (i % size) as f32 * meters_per_pixel - half;
+                let z = (i / size) as f32 * meters_per_pixel - half;
+
+                self.get_map_color(mode, x, z)
+            })
+            .collect();
+
+        MapPreview { size, pixels }
+    }
+
+    fn get_map_color(&self, mode: MapMode, x: f32, z: f32) -> Color {
+        let pos = Chunk::vec_to_chunk_pos(Vec3::new(x, 0.0, z));
+
+        match mode {
+            // surface voxel is the most recognizable color of the biome
+            MapMode::Biome => self.get_generate_voxel_inp(pos).first_layer_id.get_color(),
+            MapMode::Temperature => {
+                let params = self.params();
+                let t = (self.get_temperature(pos) - params.min_temp)
+                    / (params.max_temp - params.min_temp);
+                Self::map_gradient(Color::BLUE, Color::RED, t)
+            }
+            MapMode::Humidity => Self::map_gradient(
+                Color::rgb_u8(150, 110, 60),
+                Color::rgb_u8(30, 90, 200),
+                self.get_humidity(pos),
+            ),
+            MapMode::Elevation => Self::map_gradient(
+                Color::BLACK,
+                Color::WHITE,
+                self.get_elevation(pos.x as f64, pos.z as f64) * 0.5,
+            ),
+            MapMode::Height => {
+                let (min, max) = Self::MAP_HEIGHT_RANGE;
+                let height = self.get_surface_height(x as f64, z as f64);
+                Self::map_gradient(Color::BLACK, Color::WHITE, (height - min) / (max - min))
+            }
+        }
+    }
+
+    fn map_gradient(from: Color, to: Color, t: f64) -> Color {
+        let t = t.clamp(0.0, 1.0) as f32;
+        Color::rgb(
+            from.r() + (to.r() - from.r()) * t,
+            from.g() + (to.g() - from.g()) * t,
+            from.b() + (to.b() - from.b()) * t,
+        )
+    }
+}
+
+#[test]
+fn render_map_is_deterministic() {
+    let gen = WorldGenerator::new(123);
+
+    let a = gen.render_map(MapMode::Biome, 16, 64.0);
+    let b = gen.render_map(MapMode::Biome, 16, 64.0);
+
+    assert_eq!(a.pixels.len(), 16 * 16);
+    assert_eq!(a.to_rgba8().len(), 16 * 16 * 4);
+    assert_eq!(a.to_rgba8(), b.to_rgba8());
+}
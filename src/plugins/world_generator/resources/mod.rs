@@ -1,6 +1,15 @@
-use super::internal::biomes::{
-    desert::DesertBiome, plains::PlainsBiome, tundra::TundraBiome, Biome, BiomeCheckInput,
-    ChunkBiomes,
+use super::internal::{
+    biomes::{
+        caves::{data::DataCaveBiome, CaveBiome, CaveBiomeCheckInput},
+        data::{BiomeLoadError, BiomeSource, DataBiome},
+        Biome, BiomeCheckInput, ChunkBiomes,
+    },
+    random::GenRng,
+    structures::{
+        boulder_field::BoulderFieldStructure, cave_entrance::CaveEntranceStructure,
+        fallen_logs::FallenLogsStructure, region_bounds, ruins::RuinsStructure, Structure,
+        StructurePlan, VoxelShape,
+    },
 };
 use crate::{
     internal::{
@@ -9,9 +18,11 @@ use crate::{
         pos::{ChunkPos, GlobalVoxelPos, VoxelPos},
         voxel::{voxel_types::VoxelId, Voxel},
     },
-    plugins::game_world::resources::GameWorld,
+    plugins::{
+        game_world::resources::GameWorld, objects::resources::objects_registry::ObjectsRegistry,
+    },
 };
-use bevy::prelude::*;
+use bevy::{asset::FileAssetIo, prelude::*, utils::HashMap};
 use bevy_inspector_egui::InspectorOptions;
 use bevy_reflect::Reflect;
 use lerp::Lerp;
@@ -20,103 +31,613 @@ use noise::{
     NoiseFn, OpenSimplex, Perlin,
 };
 use num_traits::Pow;
+use serde::Deserialize;
 use std::{
     collections::LinkedList,
     f64::consts::{E, PI},
-    slice::from_raw_parts,
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+pub mod map_preview;
+pub mod noise_graph;
+pub mod settings;
+
+use self::{
+    noise_graph::{BiomeKey, BiomeWeights, NoiseContext, NoiseNode},
+    settings::{GeneratorParams, GeneratorSettings},
 };
 
-pub type WorldSeed = u32;
+pub type WorldSeed = u64;
 pub type ObjectGeneratorID = usize;
 
+/// Structure plans of the regions by region position, shared by clones of the generator
+type StructurePlansCache = Arc<Mutex<HashMap<ChunkPos, Arc<Vec<StructurePlan>>>>>;
+
 #[derive(Resource, Debug, Clone, Reflect, InspectorOptions)]
 #[reflect(Resource)]
 pub struct WorldGenerator {
     seed: WorldSeed,
+    settings: GeneratorSettings,
     #[reflect(ignore)]
     simplex: OpenSimplex,
     #[reflect(ignore)]
     perlin: Perlin,
+    /// Hasher of the objects placement before [`GeneratorSettings::V2`]
+    #[reflect(ignore)]
+    hasher: PermutationTable,
     #[reflect(ignore)]
     biomes: LinkedList<Arc<dyn Biome>>,
+    /// Biome definitions of the new worlds, built-in ones replaced by the loaded from the assets
     #[reflect(ignore)]
-    hasher: PermutationTable,
+    asset_biomes: Vec<BiomeSource>,
+    /// Cave biome definitions of the new worlds, like [`Self::asset_biomes`]
+    #[reflect(ignore)]
+    asset_cave_biomes: Vec<BiomeSource>,
+    /// Ids of all registered biomes, indexed by [`BiomeKey`]
+    #[reflect(ignore)]
+    biome_keys: Vec<BiomeID>,
+    /// Terrain graphs of the biomes, indexed by [`BiomeKey`]
+    #[reflect(ignore)]
+    biome_terrains: Vec<Option<NoiseNode>>,
+    /// Terrain graph of the biomes without their own graph
+    #[reflect(ignore)]
+    terrain: NoiseNode,
+    #[reflect(ignore)]
+    cave_biomes: Vec<Arc<dyn CaveBiome>>,
+    #[reflect(ignore)]
+    structures: Vec<Arc<dyn Structure>>,
+    #[reflect(ignore)]
+    structure_plans: StructurePlansCache,
 }
 
 impl WorldGenerator {
-    const LANDSCAPE_OCTAVES: usize = 4;
-    const SCALE: f64 = 0.045;
-    const LANDSCAPE_SCALE: f64 = 0.01;
-    const CAVE_SCALE: f64 = 1.0 / 50.0;
-    const CAVE_Y_SCALE: f64 = 4.0;
-
-    const COLOR_RANDOM_SCALE: f64 = 0.1;
-    const TEMP_NOISE_SCALE: f64 = 0.01;
-    const HUMIDITY_NOISE_SCALE: f64 = 0.01;
-    const MOUNTAINOUSNESS_NOISE_SCALE: f64 = 0.01;
-
-    /// Min temperature in celsius
-    const MIN_TEMP: f64 = -70.0;
-    /// Max temperature in celsius
-    const MAX_TEMP: f64 = 100.0;
+    /// Directory with biome definitions relative to the assets base path
+    pub const BIOMES_DIR: &str = "assets/biomes";
+
+    /// Max number of biomes blended at one chunk
+    pub const MAX_BLENDED_BIOMES: usize = 3;
+
+    /// Scale of the noise splitting the underground into cave biomes, per chunk
+    const CAVE_BIOME_NOISE_SCALE: f64 = 0.05;
+    /// 4th coordinate of the cave biome noise, so it doesn't follow the ore deposits
+    const CAVE_BIOME_NOISE_LAYER: f64 = -100.0;
 
+    /// Max count of regions with cached structure plans, the cache is cleared when it is full
+    const MAX_CACHED_STRUCTURE_REGIONS: usize = 4096;
+
+    /// Create generator of the current version
     pub fn new(seed: WorldSeed) -> Self {
+        Self::with_settings(seed, GeneratorSettings::current())
+    }
+
+    /// Create generator with settings of the existing world
+    pub fn with_settings(seed: WorldSeed, settings: GeneratorSettings) -> Self {
+        let noise_seed = settings.noise_seed(seed);
+
         let mut g = Self {
             seed,
-            simplex: OpenSimplex::new(seed),
-            perlin: Perlin::new(seed),
+            terrain: Self::world_terrain(&settings),
+            settings,
+            simplex: OpenSimplex::new(noise_seed),
+            perlin: Perlin::new(noise_seed),
+            hasher: PermutationTable::new(noise_seed),
             biomes: LinkedList::new(),
-            hasher: PermutationTable::new(seed),
+            asset_biomes: BiomeSource::builtin(),
+            asset_cave_biomes: DataCaveBiome::builtin_sources(),
+            biome_keys: Vec::new(),
+            biome_terrains: Vec::new(),
+            cave_biomes: Vec::new(),
+            structures: Vec::new(),
+            structure_plans: StructurePlansCache::default(),
         };
 
-        // Register plains biome first, so it will be checked last and used as default
-        g.register_biome(PlainsBiome::new());
-        g.register_biome(DesertBiome::new());
-        g.register_biome(TundraBiome::new());
+        g.reset_biomes();
+
+        g.register_structure(BoulderFieldStructure::new());
+        g.register_structure(FallenLogsStructure::new());
+        g.register_structure(CaveEntranceStructure::new());
+        g.register_structure(RuinsStructure::new());
 
         g
     }
 
     /// Adds biome to the world generator (All biomes should be registered before InGame stage)
     ///
-    /// Biomes are checked in the reverse order they were added,
-    /// so the last added biome will be checked first and if it doesn't match the chunk,
-    /// the second last will be checked and so on
+    /// Biomes are checked from the highest [`Biome::priority`] to the lowest,
+    /// biomes with the same priority are checked in the reverse order they were added.
     ///
-    /// If no biome matches the chunk, the default biome will be used ([`PlainsBiome`])
+    /// If no biome matches the chunk, the last checked one is used as default (plains)
+    ///
+    /// If biome with the same id is already registered, it is replaced and ordered by its new priority,
+    /// [`BiomeKey`] of the id is kept
     pub fn register_biome(&mut self, biome: Arc<dyn Biome>) {
-        self.biomes.push_front(biome);
+        let key = self.get_biome_key(biome.get_id()).unwrap_or_else(|| {
+            self.biome_keys.push(biome.get_id());
+            self.biome_terrains.push(None);
+            BiomeKey::new(self.biome_keys.len() - 1)
+        });
+        self.biome_terrains[key.index()] = biome.get_terrain();
+
+        // structures are placed by biomes, clones of the generator keep the old plans
+        self.structure_plans = StructurePlansCache::default();
+
+        let mut biomes = std::mem::take(&mut self.biomes)
+            .into_iter()
+            .filter(|b| b.get_id() != biome.get_id())
+            .collect::<Vec<_>>();
+
+        let index = biomes
+            .iter()
+            .position(|b| b.priority() <= biome.priority())
+            .unwrap_or(biomes.len());
+        biomes.insert(index, biome);
+
+        self.biomes = biomes.into_iter().collect();
     }
 
-    pub fn get_biome(&self, pos: ChunkPos) -> Arc<dyn Biome> {
-        let inp = BiomeCheckInput {
+    pub fn get_biome_key(&self, id: &str) -> Option<BiomeKey> {
+        self.biome_keys
+            .iter()
+            .position(|key| *key == id)
+            .map(BiomeKey::new)
+    }
+
+    pub fn get_biome_id(&self, key: BiomeKey) -> Option<BiomeID> {
+        self.biome_keys.get(key.index()).copied()
+    }
+
+    /// Terrain graph of the biomes without their own graph (see [`Biome::get_terrain`])
+    pub fn terrain(&self) -> &NoiseNode {
+        &self.terrain
+    }
+
+    /// Replace the default terrain graph, should be called after [`Self::set_world`]
+    ///
+    /// Graph is pinned in the settings (see [`GeneratorSettings::terrain`]), so it is saved with the world.
+    pub fn set_terrain(&mut self, terrain: NoiseNode) -> Result<(), serde_json::Error> {
+        self.settings.terrain = Some(serde_json::to_string(&terrain)?);
+        self.terrain = terrain;
+
+        Ok(())
+    }
+
+    /// Terrain graph pinned in the `settings` or the default one of their parameters
+    fn world_terrain(settings: &GeneratorSettings) -> NoiseNode {
+        settings
+            .terrain
+            .as_ref()
+            .and_then(|terrain| {
+                serde_json::from_str(terrain)
+                    .map_err(|err| {
+                        error!("Invalid terrain of the world, using default one: {}", err)
+                    })
+                    .ok()
+            })
+            .unwrap_or_else(|| NoiseNode::default_terrain(&settings.params))
+    }
+
+    /// Adds underground biome to the world generator (All cave biomes should be registered before InGame stage)
+    ///
+    /// Cave biomes are checked in the order they were added, if none matches the chunk,
+    /// caves of the surface biome are used
+    pub fn register_cave_biome(&mut self, biome: Arc<dyn CaveBiome>) {
+        self.cave_biomes.push(biome);
+    }
+
+    /// Adds structure to the world generator (All structures should be registered before InGame stage)
+    ///
+    /// Placement of the structure depends only on its id, so the order of registration doesn't matter
+    pub fn register_structure(&mut self, structure: Arc<dyn Structure>) {
+        self.structures.push(structure);
+        self.structure_plans = StructurePlansCache::default();
+    }
+
+    /// Default directory with biome definitions
+    pub fn biomes_dir() -> PathBuf {
+        FileAssetIo::get_base_path().join(Self::BIOMES_DIR)
+    }
+
+    /// Load biomes defined in the `dir` (see [`DataBiome`]) and cave biomes defined in its
+    /// [`DataCaveBiome::DIR`] subdirectory (see [`DataCaveBiome`]) for the new worlds
+    ///
+    /// Built-in biomes with the same file name are replaced by the loaded ones.
+    /// Worlds with pinned biomes (see [`GeneratorSettings::biomes`]) keep them.
+    /// Returns number of loaded biomes.
+    pub fn load_biomes(
+        &mut self,
+        dir: &Path,
+        registry: &ObjectsRegistry,
+    ) -> Result<usize, BiomeLoadError> {
+        let sources = BiomeSource::read_dir(dir)?;
+        let mut count = DataBiome::load_sources(&sources, registry)?.len();
+
+        let cave_dir = dir.join(DataCaveBiome::DIR);
+        let cave_sources = match cave_dir.is_dir() {
+            true => BiomeSource::read_dir(&cave_dir)?,
+            false => Vec::new(),
+        };
+        count += DataCaveBiome::load_sources(&cave_sources, registry)?.len();
+
+        BiomeSource::merge(&mut self.asset_biomes, sources);
+        BiomeSource::merge(&mut self.asset_cave_biomes, cave_sources);
+        self.reset_biomes();
+
+        Ok(count)
+    }
+
+    /// Biome definitions of the world: pinned in its settings or the defaults of its version
+    fn world_biome_sources(&self) -> Vec<BiomeSource> {
+        if !self.settings.biomes.is_empty() {
+            self.settings.biomes.clone()
+        } else if self.settings.version < GeneratorSettings::V2 {
+            BiomeSource::builtin()
+        } else {
+            self.asset_biomes.clone()
+        }
+    }
+
+    /// Cave biome definitions of the world: pinned in its settings or the defaults of its version
+    fn world_cave_biome_sources(&self) -> Vec<BiomeSource> {
+        if self.settings.version < GeneratorSettings::V4 {
+            Vec::new()
+        } else if !self.settings.cave_biomes.is_empty() {
+            self.settings.cave_biomes.clone()
+        } else {
+            self.asset_cave_biomes.clone()
+        }
+    }
+
+    /// Replace registered biomes and cave biomes with the ones of the world
+    fn reset_biomes(&mut self) {
+        let registry = ObjectsRegistry::new();
+        let biomes = DataBiome::load_sources(&self.world_biome_sources(), &registry)
+            .unwrap_or_else(|err| {
+                error!(
+                    "Failed to load biomes of the world, using built-in ones: {}",
+                    err
+                );
+                DataBiome::builtin(&registry)
+            });
+
+        self.biomes = LinkedList::new();
+        self.biome_terrains
+            .iter_mut()
+            .for_each(|terrain| *terrain = None);
+
+        for biome in biomes {
+            self.register_biome(Arc::new(biome));
+        }
+
+        let cave_biomes = DataCaveBiome::load_sources(&self.world_cave_biome_sources(), &registry)
+            .unwrap_or_else(|err| {
+                error!(
+                    "Failed to load cave biomes of the world, using built-in ones: {}",
+                    err
+                );
+                DataCaveBiome::builtin(&registry)
+            });
+
+        self.cave_biomes = Vec::new();
+        for biome in cave_biomes {
+            self.register_cave_biome(Arc::new(biome));
+        }
+    }
+
+    /// Settings of the world with its biome definitions pinned, they should be saved with the world
+    pub fn pinned_settings(&self) -> GeneratorSettings {
+        GeneratorSettings {
+            biomes: self.world_biome_sources(),
+            cave_biomes: self.world_cave_biome_sources(),
+            ..self.settings.clone()
+        }
+    }
+
+    fn get_biome_check_input(&self, pos: ChunkPos) -> BiomeCheckInput {
+        BiomeCheckInput {
             temperature: self.get_temperature(pos),
             humidity: self.get_humidity(pos),
             elevation: self.get_elevation(pos.x as f64, pos.z as f64),
-        };
+        }
+    }
+
+    /// Get biomes blended at the chunk with their weights, sorted by weight.
+    ///
+    /// Each biome takes its [`Biome::suitability`] share of the weight left by the biomes
+    /// checked before it, the default biome takes the rest.
+    /// Only [`Self::MAX_BLENDED_BIOMES`] heaviest biomes are kept, weights sum up to 1.
+    ///
+    /// Before [`GeneratorSettings::V3`] only the first matching biome is used.
+    pub fn get_biome_weights(&self, pos: ChunkPos) -> Vec<(Arc<dyn Biome>, f64)> {
+        let inp = self.get_biome_check_input(pos);
+        let default = self.biomes.back().unwrap();
+
+        if self.settings.version < GeneratorSettings::V3 {
+            let biome = self
+                .biomes
+                .iter()
+                .find(|b| b.check_pos(self, pos, inp))
+                .unwrap_or(default);
+
+            return vec![(biome.clone(), 1.0)];
+        }
+
+        let mut remaining = 1.0;
+        let mut weights: Vec<(Arc<dyn Biome>, f64)> = Vec::new();
+
+        for biome in self.biomes.iter() {
+            let weight = biome.suitability(self, pos, inp).clamp(0.0, 1.0) * remaining;
+
+            if weight > 0.0 {
+                weights.push((biome.clone(), weight));
+                remaining -= weight;
+            }
+
+            if remaining <= 0.0 {
+                break;
+            }
+        }
+
+        if remaining > 0.0 {
+            match weights
+                .iter_mut()
+                .find(|(b, _)| b.get_id() == default.get_id())
+            {
+                Some((_, weight)) => *weight += remaining,
+                None => weights.push((default.clone(), remaining)),
+            }
+        }
+
+        // stable sort keeps the check order for the same weights
+        weights.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        weights.truncate(Self::MAX_BLENDED_BIOMES);
+
+        let sum = weights.iter().map(|(_, weight)| weight).sum::<f64>();
+        weights.iter_mut().for_each(|(_, weight)| *weight /= sum);
 
-        self.biomes
+        weights
+    }
+
+    /// Get the biome with the highest weight at the chunk
+    pub fn get_biome(&self, pos: ChunkPos) -> Arc<dyn Biome> {
+        self.get_biome_weights(pos).swap_remove(0).0
+    }
+
+    /// Weighted average of the values
+    fn blend<T: Lerp<f32> + Copy>(values: impl Iterator<Item = (T, f64)>) -> Option<T> {
+        let mut sum = 0.0;
+
+        values.fold(None, |acc, (value, weight)| {
+            sum += weight;
+            Some(match acc {
+                Some(acc) => acc.lerp(value, (weight / sum) as f32),
+                None => value,
+            })
+        })
+    }
+
+    /// Landscape input of the blended biomes at the chunk
+    pub fn get_landscape_height_inp(&self, pos: ChunkPos) -> LandscapeHeightInp {
+        let weights = self.get_biome_weights(pos);
+
+        Self::blend(
+            weights
+                .iter()
+                .map(|(biome, weight)| (biome.get_landscape_height_inp(self, pos), *weight)),
+        )
+        .unwrap()
+    }
+
+    /// Voxel input of the blended biomes at the chunk.
+    ///
+    /// Voxel ids can't be averaged, so they are taken from one of the biomes
+    /// chosen randomly with the probability of its weight.
+    pub fn get_generate_voxel_inp(&self, pos: ChunkPos) -> GenVoxelInp {
+        let weights = self.get_biome_weights(pos);
+
+        let mut result = Self::blend(
+            weights
+                .iter()
+                .map(|(biome, weight)| (biome.get_generate_voxel_inp(self, pos), *weight)),
+        )
+        .unwrap();
+
+        let mut pick = GenRng::new(self.seed)
+            .with_str("biome_ids")
+            .with_pos(pos)
+            .next_f64();
+        let (biome, _) = weights
             .iter()
-            .find(|b| b.check_pos(self, pos, inp))
-            .cloned()
-            // Default biome
-            .unwrap_or_else(|| self.biomes.back().unwrap().clone())
+            .find(|(_, weight)| {
+                pick -= weight;
+                pick < 0.0
+            })
+            .unwrap_or(&weights[0]);
+
+        let ids = biome.get_generate_voxel_inp(self, pos);
+        result.biomes = BiomeWeights::new(weights.iter().filter_map(|(biome, weight)| {
+            self.get_biome_key(biome.get_id())
+                .map(|key| (key, *weight as f32))
+        }));
+        result.first_layer_id = ids.first_layer_id;
+        result.second_layer_id = ids.second_layer_id;
+        result.rest_layers_id = ids.rest_layers_id;
+
+        if let Some(cave) = self.get_cave_biome(pos) {
+            result.cave_inp = cave.get_cave_inp();
+        }
+
+        result
+    }
+
+    /// Get underground biome of the chunk, `None` if caves of the surface biome are used
+    ///
+    /// Cave biomes are generated since [`GeneratorSettings::V4`].
+    pub fn get_cave_biome(&self, pos: ChunkPos) -> Option<Arc<dyn CaveBiome>> {
+        if self.settings.version < GeneratorSettings::V4 {
+            return None;
+        }
+
+        let center = Chunk::pos_to_translation(pos) + Vec3::splat(Chunk::REAL_SIZE * 0.5);
+        let depth = self.get_surface_height(center.x as f64, center.z as f64) - center.y as f64;
+        if depth <= 0.0 {
+            return None;
+        }
+
+        let scale = Self::CAVE_BIOME_NOISE_SCALE;
+        let inp = CaveBiomeCheckInput {
+            depth,
+            noise: self.simplex.get([
+                pos.x as f64 * scale,
+                pos.y as f64 * scale,
+                pos.z as f64 * scale,
+                Self::CAVE_BIOME_NOISE_LAYER,
+            ]),
+            temperature: self.get_temperature(pos),
+        };
+
+        self.cave_biomes.iter().find(|b| b.check(inp)).cloned()
+    }
+
+    /// Get biome at the horizontal position in meters, `pos.y` is ignored
+    pub fn get_biome_at(&self, pos: Vec3) -> Arc<dyn Biome> {
+        self.get_biome(Chunk::vec_to_chunk_pos(Vec3::new(pos.x, 0.0, pos.z)))
+    }
+
+    /// Approximate height of the landscape in meters, used to place structures.
+    ///
+    /// Unlike the generated terrain, biomes are not interpolated between chunks here.
+    pub fn get_surface_height(&self, x: f64, z: f64) -> f64 {
+        let pos = Chunk::vec_to_chunk_pos(Vec3::new(x as f32, 0.0, z as f32));
+        let inp = self.get_landscape_height_inp(pos);
+
+        self.gel_landscape_height(inp, x, z)
+    }
+
+    /// Get all structures with origin in the region.
+    ///
+    /// Plans are cached, so neighboring chunks don't plan the same structures again.
+    pub fn get_region_structures(&self, region_pos: ChunkPos) -> Arc<Vec<StructurePlan>> {
+        if let Some(plans) = self.structure_plans.lock().unwrap().get(&region_pos) {
+            return plans.clone();
+        }
+
+        // planned without the lock, so other threads are not blocked by it
+        let plans = Arc::new(self.plan_region_structures(region_pos));
+
+        let mut cache = self.structure_plans.lock().unwrap();
+        if cache.len() >= Self::MAX_CACHED_STRUCTURE_REGIONS {
+            cache.clear();
+        }
+        cache.insert(region_pos, plans.clone());
+
+        plans
+    }
+
+    fn plan_region_structures(&self, region_pos: ChunkPos) -> Vec<StructurePlan> {
+        let (min, max) = region_bounds(region_pos);
+        let mut plans = Vec::new();
+
+        for structure in self.structures.iter() {
+            let mut rng = GenRng::new(self.seed)
+                .with_str(structure.get_id())
+                .with_pos(region_pos);
+
+            for attempt in 0..structure.attempts() {
+                let chance = rng.next_f64();
+                let x = min.x + (max.x - min.x) * rng.next_f32();
+                let z = min.z + (max.z - min.z) * rng.next_f32();
+
+                if chance > structure.chance() {
+                    continue;
+                }
+
+                // structure belongs to the region containing its origin on the surface
+                let y = self.get_surface_height(x as f64, z as f64) as f32;
+                if y < min.y || y >= max.y {
+                    continue;
+                }
+
+                let origin = Vec3::new(x, y, z);
+                if !structure.check_biome(self.get_biome_at(origin).get_id()) {
+                    continue;
+                }
+
+                let mut plan_rng = rng.with(attempt as u64);
+                plans.push(structure.plan(self, origin, &mut plan_rng));
+            }
+        }
+
+        plans
+    }
+
+    /// Get all structures intersecting the box between `min` and `max` in meters
+    ///
+    /// Structures are generated since [`GeneratorSettings::V2`].
+    pub fn get_structures_in(&self, min: Vec3, max: Vec3) -> Vec<StructurePlan> {
+        if self.settings.version < GeneratorSettings::V2 {
+            return Vec::new();
+        }
+
+        let radius = Vec3::splat(StructurePlan::MAX_RADIUS);
+        let from = GameWorld::translation_to_region_pos(min - radius);
+        let to = GameWorld::translation_to_region_pos(max + radius);
+
+        let mut plans = Vec::new();
+
+        for x in from.x..=to.x {
+            for y in from.y..=to.y {
+                for z in from.z..=to.z {
+                    plans.extend(
+                        self.get_region_structures(ChunkPos::new(x, y, z))
+                            .iter()
+                            .filter(|plan| plan.intersects(min, max))
+                            .cloned(),
+                    );
+                }
+            }
+        }
+
+        plans
+    }
+
+    /// Parse seed entered by the user, non numeric seeds are hashed
+    pub fn seed_from_str(seed: &str) -> WorldSeed {
+        seed.parse()
+            .unwrap_or_else(|_| GenRng::new(0).with_str(seed).next_u64())
     }
 
     pub fn seed(&self) -> WorldSeed {
         self.seed
     }
 
-    pub fn set_seed(&mut self, seed: WorldSeed) {
+    pub fn settings(&self) -> &GeneratorSettings {
+        &self.settings
+    }
+
+    fn params(&self) -> &GeneratorParams {
+        &self.settings.params
+    }
+
+    /// Switch generator to the world with the `seed` created by the generator with `settings`.
+    ///
+    /// Registered biomes are replaced by the biomes of the world, structures are kept.
+    pub fn set_world(&mut self, seed: WorldSeed, settings: GeneratorSettings) {
+        let noise_seed = settings.noise_seed(seed);
+
         self.seed = seed;
-        self.simplex = OpenSimplex::new(seed);
-        self.perlin = Perlin::new(seed);
-        self.hasher = PermutationTable::new(seed);
+        self.terrain = Self::world_terrain(&settings);
+        self.settings = settings;
+        self.simplex = OpenSimplex::new(noise_seed);
+        self.perlin = Perlin::new(noise_seed);
+        self.hasher = PermutationTable::new(noise_seed);
+        self.structure_plans = StructurePlansCache::default();
+
+        self.reset_biomes();
     }
 
     /// Simple sigmoid like function. Bound value to (-1, 1)
-    fn normalize_value(v: f64) -> f64 {
+    pub fn normalize_value(v: f64) -> f64 {
         (2.0 / (1.0 + E.pow(-v * 2.0))) - 1.0
     }
 
@@ -126,7 +647,7 @@ impl WorldGenerator {
             pos.y as f64,
             pos.z as f64,
             (channel + 1) as f64,
-        ]) * Self::COLOR_RANDOM_SCALE) as f32
+        ]) * self.params().color_random_scale) as f32
             * value;
 
         (value + random).clamp(0.0, 1.0)
@@ -144,13 +665,14 @@ impl WorldGenerator {
         let x = pos.x as f64;
         let z = pos.z as f64;
 
-        let t = self
-            .simplex
-            .get([x * Self::TEMP_NOISE_SCALE, z * Self::TEMP_NOISE_SCALE, 0.0])
-            * 0.5
+        let t = self.simplex.get([
+            x * self.params().temp_noise_scale,
+            z * self.params().temp_noise_scale,
+            0.0,
+        ]) * 0.5
             + 0.5;
 
-        Self::MIN_TEMP.lerp(Self::MAX_TEMP, t)
+        self.params().min_temp.lerp(self.params().max_temp, t)
     }
 
     fn get_humidity(&self, pos: ChunkPos) -> f64 {
@@ -158,8 +680,8 @@ impl WorldGenerator {
         let z = pos.z as f64;
 
         self.simplex.get([
-            x * Self::HUMIDITY_NOISE_SCALE,
-            z * Self::HUMIDITY_NOISE_SCALE,
+            x * self.params().humidity_noise_scale,
+            z * self.params().humidity_noise_scale,
             1.0,
         ]) * 0.5
             + 0.5
@@ -170,18 +692,18 @@ impl WorldGenerator {
         // let z = pos.z as f64;
 
         self.simplex.get([
-            x * Self::MOUNTAINOUSNESS_NOISE_SCALE,
-            z * Self::MOUNTAINOUSNESS_NOISE_SCALE,
+            x * self.params().mountainousness_noise_scale,
+            z * self.params().mountainousness_noise_scale,
             2.0,
         ]) + 1.0
     }
 
     pub fn gel_landscape_height(&self, inp: LandscapeHeightInp, x: f64, z: f64) -> f64 {
         let mut result = 0.0;
-        let mut scale = Self::LANDSCAPE_SCALE;
+        let mut scale = self.params().landscape_scale;
         let mut height = inp.height;
 
-        for _ in 0..Self::LANDSCAPE_OCTAVES {
+        for _ in 0..self.params().landscape_octaves {
             result += (self.simplex.get([x * scale, z * scale, 0.0]) * 0.5 + 0.5) * height;
             scale *= 2.0;
             height *= 0.5;
@@ -190,65 +712,34 @@ impl WorldGenerator {
         result.pow(self.get_elevation(x, z))
     }
 
-    /// Returns a random value of type `u8` based on the input `inp`.
-    ///
-    /// # Safety
-    ///
-    /// same as [`WorldGenerator::get_random`] for `T`
-    fn get_random_u8<T>(&self, inp: *const T) -> u8 {
-        let size = std::mem::size_of::<T>();
-
-        let arr =
-            unsafe { from_raw_parts(inp as *const isize, size / std::mem::size_of::<isize>()) };
-
-        self.hasher.hash(arr) as u8
-    }
-
-    /// Returns a random value of type `R` based on the input `inp`.
-    ///
-    /// # Safety
-    ///
-    /// `R` must be a primitive type
-    ///
-    /// `T` mustn't be a reference/pointer or contain any
-    pub unsafe fn get_random<T, R>(&self, inp: *const T) -> R
-    where
-        R: Copy,
-        T: Copy,
-    {
-        let res_size = std::mem::size_of::<R>();
-
-        let inp = inp.read();
-
-        let rands = (0..res_size)
-            .map(|i| self.get_random_u8(&(inp, i)))
-            .collect::<Vec<_>>();
-
-        let rands = unsafe { from_raw_parts(rands.as_ptr() as *const R, res_size) };
-
-        rands[0]
+    /// Deterministic random generator for the object generator `id` in the chunk
+    pub fn rng(&self, chunk_pos: ChunkPos, id: ObjectGeneratorID) -> GenRng {
+        GenRng::new(self.seed).with_pos(chunk_pos).with(id as u64)
     }
 
-    /// Returns a random value between 0 and 1.
+    /// Random value between 0 and 1 used to place objects before [`GeneratorSettings::V2`].
     ///
-    /// # Safety
-    ///
-    /// same as [`WorldGenerator::get_random`] for `T`
-    pub unsafe fn get_random_f64<T>(&self, inp: *const T) -> f64
-    where
-        T: Copy,
-    {
-        self.get_random::<_, u64>(inp) as f64 / (u64::MAX as f64)
-    }
-
-    /// Returns a random value between 0 and 1.
-    pub fn get_chunk_random(
+    /// Same as the old hashing of the memory of `((chunk_pos, id, variant), byte)`,
+    /// which consisted of these 64-bit values in this order.
+    fn get_legacy_chunk_random(
         &self,
         chunk_pos: ChunkPos,
         id: ObjectGeneratorID,
         variant: usize,
     ) -> f64 {
-        unsafe { self.get_random_f64(&(chunk_pos, id, variant)) }
+        let mut bytes = [0u8; 8];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = self.hasher.hash(&[
+                chunk_pos.x as isize,
+                chunk_pos.y as isize,
+                chunk_pos.z as isize,
+                id as isize,
+                variant as isize,
+                i as isize,
+            ]) as u8;
+        }
+
+        u64::from_ne_bytes(bytes) as f64 / u64::MAX as f64
     }
 
     /// Returns the position of the object in the chunk, if there is one.
@@ -258,7 +749,8 @@ impl WorldGenerator {
     /// - `chance`: value between 0 and 1. The higher the value, the more likely the object will be generated.
     /// - `allow_air`: if true, objects can spawn in the air, otherwise the placement will be skipped for air voxels
     ///
-    /// The number is used to generate multiple objects in the same chunk.
+    /// The number is used to generate multiple objects in the same chunk,
+    /// `max_count` is the number of objects of the generator (used only before [`GeneratorSettings::V2`]).
     #[allow(clippy::too_many_arguments)]
     pub fn get_ground_object_pos(
         &self,
@@ -272,15 +764,33 @@ impl WorldGenerator {
     ) -> Option<(Vec3, f32)> {
         let chunk_offset = Chunk::pos_to_translation(chunk_pos);
 
-        let factor = self.get_chunk_random(chunk_pos, id, 3 + number * max_count) as f32;
+        let (factor, tree_x, tree_z, y_angle) = if self.settings.version < GeneratorSettings::V2 {
+            let random = |variant| self.get_legacy_chunk_random(chunk_pos, id, variant);
+            let offset = number * max_count;
+
+            (
+                random(3 + offset) as f32,
+                random(offset),
+                random(1 + offset),
+                random(2 + offset),
+            )
+        } else {
+            let mut rng = self.rng(chunk_pos, id).with(number as u64);
+
+            (
+                rng.next_f32(),
+                rng.next_f64(),
+                rng.next_f64(),
+                rng.next_f64(),
+            )
+        };
+
         if factor > chance {
             return None;
         }
 
-        let tree_x =
-            self.get_chunk_random(chunk_pos, id, number * max_count) * Chunk::REAL_SIZE as f64;
-        let tree_z =
-            self.get_chunk_random(chunk_pos, id, 1 + number * max_count) * Chunk::REAL_SIZE as f64;
+        let tree_x = tree_x * Chunk::REAL_SIZE as f64;
+        let tree_z = tree_z * Chunk::REAL_SIZE as f64;
 
         let tree_x = tree_x + chunk_offset.x as f64;
         let tree_z = tree_z + chunk_offset.z as f64;
@@ -312,7 +822,61 @@ impl WorldGenerator {
             }
         }
 
-        let y_angle = self.get_chunk_random(chunk_pos, id, 2 + number * max_count) * PI * 2.0;
+        let y_angle = y_angle * PI * 2.0;
+        Some((pos, y_angle as f32))
+    }
+
+    /// Returns the position of the object on the cave floor in the chunk, if there is one.
+    ///
+    /// Works the same way as [`Self::get_ground_object_pos`], but looks for the floor
+    /// under the surface inside of the chunk.
+    pub fn get_cave_floor_object_pos(
+        &self,
+        biomes: &ChunkBiomes,
+        chunk_pos: ChunkPos,
+        id: ObjectGeneratorID,
+        chance: f32,
+        number: usize,
+    ) -> Option<(Vec3, f32)> {
+        let chunk_offset = Chunk::pos_to_translation(chunk_pos);
+
+        let mut rng = self.rng(chunk_pos, id).with_str("cave").with(number as u64);
+
+        let factor = rng.next_f32();
+        if factor > chance {
+            return None;
+        }
+
+        let x = chunk_offset.x + rng.next_f32() * Chunk::REAL_SIZE;
+        let z = chunk_offset.z + rng.next_f32() * Chunk::REAL_SIZE;
+
+        let base = Chunk::vec_to_voxel_pos(Vec3::new(x, chunk_offset.y, z));
+        let landscape_height =
+            self.gel_landscape_height(biomes.get_landscape_height_inp(base), x as f64, z as f64);
+
+        let value_at = |y: i64| {
+            let pos = base + GlobalVoxelPos::new(0, y, 0);
+            self.generate_voxel_value(biomes.get_generate_voxel_inp(pos), landscape_height, pos)
+        };
+
+        // scan the column down from the top of the chunk to find solid voxel under the air
+        let mut above = value_at(Chunk::SIZE_I64);
+        let floor = (0..Chunk::SIZE_I64).rev().find(|y| {
+            let value = value_at(*y);
+            let found = above < 0.0 && value >= 0.0;
+            above = value;
+            found
+        })?;
+
+        let pos = Chunk::voxel_pos_to_vec(base + GlobalVoxelPos::new(0, floor + 1, 0));
+        let pos = Vec3::new(x, pos.y, z);
+
+        // air under the surface layer is not a cave
+        if pos.y as f64 > landscape_height - 2.0 {
+            return None;
+        }
+
+        let y_angle = rng.next_f64() * PI * 2.0;
         Some((pos, y_angle as f32))
     }
 
@@ -324,9 +888,9 @@ impl WorldGenerator {
         let z = pos_vec.z as f64 * Voxel::SCALE as f64;
 
         let cave = self.simplex.get([
-            x * Self::CAVE_SCALE,
-            y * Self::CAVE_SCALE * Self::CAVE_Y_SCALE,
-            z * Self::CAVE_SCALE,
+            x * self.params().cave_scale,
+            y * self.params().cave_scale * self.params().cave_y_scale,
+            z * self.params().cave_scale,
         ]) * inp.cave_factor
             - inp.cave_offset;
 
@@ -337,30 +901,67 @@ impl WorldGenerator {
         cave * cave * inp.cave_strength
     }
 
+    /// Terrain graph of the biome, the default one if the biome has no own graph
+    fn get_biome_terrain(&self, key: BiomeKey) -> &NoiseNode {
+        self.biome_terrains
+            .get(key.index())
+            .and_then(Option::as_ref)
+            .unwrap_or(&self.terrain)
+    }
+
+    /// Evaluate terrain graphs of the biomes blended at the voxel, see [`NoiseNode`]
+    ///
+    /// Values are blended by the biome weights. Biomes sharing the graph are evaluated once,
+    /// unless it depends on the biome.
     fn generate_voxel_value(
         &self,
         inp: GenVoxelInp,
         landscape_height: f64,
         pos: GlobalVoxelPos,
     ) -> f64 {
-        let bumps_scale = 1.0 / Self::SCALE;
-        let bumps_factor: f64 = inp.bumps_factor;
-
-        let pos_vec = pos.to_vec3();
-
-        let x = pos_vec.x as f64 * Voxel::SCALE as f64;
-        let y = pos_vec.y as f64 * Voxel::SCALE as f64;
-        let z = pos_vec.z as f64 * Voxel::SCALE as f64;
+        let mut groups = [(BiomeKey::NONE, 0.0); BiomeWeights::MAX_BIOMES];
+        let mut len = 0;
+
+        for (key, weight) in inp.biomes.iter() {
+            let terrain = self.get_biome_terrain(key);
+            let shared = match terrain.selects_by_biome() {
+                true => None,
+                false => groups[..len]
+                    .iter_mut()
+                    .find(|(k, _)| std::ptr::eq(self.get_biome_terrain(*k), terrain)),
+            };
+
+            match shared {
+                Some((_, w)) => *w += weight,
+                None => {
+                    groups[len] = (key, weight);
+                    len += 1;
+                }
+            }
+        }
 
-        let bumps = bumps_factor
-            * self
-                .simplex
-                .get([x * bumps_scale, y * bumps_scale, z * bumps_scale]);
-        let value = (landscape_height - y) + bumps;
+        let eval = |biome: BiomeKey| {
+            let ctx = NoiseContext {
+                gen: self,
+                inp: &inp,
+                biome,
+                landscape_height,
+                voxel_pos: pos,
+            };
 
-        let value = Self::normalize_value(value);
+            self.get_biome_terrain(biome)
+                .eval(&ctx, NoiseNode::voxel_pos_to_meters(pos))
+        };
 
-        value - self.get_caves(inp.cave_inp, pos)
+        // single graph is returned as is, so blending doesn't change terrain of the same graphs
+        match &groups[..len] {
+            [] => eval(BiomeKey::NONE),
+            [(key, _)] => eval(*key),
+            groups => groups
+                .iter()
+                .map(|(key, weight)| eval(*key) * *weight as f64)
+                .sum(),
+        }
     }
 
     fn generate_voxel(
@@ -369,11 +970,12 @@ impl WorldGenerator {
         landscape_height: f64,
         pos: GlobalVoxelPos,
         scale: usize,
+        shapes: &[VoxelShape],
     ) -> Voxel {
         let value = self.generate_voxel_value(inp, landscape_height, pos);
 
         let dirt_start = scale as f64 * Voxel::SCALE as f64;
-        let stone_start = 32.0;
+        let stone_start = self.params().stone_depth;
 
         let current_depth = pos.y as f64 * Voxel::SCALE as f64 - landscape_height;
         let id = match current_depth {
@@ -383,9 +985,68 @@ impl WorldGenerator {
             _ => inp.first_layer_id,
         };
 
+        // walls of the caves, the surface layer is kept
+        let id = if self.settings.version >= GeneratorSettings::V4
+            && current_depth < -dirt_start
+            && self.get_caves(inp.cave_inp, pos) > 0.0
+        {
+            inp.cave_inp.wall_id
+        } else {
+            id
+        };
+
+        let id = self.get_ore(inp.ores, -current_depth, pos).unwrap_or(id);
+
+        let pos_vec = pos.to_vec3() * Voxel::SCALE;
+        let (value, id) = shapes.iter().fold((value, id), |(value, id), shape| {
+            shape.apply(pos_vec, value, id)
+        });
+
         Voxel::new(value as f32, id)
     }
 
+    /// Returns ore at the given position if there is a deposit
+    ///
+    /// `depth`: depth of the voxel under the surface in meters
+    ///
+    /// Ores are generated since [`GeneratorSettings::V2`].
+    fn get_ore(&self, inp: GenOresInp, depth: f64, pos: GlobalVoxelPos) -> Option<VoxelId> {
+        if self.settings.version < GeneratorSettings::V2 {
+            return None;
+        }
+
+        let pos_vec = pos.to_vec3();
+
+        let x = pos_vec.x as f64 * Voxel::SCALE as f64;
+        let y = pos_vec.y as f64 * Voxel::SCALE as f64;
+        let z = pos_vec.z as f64 * Voxel::SCALE as f64;
+
+        for (i, (id, shape, size, ore)) in inp.deposits().into_iter().enumerate() {
+            if ore.richness <= 0.0 || depth < ore.min_depth || depth > ore.max_depth {
+                continue;
+            }
+
+            // use separate noise slices for each ore so deposits don't follow each other
+            let layer = (i as f64 + 1.0) * self.params().ore_noise_offset;
+            let noise = |w: f64| self.simplex.get([x / size, y / size, z / size, layer + w]);
+
+            let found = match shape {
+                OreShape::Blob => noise(0.0) > 1.0 - ore.richness,
+                // veins are intersections of the zero surfaces of two noises
+                OreShape::Vein => {
+                    noise(0.0).abs() + noise(self.params().ore_noise_offset * 0.5).abs()
+                        < ore.richness * self.params().ore_vein_width
+                }
+            };
+
+            if found {
+                return Some(id);
+            }
+        }
+
+        None
+    }
+
     /// Generates the voxels for a chunk.
     pub fn generate_voxels(
         &self,
@@ -398,6 +1059,19 @@ impl WorldGenerator {
 
         let offset = chunk_pos * (Chunk::SIZE * scale) as i64;
 
+        let min = offset.to_vec3() * Voxel::SCALE;
+        let max = (offset + ChunkPos::from_scalar((Chunk::SIZE_VOXELS * scale) as i64)).to_vec3()
+            * Voxel::SCALE;
+        let shapes = self
+            .get_structures_in(min, max)
+            .into_iter()
+            .flat_map(|plan| plan.shapes)
+            .filter(|shape| {
+                let (a, b) = shape.bounds();
+                a.cmple(max).all() && b.cmpge(min).all()
+            })
+            .collect::<Vec<_>>();
+
         for x in 0..Chunk::SIZE_VOXELS {
             let px = offset.x + (x * scale) as i64;
             for z in 0..Chunk::SIZE_VOXELS {
@@ -419,8 +1093,13 @@ impl WorldGenerator {
 
                     let inp = biomes.get_generate_voxel_inp(absolute_voxel_pos);
 
-                    let voxel =
-                        self.generate_voxel(inp, landscape_height, absolute_voxel_pos, scale);
+                    let voxel = self.generate_voxel(
+                        inp,
+                        landscape_height,
+                        absolute_voxel_pos,
+                        scale,
+                        &shapes,
+                    );
 
                     voxels[VoxelPos::new(x, y, z).to_index(Chunk::SIZE_VOXELS)] = voxel;
                 }
@@ -470,11 +1149,85 @@ pub struct GenCaveInp {
     pub cave_factor: f64,
     pub cave_offset: f64,
     pub cave_strength: f64,
+    /// Material of the voxels around the caves, surface layer is not replaced
+    #[lerp(f32)]
+    pub wall_id: VoxelId,
+}
+
+/// Shape of the ore deposit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OreShape {
+    /// Round clusters
+    Blob,
+    /// Long thin tubes
+    Vein,
+}
+
+/// Ore deposits of a single material in the biome
+#[derive(Debug, Default, Clone, Copy, Lerp, Reflect, FromReflect, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenOreInp {
+    /// Value between 0 and 1, 0 means no deposits of this ore
+    pub richness: f64,
+    /// Min depth under the surface in meters
+    pub min_depth: f64,
+    /// Max depth under the surface in meters
+    pub max_depth: f64,
+}
+
+impl GenOreInp {
+    pub const NONE: Self = Self {
+        richness: 0.0,
+        min_depth: 0.0,
+        max_depth: 0.0,
+    };
+
+    pub const fn new(richness: f64, min_depth: f64, max_depth: f64) -> Self {
+        Self {
+            richness,
+            min_depth,
+            max_depth,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Lerp, Reflect, FromReflect, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenOresInp {
+    pub flint: GenOreInp,
+    pub clay: GenOreInp,
+    pub copper: GenOreInp,
+    pub tin: GenOreInp,
+}
+
+impl GenOresInp {
+    pub const NONE: Self = Self {
+        flint: GenOreInp::NONE,
+        clay: GenOreInp::NONE,
+        copper: GenOreInp::NONE,
+        tin: GenOreInp::NONE,
+    };
+
+    /// Ores with the shape and the size of their deposits in meters
+    ///
+    /// Earlier ores take precedence if deposits overlap
+    fn deposits(&self) -> [(VoxelId, OreShape, f64, GenOreInp); 4] {
+        [
+            (VoxelId::COPPER_ORE, OreShape::Vein, 12.0, self.copper),
+            (VoxelId::TIN_ORE, OreShape::Vein, 10.0, self.tin),
+            (VoxelId::FLINT, OreShape::Blob, 3.0, self.flint),
+            (VoxelId::CLAY, OreShape::Blob, 8.0, self.clay),
+        ]
+    }
 }
 
 #[derive(Debug, Clone, Copy, Reflect, FromReflect, Lerp)]
 pub struct GenVoxelInp {
     pub cave_inp: GenCaveInp,
+    /// Biomes of the terrain graphs, set by [`WorldGenerator`]
+    #[lerp(f32)]
+    pub biomes: BiomeWeights,
+    pub ores: GenOresInp,
     #[lerp(f32)]
     pub first_layer_id: VoxelId,
     #[lerp(f32)]
@@ -487,8 +1240,9 @@ pub struct GenVoxelInp {
 #[test]
 fn test_avg_temp() {
     let mut sum = 0.0;
-    let mut min = WorldGenerator::MAX_TEMP;
-    let mut max = WorldGenerator::MIN_TEMP;
+    let params = GeneratorParams::default();
+    let mut min = params.max_temp;
+    let mut max = params.min_temp;
 
     let size = 32;
     let volume = size * size * size;
@@ -517,3 +1271,111 @@ fn test_avg_temp() {
     println!("min: {}", min);
     println!("max: {}", max);
 }
+
+#[test]
+fn biome_weights_are_normalized() {
+    let gen = WorldGenerator::new(123);
+    let legacy = WorldGenerator::with_settings(123, GeneratorSettings::legacy());
+
+    for i in 0..64 {
+        let pos = ChunkPos::from_index_2d(i, 8) * 50;
+
+        let weights = gen.get_biome_weights(pos);
+        assert!(!weights.is_empty() && weights.len() <= WorldGenerator::MAX_BLENDED_BIOMES);
+        assert!(weights.windows(2).all(|w| w[0].1 >= w[1].1));
+
+        let sum = weights.iter().map(|(_, weight)| weight).sum::<f64>();
+        assert!((sum - 1.0).abs() < 1e-9, "sum: {}", sum);
+
+        assert_eq!(legacy.get_biome_weights(pos).len(), 1);
+    }
+}
+
+#[test]
+fn biomes_are_ordered_by_priority() {
+    use super::internal::biomes::{PLAINS_ID, TUNDRA_ID};
+
+    let mut gen = WorldGenerator::new(123);
+
+    let order = |gen: &WorldGenerator| gen.biomes.iter().map(|b| b.get_id()).collect::<Vec<_>>();
+    assert_eq!(order(&gen).first(), Some(&TUNDRA_ID));
+    assert_eq!(order(&gen).last(), Some(&PLAINS_ID));
+
+    let plains_key = gen.get_biome_key(PLAINS_ID);
+
+    let plains =
+        br#"{ "id": "plains", "priority": 10, "layers": { "first": 0, "second": 1, "rest": 2 },
+        "cave": { "factor": 1.0, "offset": 0.0, "strength": 0.0 }, "bumps": 0.0, "height": 1.0 }"#;
+    let plains = DataBiome::from_json(plains, &ObjectsRegistry::new()).unwrap();
+    gen.register_biome(Arc::new(plains));
+
+    assert_eq!(order(&gen).first(), Some(&PLAINS_ID));
+    assert_eq!(order(&gen).len(), 3);
+    assert_eq!(gen.get_biome_key(PLAINS_ID), plains_key);
+}
+
+#[test]
+fn world_keeps_pinned_biomes() {
+    use super::internal::biomes::PLAINS_ID;
+
+    let mut settings = GeneratorSettings::current();
+    settings.biomes = BiomeSource::builtin()
+        .into_iter()
+        .filter(|source| source.file == "plains.json")
+        .collect();
+    settings.cave_biomes = DataCaveBiome::builtin_sources()
+        .into_iter()
+        .filter(|source| source.file == "mossy_caves.json")
+        .collect();
+
+    let gen = WorldGenerator::with_settings(123, settings.clone());
+    let ids = gen.biomes.iter().map(|b| b.get_id()).collect::<Vec<_>>();
+    assert_eq!(ids, vec![PLAINS_ID]);
+    let cave_ids = gen
+        .cave_biomes
+        .iter()
+        .map(|b| b.get_id())
+        .collect::<Vec<_>>();
+    assert_eq!(cave_ids, vec!["mossy_caves"]);
+    assert_eq!(gen.pinned_settings(), settings);
+
+    let legacy = WorldGenerator::with_settings(123, GeneratorSettings::legacy());
+    assert_eq!(legacy.pinned_settings().biomes, BiomeSource::builtin());
+    assert!(legacy.pinned_settings().cave_biomes.is_empty());
+    assert!(legacy
+        .get_structures_in(Vec3::splat(-1000.0), Vec3::splat(1000.0))
+        .is_empty());
+}
+
+#[test]
+fn biome_terrains_are_blended_by_weights() {
+    use super::internal::biomes::{DESERT_ID, PLAINS_ID};
+
+    let mut gen = WorldGenerator::new(123);
+
+    for (id, value) in [(PLAINS_ID, 1.0), (DESERT_ID, -1.0)] {
+        let json = format!(
+            r#"{{ "id": "{}", "layers": {{ "first": 0, "second": 1, "rest": 2 }},
+            "cave": {{ "factor": 1.0, "offset": 0.0, "strength": 0.0 }}, "bumps": 0.0, "height": 1.0,
+            "terrain": {{ "type": "constant", "value": {} }} }}"#,
+            id, value
+        );
+        let biome = DataBiome::from_json(json.as_bytes(), &ObjectsRegistry::new()).unwrap();
+        gen.register_biome(Arc::new(biome));
+    }
+
+    let plains = gen.get_biome_key(PLAINS_ID).unwrap();
+    let desert = gen.get_biome_key(DESERT_ID).unwrap();
+
+    let weights = BiomeWeights::single(plains).lerp(BiomeWeights::single(desert), 0.25);
+    assert_eq!(weights, BiomeWeights::new([(desert, 0.25), (plains, 0.75)]));
+
+    let mut inp = gen.get_generate_voxel_inp(ChunkPos::new(0, 0, 0));
+    let pos = GlobalVoxelPos::new(0, 0, 0);
+
+    inp.biomes = weights;
+    assert!((gen.generate_voxel_value(inp, 0.0, pos) - 0.5).abs() < 1e-6);
+
+    inp.biomes = BiomeWeights::single(desert);
+    assert_eq!(gen.generate_voxel_value(inp, 0.0, pos), -1.0);
+}
@@ -0,0 +1,426 @@
+use super::{settings::GeneratorParams, GenVoxelInp, WorldGenerator};
+use crate::{
+    internal::{pos::GlobalVoxelPos, voxel::Voxel},
+    plugins::world_generator::internal::biomes::BiomeID,
+};
+use bevy::prelude::*;
+use lerp::Lerp;
+use noise::NoiseFn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Index of the registered biome, used to select terrain graph of the voxel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, FromReflect)]
+pub struct BiomeKey(u32);
+
+impl BiomeKey {
+    /// Key of the unknown biome, set by [`WorldGenerator`] when the voxel input is blended
+    pub const NONE: Self = Self(u32::MAX);
+
+    pub(super) fn new(index: usize) -> Self {
+        Self(index as u32)
+    }
+
+    pub(super) fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Biomes blended at the voxel with their weights, terrain graphs of the biomes are blended by them
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, FromReflect)]
+pub struct BiomeWeights {
+    keys: [BiomeKey; Self::MAX_BIOMES],
+    /// Weights sum up to 1, unused slots have zero weight
+    weights: [f32; Self::MAX_BIOMES],
+}
+
+impl BiomeWeights {
+    /// Max number of biomes at the voxel, the lightest ones are dropped when inputs are interpolated
+    pub const MAX_BIOMES: usize = 4;
+
+    /// Without biomes, set by [`WorldGenerator`] when the voxel input is blended
+    pub const NONE: Self = Self {
+        keys: [BiomeKey::NONE; Self::MAX_BIOMES],
+        weights: [0.0; Self::MAX_BIOMES],
+    };
+
+    pub fn single(key: BiomeKey) -> Self {
+        Self::new([(key, 1.0)])
+    }
+
+    /// Keep the heaviest [`Self::MAX_BIOMES`] biomes and normalize their weights
+    pub fn new(weights: impl IntoIterator<Item = (BiomeKey, f32)>) -> Self {
+        let mut entries = [(BiomeKey::NONE, 0.0); Self::MAX_BIOMES * 2];
+        let mut len = 0;
+
+        for (key, weight) in weights {
+            if weight <= 0.0 {
+                continue;
+            }
+
+            match entries[..len].iter_mut().find(|(k, _)| *k == key) {
+                Some((_, w)) => *w += weight,
+                None if len < entries.len() => {
+                    entries[len] = (key, weight);
+                    len += 1;
+                }
+                None => {}
+            }
+        }
+
+        // key order makes the result independent of the order of the same weights
+        let entries = &mut entries[..len];
+        entries
+            .sort_unstable_by(|(a_key, a), (b_key, b)| b.total_cmp(a).then(a_key.0.cmp(&b_key.0)));
+
+        let entries = &entries[..len.min(Self::MAX_BIOMES)];
+        let sum = entries.iter().map(|(_, weight)| weight).sum::<f32>();
+
+        let mut result = Self::NONE;
+        for (i, (key, weight)) in entries.iter().enumerate() {
+            result.keys[i] = *key;
+            result.weights[i] = weight / sum;
+        }
+
+        result
+    }
+
+    /// Biomes with their weights, from the heaviest
+    pub fn iter(&self) -> impl Iterator<Item = (BiomeKey, f32)> {
+        self.keys
+            .into_iter()
+            .zip(self.weights)
+            .filter(|(_, weight)| *weight > 0.0)
+    }
+}
+
+/// Weights of the same biomes are interpolated, so the graphs of neighboring biomes are blended smoothly
+impl Lerp<f32> for BiomeWeights {
+    fn lerp(self, other: Self, pos: f32) -> Self {
+        Self::new(
+            self.iter()
+                .map(|(key, weight)| (key, weight * (1.0 - pos)))
+                .chain(other.iter().map(|(key, weight)| (key, weight * pos))),
+        )
+    }
+}
+
+/// Values of the generation pipeline available to the graph nodes
+pub struct NoiseContext<'a> {
+    pub gen: &'a WorldGenerator,
+    pub inp: &'a GenVoxelInp,
+    /// Biome the graph is evaluated for, one of the biomes blended at the voxel
+    pub biome: BiomeKey,
+    /// Height of the landscape in meters above the voxel column
+    pub landscape_height: f64,
+    pub voxel_pos: GlobalVoxelPos,
+}
+
+impl<'a> NoiseContext<'a> {
+    pub fn biome(&self) -> Option<BiomeID> {
+        self.gen.get_biome_id(self.biome)
+    }
+}
+
+/// Node of the terrain noise graph.
+///
+/// Graph is evaluated for each voxel at its position in meters, positive values are solid.
+/// Graphs can be defined in code or loaded from json, e.g. `{ "type": "constant", "value": 1.0 }`.
+///
+/// Inputs of the pipeline ([`NoiseNode::Landscape`], [`NoiseNode::BumpsFactor`], [`NoiseNode::Caves`])
+/// are not affected by [`NoiseNode::Scale`] and [`NoiseNode::Warp`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NoiseNode {
+    Constant {
+        value: f64,
+    },
+    /// Coordinates of the position in meters
+    X,
+    Y,
+    Z,
+    /// Simplex noise of the world seed between -1 and 1, each `layer` is an independent noise
+    Simplex {
+        #[serde(default)]
+        layer: Option<f64>,
+    },
+    /// Perlin noise of the world seed between -1 and 1, each `layer` is an independent noise
+    Perlin {
+        #[serde(default)]
+        layer: Option<f64>,
+    },
+    /// Height of the landscape of the blended biomes in meters
+    Landscape,
+    /// Bumps factor of the blended biomes
+    BumpsFactor,
+    /// Value carved by the caves of the blended biomes, positive inside of the caves
+    Caves,
+    /// Multiply the position by `scale` before evaluating `source`
+    Scale {
+        source: Box<NoiseNode>,
+        scale: [f64; 3],
+    },
+    Add {
+        sources: Vec<NoiseNode>,
+    },
+    Multiply {
+        sources: Vec<NoiseNode>,
+    },
+    Negate {
+        source: Box<NoiseNode>,
+    },
+    Clamp {
+        source: Box<NoiseNode>,
+        min: f64,
+        max: f64,
+    },
+    /// Piecewise linear remap, `points` are `[input, output]` pairs sorted by input.
+    ///
+    /// Values outside of the points are clamped to the first and the last output.
+    Curve {
+        source: Box<NoiseNode>,
+        points: Vec<[f64; 2]>,
+    },
+    /// Bound value to (-1, 1), see [`WorldGenerator::normalize_value`]
+    Normalize {
+        source: Box<NoiseNode>,
+    },
+    /// Offset the position by values of the `x`, `y` and `z` nodes before evaluating `source`
+    Warp {
+        source: Box<NoiseNode>,
+        #[serde(default)]
+        x: Option<Box<NoiseNode>>,
+        #[serde(default)]
+        y: Option<Box<NoiseNode>>,
+        #[serde(default)]
+        z: Option<Box<NoiseNode>>,
+    },
+    /// Use the graph of the biome, `default` for the rest of biomes.
+    ///
+    /// Graph is evaluated for each biome blended at the voxel and the values are blended by the biome weights.
+    SelectByBiome {
+        biomes: HashMap<String, NoiseNode>,
+        default: Box<NoiseNode>,
+    },
+}
+
+impl NoiseNode {
+    pub fn constant(value: f64) -> Self {
+        Self::Constant { value }
+    }
+
+    pub fn scale(self, scale: [f64; 3]) -> Self {
+        Self::Scale {
+            source: Box::new(self),
+            scale,
+        }
+    }
+
+    pub fn negate(self) -> Self {
+        Self::Negate {
+            source: Box::new(self),
+        }
+    }
+
+    pub fn normalize(self) -> Self {
+        Self::Normalize {
+            source: Box::new(self),
+        }
+    }
+
+    pub fn clamp(self, min: f64, max: f64) -> Self {
+        Self::Clamp {
+            source: Box::new(self),
+            min,
+            max,
+        }
+    }
+
+    pub fn curve(self, points: Vec<[f64; 2]>) -> Self {
+        Self::Curve {
+            source: Box::new(self),
+            points,
+        }
+    }
+
+    /// Terrain of the generator before the graphs were introduced:
+    /// landscape with bumps bounded by [`WorldGenerator::normalize_value`] and carved by caves
+    pub fn default_terrain(params: &GeneratorParams) -> Self {
+        let bumps_scale = 1.0 / params.scale;
+
+        let bumps = Self::Multiply {
+            sources: vec![
+                Self::BumpsFactor,
+                Self::Simplex { layer: None }.scale([bumps_scale; 3]),
+            ],
+        };
+
+        Self::Add {
+            sources: vec![
+                Self::Add {
+                    sources: vec![
+                        Self::Add {
+                            sources: vec![Self::Landscape, Self::Y.negate()],
+                        },
+                        bumps,
+                    ],
+                }
+                .normalize(),
+                Self::Caves.negate(),
+            ],
+        }
+    }
+
+    /// Value of the graph depends on the biome it is evaluated for
+    pub fn selects_by_biome(&self) -> bool {
+        match self {
+            Self::SelectByBiome { .. } => true,
+            Self::Scale { source, .. }
+            | Self::Negate { source }
+            | Self::Clamp { source, .. }
+            | Self::Curve { source, .. }
+            | Self::Normalize { source } => source.selects_by_biome(),
+            Self::Add { sources } | Self::Multiply { sources } => {
+                sources.iter().any(Self::selects_by_biome)
+            }
+            Self::Warp { source, x, y, z } => {
+                source.selects_by_biome()
+                    || [x, y, z]
+                        .into_iter()
+                        .flatten()
+                        .any(|node| node.selects_by_biome())
+            }
+            Self::Constant { .. }
+            | Self::X
+            | Self::Y
+            | Self::Z
+            | Self::Simplex { .. }
+            | Self::Perlin { .. }
+            | Self::Landscape
+            | Self::BumpsFactor
+            | Self::Caves => false,
+        }
+    }
+
+    /// Position of the voxel in meters
+    pub fn voxel_pos_to_meters(pos: GlobalVoxelPos) -> [f64; 3] {
+        let pos_vec = pos.to_vec3();
+
+        [
+            pos_vec.x as f64 * Voxel::SCALE as f64,
+            pos_vec.y as f64 * Voxel::SCALE as f64,
+            pos_vec.z as f64 * Voxel::SCALE as f64,
+        ]
+    }
+
+    pub fn eval(&self, ctx: &NoiseContext, pos: [f64; 3]) -> f64 {
+        let [x, y, z] = pos;
+
+        match self {
+            Self::Constant { value } => *value,
+            Self::X => x,
+            Self::Y => y,
+            Self::Z => z,
+            Self::Simplex { layer: None } => ctx.gen.simplex.get(pos),
+            Self::Simplex { layer: Some(w) } => ctx.gen.simplex.get([x, y, z, *w]),
+            Self::Perlin { layer: None } => ctx.gen.perlin.get(pos),
+            Self::Perlin { layer: Some(w) } => ctx.gen.perlin.get([x, y, z, *w]),
+            Self::Landscape => ctx.landscape_height,
+            Self::BumpsFactor => ctx.inp.bumps_factor,
+            Self::Caves => ctx.gen.get_caves(ctx.inp.cave_inp, ctx.voxel_pos),
+            Self::Scale { source, scale } => {
+                source.eval(ctx, [x * scale[0], y * scale[1], z * scale[2]])
+            }
+            // fold from the first value, so the single source is returned as is
+            Self::Add { sources } => sources
+                .iter()
+                .map(|source| source.eval(ctx, pos))
+                .reduce(|a, b| a + b)
+                .unwrap_or(0.0),
+            Self::Multiply { sources } => sources
+                .iter()
+                .map(|source| source.eval(ctx, pos))
+                .reduce(|a, b| a * b)
+                .unwrap_or(1.0),
+            Self::Negate { source } => -source.eval(ctx, pos),
+            Self::Clamp { source, min, max } => source.eval(ctx, pos).clamp(*min, *max),
+            Self::Curve { source, points } => Self::eval_curve(points, source.eval(ctx, pos)),
+            Self::Normalize { source } => WorldGenerator::normalize_value(source.eval(ctx, pos)),
+            Self::Warp {
+                source,
+                x: wx,
+                y: wy,
+                z: wz,
+            } => {
+                let offset = |node: &Option<Box<NoiseNode>>| {
+                    node.as_ref().map_or(0.0, |node| node.eval(ctx, pos))
+                };
+
+                source.eval(ctx, [x + offset(wx), y + offset(wy), z + offset(wz)])
+            }
+            Self::SelectByBiome { biomes, default } => ctx
+                .biome()
+                .and_then(|id| biomes.get(id))
+                .unwrap_or(default)
+                .eval(ctx, pos),
+        }
+    }
+
+    fn eval_curve(points: &[[f64; 2]], value: f64) -> f64 {
+        let (first, last) = match (points.first(), points.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return value,
+        };
+
+        if value <= first[0] {
+            return first[1];
+        }
+
+        points
+            .windows(2)
+            .find(|w| value <= w[1][0])
+            .map_or(last[1], |w| {
+                let [x0, y0] = w[0];
+                let [x1, y1] = w[1];
+                y0 + (y1 - y0) * (value - x0) / (x1 - x0)
+            })
+    }
+}
+
+#[test]
+fn noise_graph_from_json() {
+    use crate::{internal::pos::ChunkPos, plugins::world_generator::internal::biomes::DESERT_ID};
+
+    let graph: NoiseNode = serde_json::from_str(
+        r#"{ "type": "clamp", "min": -1.0, "max": 1.0, "source": {
+            "type": "add", "sources": [
+                { "type": "curve", "points": [[0.0, 0.0], [10.0, 2.0]], "source": { "type": "y" } },
+                { "type": "select_by_biome", "default": { "type": "constant", "value": -0.5 },
+                    "biomes": { "desert": { "type": "landscape" } } }
+            ]
+        } }"#,
+    )
+    .unwrap();
+
+    let gen = WorldGenerator::new(123);
+    let inp = gen.get_generate_voxel_inp(ChunkPos::new(0, 0, 0));
+
+    let eval = |biome: BiomeKey, y: f64| {
+        let ctx = NoiseContext {
+            gen: &gen,
+            inp: &inp,
+            biome,
+            landscape_height: -0.25,
+            voxel_pos: GlobalVoxelPos::new(0, 0, 0),
+        };
+        graph.eval(&ctx, [0.0, y, 0.0])
+    };
+
+    assert_eq!(eval(BiomeKey::NONE, -5.0), -0.5);
+    assert_eq!(eval(BiomeKey::NONE, 5.0), 0.5);
+    assert_eq!(eval(BiomeKey::NONE, 100.0), 1.0);
+
+    let desert = gen.get_biome_key(DESERT_ID).unwrap();
+    assert_eq!(eval(desert, 5.0), 0.75);
+    assert!(graph.selects_by_biome());
+}
@@ -0,0 +1,132 @@
+use super::WorldSeed;
+use crate::plugins::world_generator::internal::{biomes::data::BiomeSource, random::GenRng};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub type GeneratorVersion = u32;
+
+/// Constants of the terrain generation
+///
+/// Saved with the world, so changing the defaults doesn't affect existing worlds.
+#[derive(Debug, Clone, PartialEq, Reflect, FromReflect, Serialize, Deserialize)]
+pub struct GeneratorParams {
+    pub landscape_octaves: u32,
+    /// Inverse scale of the surface bumps noise
+    pub scale: f64,
+    pub landscape_scale: f64,
+    pub cave_scale: f64,
+    pub cave_y_scale: f64,
+    pub color_random_scale: f64,
+    pub temp_noise_scale: f64,
+    pub humidity_noise_scale: f64,
+    pub mountainousness_noise_scale: f64,
+    /// Min temperature in celsius
+    pub min_temp: f64,
+    /// Max temperature in celsius
+    pub max_temp: f64,
+    /// Depth of the rest layers under the surface in meters
+    pub stone_depth: f64,
+    pub ore_noise_offset: f64,
+    pub ore_vein_width: f64,
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        Self {
+            landscape_octaves: 4,
+            scale: 0.045,
+            landscape_scale: 0.01,
+            cave_scale: 1.0 / 50.0,
+            cave_y_scale: 4.0,
+            color_random_scale: 0.1,
+            temp_noise_scale: 0.01,
+            humidity_noise_scale: 0.01,
+            mountainousness_noise_scale: 0.01,
+            min_temp: -70.0,
+            max_temp: 100.0,
+            stone_depth: 32.0,
+            ore_noise_offset: 100.0,
+            ore_vein_width: 0.2,
+        }
+    }
+}
+
+/// Version and parameters of the generator that created the world
+#[derive(Debug, Clone, PartialEq, Reflect, FromReflect, Serialize, Deserialize)]
+pub struct GeneratorSettings {
+    pub version: GeneratorVersion,
+    pub params: GeneratorParams,
+    /// Biome definitions of the world, empty until the world is created.
+    ///
+    /// Worlds without them use the built-in biomes before [`GeneratorSettings::V2`]
+    /// and the biomes loaded from the assets since then.
+    #[reflect(ignore)]
+    pub biomes: Vec<BiomeSource>,
+    /// Cave biome definitions of the world, pinned like [`GeneratorSettings::biomes`].
+    ///
+    /// Worlds before [`GeneratorSettings::V4`] have no cave biomes.
+    #[reflect(ignore)]
+    pub cave_biomes: Vec<BiomeSource>,
+    /// JSON of the terrain graph of the biomes without their own graph,
+    /// see [`WorldGenerator::set_terrain`](super::WorldGenerator::set_terrain).
+    /// Default terrain of the parameters is used if `None`
+    #[reflect(ignore)]
+    pub terrain: Option<String>,
+}
+
+impl GeneratorSettings {
+    /// Noise is seeded with the lower 32 bits of the world seed, worlds created before
+    /// 64-bit seeds always use this version
+    pub const V1: GeneratorVersion = 1;
+    /// Noise is seeded with the hash of the full 64-bit world seed.
+    ///
+    /// Also the first version with ore deposits, structures and objects placed by [`GenRng`],
+    /// older worlds were generated without them.
+    pub const V2: GeneratorVersion = 2;
+    /// Neighboring biomes are blended by their suitability instead of using the first matching one
+    pub const V3: GeneratorVersion = 3;
+    /// Underground cave biomes with their own caves, wall materials and objects
+    pub const V4: GeneratorVersion = 4;
+
+    pub const CURRENT_VERSION: GeneratorVersion = Self::V4;
+
+    /// Settings for the newly created worlds
+    pub fn current() -> Self {
+        Self {
+            version: Self::CURRENT_VERSION,
+            params: GeneratorParams::default(),
+            biomes: Vec::new(),
+            cave_biomes: Vec::new(),
+            terrain: None,
+        }
+    }
+
+    /// Settings of the worlds saved before the generator version was recorded
+    pub fn legacy() -> Self {
+        Self {
+            version: Self::V1,
+            params: GeneratorParams::default(),
+            biomes: Vec::new(),
+            cave_biomes: Vec::new(),
+            terrain: None,
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        (Self::V1..=Self::CURRENT_VERSION).contains(&self.version)
+    }
+
+    /// Seed of the noise functions, they only support 32-bit seeds
+    pub fn noise_seed(&self, seed: WorldSeed) -> u32 {
+        match self.version {
+            Self::V1 => seed as u32,
+            _ => (GenRng::new(seed).next_u64() >> 32) as u32,
+        }
+    }
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        Self::current()
+    }
+}
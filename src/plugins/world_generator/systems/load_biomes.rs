@@ -0,0 +1,14 @@
+use crate::plugins::{
+    objects::resources::objects_registry::ObjectsRegistry,
+    world_generator::resources::WorldGenerator,
+};
+use bevy::prelude::*;
+
+pub fn load_biomes_system(mut gen: ResMut<WorldGenerator>, registry: Res<ObjectsRegistry>) {
+    let dir = WorldGenerator::biomes_dir();
+
+    match gen.load_biomes(&dir, &registry) {
+        Ok(count) => info!("{} biomes loaded from {}", count, dir.display()),
+        Err(err) => error!("Failed to load biomes, using built-in ones: {}", err),
+    }
+}
@@ -0,0 +1,417 @@
+//! Offline tool to inspect saved worlds, pregenerate their chunks and render seed maps without opening a window.
+
+use pariter::IteratorExt;
+use primitive_engineering::{
+    internal::{
+        chunks::{pointer::ChunkPointer, Chunk},
+        pos::{ChunkPos, VoxelPos},
+        voxel::Voxel,
+    },
+    plugins::{
+        game_world::{
+            resources::{meta::GameWorldMeta, storage::GameWorldStorage, GameWorld},
+            utils::{export::WorldExport, storage::FsStorage},
+        },
+        objects::resources::objects_registry::ObjectsRegistry,
+        world_generator::{
+            internal::biomes::ChunkBiomes,
+            resources::{
+                map_preview::MapMode, settings::GeneratorSettings, WorldGenerator, WorldSeed,
+            },
+        },
+    },
+};
+use serde::Serialize;
+use std::{env, error::Error, fs, io::BufWriter, path::Path, process};
+
+const USAGE: &str = "Usage: save-tool [--saves <dir>] <command>
+
+Commands:
+    list                                     list saved worlds
+    stats <world>                            print chunk counts and sizes of each region
+    dump-chunk <world> <x> <y> <z> <level> [--full]
+                                             print saved voxels of the chunk as json, only
+                                             modified ones unless the chunk is baked, with
+                                             --full all voxels are printed with generated ones
+    dump-objects <world> <x> <y> <z>         print objects of the region as json
+    validate <world>                         check that every file of the world can be loaded
+    pregen <world> <radius> [max level]      generate and save chunks of regions around spawn
+                                             at the levels that are loaded from the save
+    export <world> <x1> <y1> <z1> <x2> <y2> <z2> <level> <file> [--objects]
+                                             export box of chunks at the level to .glb or .obj
+    map <seed> <mode> <file> [size] [meters per pixel]
+                                             render map around spawn to .png, modes are
+                                             biome, temperature, humidity, elevation and height
+
+<world> is the id or the name of the world";
+
+/// Lowest level of pregenerated chunks, chunks at level 0 are always generated by the game
+const PREGEN_MIN_LEVEL: usize = 1;
+/// Default max detail level of pregenerated chunks, each level has 8 times more chunks
+const PREGEN_MAX_LEVEL: usize = 2;
+
+/// Default size of the rendered map in pixels
+const MAP_SIZE: usize = 512;
+/// Default area covered by one pixel of the rendered map
+const MAP_METERS_PER_PIXEL: f32 = 4.0;
+
+type CmdResult = Result<(), Box<dyn Error>>;
+
+#[derive(Serialize)]
+struct VoxelDump {
+    pos: (usize, usize, usize),
+    voxel: Voxel,
+}
+
+#[derive(Serialize)]
+struct ChunkDump {
+    baked: bool,
+    voxels: Vec<VoxelDump>,
+}
+
+fn find_world(storage: &GameWorldStorage, world: &str) -> Result<GameWorldMeta, Box<dyn Error>> {
+    GameWorldMeta::get_saves(storage)?
+        .into_iter()
+        .find(|meta| meta.id == world || meta.name == world)
+        .ok_or_else(|| format!("world {} not found", world).into())
+}
+
+/// Create world generator with biomes loaded from the assets, same as in the game
+fn create_generator(
+    seed: WorldSeed,
+    settings: GeneratorSettings,
+    registry: &ObjectsRegistry,
+) -> Result<WorldGenerator, Box<dyn Error>> {
+    if !settings.is_supported() {
+        return Err(format!("unsupported generator version {}", settings.version).into());
+    }
+
+    let mut gen = WorldGenerator::with_settings(seed, settings);
+    gen.load_biomes(&WorldGenerator::biomes_dir(), registry)?;
+
+    Ok(gen)
+}
+
+fn parse_pos(x: &str, y: &str, z: &str) -> Result<ChunkPos, Box<dyn Error>> {
+    Ok(ChunkPos::new(x.parse()?, y.parse()?, z.parse()?))
+}
+
+fn parse_pregen_args(radius: &str, max_level: &str) -> Result<(i64, usize), Box<dyn Error>> {
+    Ok((radius.parse()?, max_level.parse()?))
+}
+
+fn parse_map_args(size: &str, meters_per_pixel: &str) -> Result<(usize, f32), Box<dyn Error>> {
+    Ok((size.parse()?, meters_per_pixel.parse()?))
+}
+
+fn list(storage: &GameWorldStorage) -> CmdResult {
+    for meta in GameWorldMeta::get_saves(storage)? {
+        println!(
+            "{}\t{}\tseed: {}\tgenerator: v{}{}",
+            meta.id,
+            meta.name,
+            meta.seed,
+            meta.generator.version,
+            if meta.needs_upgrade {
+                "\tneeds upgrade"
+            } else {
+                ""
+            }
+        );
+    }
+
+    Ok(())
+}
+
+fn stats(storage: &GameWorldStorage, world: &str) -> CmdResult {
+    let meta = find_world(storage, world)?;
+    let stats = meta.get_region_stats(storage)?;
+
+    let mut total = vec![(0, 0); GameWorld::MAX_DETAIL_LEVEL + 1];
+
+    for region in stats.iter() {
+        println!(
+            "region {:?}: {} bytes ({} unused)",
+            region.region_pos, region.file_size, region.free_size
+        );
+
+        for (level, level_stats) in region.levels.iter().enumerate() {
+            if level_stats.chunks == 0 {
+                continue;
+            }
+
+            println!(
+                "    level {}: {} chunks, {} bytes",
+                level, level_stats.chunks, level_stats.size
+            );
+
+            if level >= total.len() {
+                total.resize(level + 1, (0, 0));
+            }
+            total[level].0 += level_stats.chunks;
+            total[level].1 += level_stats.size;
+        }
+    }
+
+    println!("total: {} regions", stats.len());
+    for (level, (chunks, size)) in total.into_iter().enumerate() {
+        println!("    level {}: {} chunks, {} bytes", level, chunks, size);
+    }
+
+    Ok(())
+}
+
+fn dump_chunk(
+    storage: &GameWorldStorage,
+    world: &str,
+    pos: ChunkPos,
+    level: usize,
+    full: bool,
+) -> CmdResult {
+    let meta = find_world(storage, world)?;
+
+    let saved = meta
+        .load_chunk(storage, pos, level)?
+        .ok_or_else(|| format!("chunk {:?}-{} is not saved", pos, level))?;
+
+    let baked = saved.is_baked();
+
+    let voxels: Vec<(VoxelPos, Voxel)> = if full {
+        let gen = create_generator(meta.seed, meta.generator.clone(), &ObjectsRegistry::new())?;
+        let biomes = ChunkBiomes::new(&gen, GameWorld::level_pos_to_level_pos(pos, level, 0));
+        let chunk = saved.into_chunk(&gen, biomes, pos, level);
+
+        (0..Chunk::VOLUME_VOXELS)
+            .map(|i| VoxelPos::from_index(i, Chunk::SIZE_VOXELS))
+            .filter_map(|pos| Some((pos, chunk.get_voxel_at(pos)?)))
+            .collect()
+    } else {
+        saved.iter().collect()
+    };
+
+    let dump = ChunkDump {
+        baked,
+        voxels: voxels
+            .into_iter()
+            .map(|(pos, voxel)| VoxelDump {
+                pos: (pos.x, pos.y, pos.z),
+                voxel,
+            })
+            .collect(),
+    };
+
+    println!("{}", serde_json::to_string_pretty(&dump)?);
+
+    Ok(())
+}
+
+fn dump_objects(storage: &GameWorldStorage, world: &str, region_pos: ChunkPos) -> CmdResult {
+    let meta = find_world(storage, world)?;
+
+    let objects = meta
+        .load_objects(storage, region_pos)?
+        .ok_or_else(|| format!("objects of region {:?} are not saved", region_pos))?;
+
+    println!("{}", serde_json::to_string_pretty(&objects)?);
+
+    Ok(())
+}
+
+fn validate(storage: &GameWorldStorage, world: &str) -> CmdResult {
+    let meta = find_world(storage, world)?;
+    let errors = meta.validate(storage)?;
+
+    for (key, err) in errors.iter() {
+        println!("{}: {}", key, err);
+    }
+
+    if !errors.is_empty() {
+        return Err(format!("{} corrupted entries found", errors.len()).into());
+    }
+
+    println!("world {} is valid", meta.id);
+
+    Ok(())
+}
+
+fn pregen(storage: &GameWorldStorage, world: &str, radius: i64, max_level: usize) -> CmdResult {
+    let meta = find_world(storage, world)?;
+    let gen = create_generator(meta.seed, meta.generator.clone(), &ObjectsRegistry::new())?;
+
+    if !(PREGEN_MIN_LEVEL..=GameWorld::MAX_DETAIL_LEVEL).contains(&max_level) {
+        return Err(format!(
+            "max level should be in {}..={}",
+            PREGEN_MIN_LEVEL,
+            GameWorld::MAX_DETAIL_LEVEL
+        )
+        .into());
+    }
+
+    let mut saved = 0;
+
+    for x in -radius..=radius {
+        for y in -radius..=radius {
+            for z in -radius..=radius {
+                let start = std::time::Instant::now();
+
+                let region_pos = ChunkPos::new(x, y, z);
+                let biomes = ChunkBiomes::new(&gen, region_pos);
+
+                let chunks = (PREGEN_MIN_LEVEL..=max_level)
+                    .flat_map(|level| {
+                        let size = 1 << level;
+                        let offset = region_pos * size as i64;
+
+                        (0..size * size * size)
+                            .map(move |i| (ChunkPos::from_index(i, size) + offset, level))
+                    })
+                    .map(|(pos, level)| (pos, level, gen.clone(), biomes.clone()))
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .parallel_map(|(pos, level, gen, biomes)| {
+                        ChunkPointer::new(Chunk::generate(&gen, biomes, pos, level), pos, level)
+                    })
+                    .collect::<Vec<_>>();
+
+                let count = meta.save_baked_chunks(storage, chunks)?;
+                saved += count;
+
+                println!(
+                    "region {:?}: {} chunks in {}ms",
+                    region_pos,
+                    count,
+                    start.elapsed().as_millis()
+                );
+            }
+        }
+    }
+
+    println!("{} chunks saved", saved);
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn export(
+    storage: &GameWorldStorage,
+    world: &str,
+    from: ChunkPos,
+    to: ChunkPos,
+    level: usize,
+    file: &str,
+    with_objects: bool,
+) -> CmdResult {
+    let meta = find_world(storage, world)?;
+    let registry = ObjectsRegistry::new();
+    let gen = create_generator(meta.seed, meta.generator.clone(), &registry)?;
+
+    if level > GameWorld::MAX_DETAIL_LEVEL {
+        return Err(format!("level should be <= {}", GameWorld::MAX_DETAIL_LEVEL).into());
+    }
+
+    let extension = Path::new(file).extension().and_then(|ext| ext.to_str());
+    if !matches!(extension, Some("glb") | Some("obj")) {
+        return Err("output file should have .glb or .obj extension".into());
+    }
+
+    let registry = if with_objects { Some(&registry) } else { None };
+
+    let export = WorldExport::collect(&meta, storage, &gen, registry, from, to, level)?;
+
+    let out = BufWriter::new(fs::File::create(file)?);
+    match extension {
+        Some("glb") => export.write_glb(out)?,
+        _ => export.write_obj(out)?,
+    }
+
+    println!(
+        "{} chunks and {} objects exported to {}",
+        export.chunks.len(),
+        export.objects.len(),
+        file
+    );
+
+    Ok(())
+}
+
+fn map(seed: &str, mode: &str, file: &str, size: usize, meters_per_pixel: f32) -> CmdResult {
+    let mode: MapMode = mode.parse()?;
+    let gen = create_generator(
+        WorldGenerator::seed_from_str(seed),
+        GeneratorSettings::current(),
+        &ObjectsRegistry::new(),
+    )?;
+
+    if !file.ends_with(".png") {
+        return Err("output file should have .png extension".into());
+    }
+
+    gen.render_map(mode, size, meters_per_pixel)
+        .save_png(Path::new(file))?;
+
+    println!("{} map of seed {} saved to {}", mode, gen.seed(), file);
+
+    Ok(())
+}
+
+fn main() {
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+
+    let storage = if args.first().map(String::as_str) == Some("--saves") && args.len() > 1 {
+        let dir = args[1].clone();
+        args.drain(..2);
+        GameWorldStorage::new(FsStorage::new(dir))
+    } else {
+        GameWorldStorage::default()
+    };
+
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let result = match args.as_slice() {
+        ["list"] => list(&storage),
+        ["stats", world] => stats(&storage, world),
+        ["dump-chunk", world, x, y, z, level, rest @ ..]
+            if rest.is_empty() || rest == ["--full"] =>
+        {
+            parse_pos(x, y, z).and_then(|pos| {
+                let level = level.parse()?;
+                dump_chunk(&storage, world, pos, level, !rest.is_empty())
+            })
+        }
+        ["dump-objects", world, x, y, z] => {
+            parse_pos(x, y, z).and_then(|pos| dump_objects(&storage, world, pos))
+        }
+        ["validate", world] => validate(&storage, world),
+        ["pregen", world, radius] => radius
+            .parse()
+            .map_err(Into::into)
+            .and_then(|radius| pregen(&storage, world, radius, PREGEN_MAX_LEVEL)),
+        ["pregen", world, radius, max_level] => parse_pregen_args(radius, max_level)
+            .and_then(|(radius, max_level)| pregen(&storage, world, radius, max_level)),
+        ["export", world, x1, y1, z1, x2, y2, z2, level, file, rest @ ..]
+            if rest.is_empty() || rest == ["--objects"] =>
+        {
+            parse_pos(x1, y1, z1).and_then(|from| {
+                let to = parse_pos(x2, y2, z2)?;
+                let level = level.parse()?;
+                export(&storage, world, from, to, level, file, !rest.is_empty())
+            })
+        }
+        ["map", seed, mode, file] => map(seed, mode, file, MAP_SIZE, MAP_METERS_PER_PIXEL),
+        ["map", seed, mode, file, size] => size
+            .parse()
+            .map_err(Into::into)
+            .and_then(|size| map(seed, mode, file, size, MAP_METERS_PER_PIXEL)),
+        ["map", seed, mode, file, size, meters_per_pixel] => parse_map_args(size, meters_per_pixel)
+            .and_then(|(size, meters_per_pixel)| map(seed, mode, file, size, meters_per_pixel)),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}